@@ -23,6 +23,7 @@ use std::cell::Cell;
 use std::os::raw::c_int;
 use std::time::{Duration, Instant};
 
+pub mod retention;
 pub mod testing;
 
 include!(concat!(env!("OUT_DIR"), "/aeron.rs"));
@@ -90,50 +91,16 @@ impl RecordingPos {
         counters_reader: &AeronCountersReader,
         counter_id: i32,
     ) -> Result<i64, AeronCError> {
-        /// The type id for an Aeron Archive recording position counter.
-        /// In Aeron Java, this is AeronCounters.ARCHIVE_RECORDING_POSITION_TYPE_ID (which is typically 100).
-        pub const RECORDING_POSITION_TYPE_ID: i32 = 100;
-
-        /// from Aeron Java code
-        pub const RECORD_ALLOCATED: i32 = 1;
-
         /// A constant to mean "no valid recording ID".
         pub const NULL_RECORDING_ID: i64 = -1;
 
-        if counter_id < 0 {
-            return Err(AeronCError::from_code(NULL_RECORDING_ID as i32));
-        }
-
-        let state = counters_reader.counter_state(counter_id)?;
-        if state != RECORD_ALLOCATED {
-            return Err(AeronCError::from_code(NULL_RECORDING_ID as i32));
-        }
-
-        let type_id = counters_reader.counter_type_id(counter_id)?;
-        if type_id != RECORDING_POSITION_TYPE_ID {
-            return Err(AeronCError::from_code(NULL_RECORDING_ID as i32));
-        }
-
-        // Read the key area. For a RECORDING_POSITION_TYPE_ID counter:
-        //    - offset 0..8 => the i64 recording_id
-        //    - offset 8..12 => the session_id (int)
-        //    etc...
-        // only need the first 8 bytes to get the recordingId.
-        let recording_id = Cell::new(-1);
-        counters_reader.foreach_counter_once(|value, id, type_id, key, label| {
-            if id == counter_id && type_id == RECORDING_POSITION_TYPE_ID {
-                let mut val = [0u8; 8];
-                val.copy_from_slice(&key[0..8]);
-                let Ok(value) = i64::from_le_bytes(val).try_into();
-                recording_id.set(value);
-            }
-        });
-        let recording_id = recording_id.get();
-        if recording_id < 0 {
-            return Err(AeronCError::from_code(NULL_RECORDING_ID as i32));
-        }
-
-        Ok(recording_id)
+        counters_reader
+            .counter_info(counter_id)
+            .filter(|info| info.is_allocated())
+            .and_then(|info| info.recording_position_key())
+            .map(|key| key.recording_id)
+            .filter(|recording_id| *recording_id >= 0)
+            .ok_or_else(|| AeronCError::from_code(NULL_RECORDING_ID as i32))
     }
 }
 
@@ -148,6 +115,129 @@ unsafe extern "C" fn default_encoded_credentials(
     Box::into_raw(empty_credentials)
 }
 
+/// Supplies the credentials sent to the archive's authenticator when connecting,
+/// see [`AeronArchiveContext::set_credentials`].
+pub trait CredentialsSupplier {
+    /// Encoded credentials to send with the connect request.
+    fn encoded_credentials(&mut self) -> Vec<u8>;
+
+    /// Encoded credentials to send in response to a challenge from the authenticator.
+    fn on_challenge(&mut self, encoded_challenge: &[u8]) -> Vec<u8> {
+        let _ = encoded_challenge;
+        vec![]
+    }
+
+    /// Called once the archive client no longer needs credentials previously returned by
+    /// [`CredentialsSupplier::encoded_credentials`] or [`CredentialsSupplier::on_challenge`],
+    /// e.g. to zero them. The buffer is dropped afterwards.
+    fn on_free(&mut self, credentials: &mut [u8]) {
+        let _ = credentials;
+    }
+}
+
+/// Sends `username:password` encoded as UTF-8, for both the connect request and any challenge.
+/// Credentials are zeroed once the archive client frees them.
+#[derive(Clone, PartialEq, Eq)]
+pub struct UsernamePasswordCredentialsSupplier {
+    username: String,
+    password: String,
+}
+
+impl std::fmt::Debug for UsernamePasswordCredentialsSupplier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UsernamePasswordCredentialsSupplier")
+            .field("username", &self.username)
+            .finish_non_exhaustive()
+    }
+}
+
+impl UsernamePasswordCredentialsSupplier {
+    pub fn new(username: &str, password: &str) -> Self {
+        Self {
+            username: username.to_string(),
+            password: password.to_string(),
+        }
+    }
+
+    #[inline]
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        format!("{}:{}", self.username, self.password).into_bytes()
+    }
+}
+
+impl CredentialsSupplier for UsernamePasswordCredentialsSupplier {
+    fn encoded_credentials(&mut self) -> Vec<u8> {
+        self.encode()
+    }
+
+    fn on_challenge(&mut self, _encoded_challenge: &[u8]) -> Vec<u8> {
+        self.encode()
+    }
+
+    fn on_free(&mut self, credentials: &mut [u8]) {
+        credentials.fill(0);
+    }
+}
+
+/// clientd shared by the supplier callbacks, owns the user's [`CredentialsSupplier`].
+struct CredentialsSupplierHandler<S: CredentialsSupplier>(S);
+
+/// Hands ownership of `credentials` to the archive client, reclaimed in the free callback.
+fn into_encoded_credentials(credentials: Vec<u8>) -> *mut aeron_archive_encoded_credentials_t {
+    let data = Box::into_raw(credentials.into_boxed_slice());
+    Box::into_raw(Box::new(aeron_archive_encoded_credentials_t {
+        length: data.len() as u32,
+        data: data as *const u8 as *const std::os::raw::c_char,
+    }))
+}
+
+unsafe extern "C" fn credentials_supplier_encoded_credentials<S: CredentialsSupplier>(
+    clientd: *mut std::os::raw::c_void,
+) -> *mut aeron_archive_encoded_credentials_t {
+    let handler = &mut *(clientd as *mut CredentialsSupplierHandler<S>);
+    into_encoded_credentials(handler.0.encoded_credentials())
+}
+
+unsafe extern "C" fn credentials_supplier_on_challenge<S: CredentialsSupplier>(
+    encoded_challenge: *mut aeron_archive_encoded_credentials_t,
+    clientd: *mut std::os::raw::c_void,
+) -> *mut aeron_archive_encoded_credentials_t {
+    let handler = &mut *(clientd as *mut CredentialsSupplierHandler<S>);
+    let challenge = match encoded_challenge.as_ref() {
+        Some(c) if !c.data.is_null() => {
+            std::slice::from_raw_parts(c.data as *const u8, c.length as usize)
+        }
+        _ => &[],
+    };
+    into_encoded_credentials(handler.0.on_challenge(challenge))
+}
+
+impl<S: CredentialsSupplier> AeronArchiveCredentialsFreeFuncCallback
+    for CredentialsSupplierHandler<S>
+{
+    fn handle_aeron_archive_credentials_free_func(
+        &mut self,
+        credentials: AeronArchiveEncodedCredentials,
+    ) -> () {
+        let credentials = credentials.get_inner();
+        if credentials.is_null() {
+            return;
+        }
+        unsafe {
+            let credentials = Box::from_raw(credentials);
+            let mut data = Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+                credentials.data as *mut u8,
+                credentials.length as usize,
+            ));
+            self.0.on_free(&mut data);
+        }
+    }
+}
+
 impl AeronArchive {
     pub fn aeron(&self) -> Aeron {
         self.get_archive_context().get_aeron()
@@ -212,6 +302,575 @@ macro_rules! impl_archive_position_methods {
 impl_archive_position_methods!(AeronPublication);
 impl_archive_position_methods!(AeronExclusivePublication);
 
+/// Length value which will replay the whole recording and then follow it live.
+pub const REPLAY_ALL_AND_FOLLOW: i64 = i64::MAX;
+
+/// Builder for [`AeronArchiveReplayParams`], the defaults match `aeron_archive_replay_params_init`
+/// i.e. replay the whole recording from the start with no bound.
+///
+/// ```no_compile
+/// let params = ReplayParams::new()
+///     .position(start_position)
+///     .replay_to_live()
+///     .bounded_by(&limit_counter);
+/// let replay = archive.start_replay_with_handle(recording_id, channel, stream_id, &params)?;
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ReplayParams {
+    bounding_limit_counter_id: i32,
+    file_io_max_length: i32,
+    position: i64,
+    length: i64,
+    replay_token: i64,
+    subscription_registration_id: i64,
+}
+
+impl Default for ReplayParams {
+    fn default() -> Self {
+        Self {
+            bounding_limit_counter_id: AERON_NULL_COUNTER_ID,
+            file_io_max_length: AERON_NULL_VALUE,
+            position: AERON_NULL_POSITION as i64,
+            length: AERON_NULL_VALUE as i64,
+            replay_token: AERON_NULL_VALUE as i64,
+            subscription_registration_id: AERON_NULL_VALUE as i64,
+        }
+    }
+}
+
+impl ReplayParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Position to start the replay from, must be on a fragment boundary.
+    #[inline]
+    pub fn position(mut self, position: i64) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// Number of bytes to replay, use [`REPLAY_ALL_AND_FOLLOW`] to follow a live recording.
+    #[inline]
+    pub fn length(mut self, length: i64) -> Self {
+        self.length = length;
+        self
+    }
+
+    /// Replay from `start_position` up to (but excluding) `stop_position`.
+    #[inline]
+    pub fn range(self, start_position: i64, stop_position: i64) -> Self {
+        debug_assert!(stop_position >= start_position);
+        self.position(start_position)
+            .length(stop_position - start_position)
+    }
+
+    /// Replay what has been recorded so far and keep following the recording if it is still live.
+    #[inline]
+    pub fn replay_to_live(self) -> Self {
+        self.length(REPLAY_ALL_AND_FOLLOW)
+    }
+
+    /// Bound the replay by a counter, the replay will not go past the value of the counter.
+    /// Useful when a replay should not overtake a live consumer.
+    #[inline]
+    pub fn bounded_by(self, limit_counter: &AeronCounter) -> Result<Self, AeronCError> {
+        Ok(self.bounding_limit_counter_id(limit_counter.get_constants()?.counter_id))
+    }
+
+    #[inline]
+    pub fn bounding_limit_counter_id(mut self, counter_id: i32) -> Self {
+        self.bounding_limit_counter_id = counter_id;
+        self
+    }
+
+    /// Maximum size of a file operation when the archive reads the recording for the replay.
+    #[inline]
+    pub fn file_io_max_length(mut self, file_io_max_length: i32) -> Self {
+        self.file_io_max_length = file_io_max_length;
+        self
+    }
+
+    /// Token returned by [`AeronArchive::request_replay_token`], required when the replay is
+    /// requested from a different client than the one which owns the archive session
+    /// (e.g. when using response channels).
+    #[inline]
+    pub fn replay_token(mut self, replay_token: i64) -> Self {
+        self.replay_token = replay_token;
+        self
+    }
+
+    /// Registration id of an existing response channel subscription which should receive the replay.
+    #[inline]
+    pub fn subscription_registration_id(mut self, subscription_registration_id: i64) -> Self {
+        self.subscription_registration_id = subscription_registration_id;
+        self
+    }
+
+    #[inline]
+    pub fn is_bounded(&self) -> bool {
+        self.bounding_limit_counter_id != AERON_NULL_COUNTER_ID
+    }
+
+    #[inline]
+    pub fn is_replay_to_live(&self) -> bool {
+        self.length == REPLAY_ALL_AND_FOLLOW
+    }
+
+    pub fn build(&self) -> Result<AeronArchiveReplayParams, AeronCError> {
+        AeronArchiveReplayParams::new(
+            self.bounding_limit_counter_id,
+            self.file_io_max_length,
+            self.position,
+            self.length,
+            self.replay_token,
+            self.subscription_registration_id,
+        )
+    }
+}
+
+/// Active replay started via [`AeronArchive::start_replay_with_handle`].
+///
+/// The replay is stopped when the handle is dropped, use [`ReplayHandle::detach`] to keep the
+/// replay running.
+pub struct ReplayHandle {
+    archive: AeronArchive,
+    replay_session_id: i64,
+    stopped: bool,
+}
+
+impl std::fmt::Debug for ReplayHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReplayHandle")
+            .field("replay_session_id", &self.replay_session_id)
+            .field("session_id", &self.session_id())
+            .field("stopped", &self.stopped)
+            .finish()
+    }
+}
+
+impl ReplayHandle {
+    /// Full 64-bit id required to stop the replay.
+    #[inline]
+    pub fn replay_session_id(&self) -> i64 {
+        self.replay_session_id
+    }
+
+    /// Session id of the image which will deliver the replay (lower 32-bits of the replay session id).
+    #[inline]
+    pub fn session_id(&self) -> i32 {
+        self.replay_session_id as i32
+    }
+
+    /// Stops the replay, calling this more than once is a no-op.
+    pub fn stop(&mut self) -> Result<(), AeronCError> {
+        if !self.stopped {
+            self.stopped = true;
+            self.archive.stop_replay(self.replay_session_id)?;
+        }
+        Ok(())
+    }
+
+    /// Leaves the replay running when the handle is dropped, returns the replay session id.
+    pub fn detach(mut self) -> i64 {
+        self.stopped = true;
+        self.replay_session_id
+    }
+}
+
+impl Drop for ReplayHandle {
+    fn drop(&mut self) {
+        if let Err(e) = self.stop() {
+            // replay may have already finished, in which case the archive rejects the stop
+            log::debug!(
+                "failed to stop replay [replaySessionId={}]: {:?}",
+                self.replay_session_id,
+                e
+            );
+        }
+    }
+}
+
+impl AeronArchive {
+    /// Starts a replay using the [`ReplayParams`] builder and returns a [`ReplayHandle`]
+    /// which stops the replay on drop.
+    pub fn start_replay_with_handle(
+        &self,
+        recording_id: i64,
+        replay_channel: &std::ffi::CStr,
+        replay_stream_id: i32,
+        params: &ReplayParams,
+    ) -> Result<ReplayHandle, AeronCError> {
+        let replay_session_id = self.start_replay(
+            recording_id,
+            replay_channel,
+            replay_stream_id,
+            &params.build()?,
+        )?;
+        Ok(ReplayHandle {
+            archive: self.clone(),
+            replay_session_id,
+            stopped: false,
+        })
+    }
+
+    /// Requests a token which authorises a replay of `recording_id` from a different
+    /// archive session, e.g. when the replay is delivered over a response channel.
+    pub fn request_replay_token(&self, recording_id: i64) -> Result<i64, AeronCError> {
+        let correlation_id = self.next_correlation_id();
+        if !self
+            .proxy()
+            .aeron_archive_request_replay_token(correlation_id, recording_id)
+        {
+            return Err(AeronCError::from_code(-1));
+        }
+        self.poll_for_response(c"AeronArchive::requestReplayToken", correlation_id)
+    }
+}
+
+/// Publication types which can be wrapped by a [`RecordedPublication`].
+pub trait RecordablePublication: Clone {
+    fn publication_position(&self) -> i64;
+    fn publication_session_id(&self) -> Result<i32, AeronCError>;
+    fn offer_bytes(&self, buffer: &[u8]) -> i64;
+    fn stop_recording_with(&self, archive: &AeronArchive) -> Result<i32, AeronCError>;
+}
+
+macro_rules! impl_recordable_publication {
+    ($pub_type:ty, $stop_fn:ident) => {
+        impl RecordablePublication for $pub_type {
+            #[inline]
+            fn publication_position(&self) -> i64 {
+                self.position()
+            }
+
+            #[inline]
+            fn publication_session_id(&self) -> Result<i32, AeronCError> {
+                Ok(self.get_constants()?.session_id())
+            }
+
+            #[inline]
+            fn offer_bytes(&self, buffer: &[u8]) -> i64 {
+                self.offer(buffer, Handlers::no_reserved_value_supplier_handler())
+            }
+
+            fn stop_recording_with(&self, archive: &AeronArchive) -> Result<i32, AeronCError> {
+                archive.$stop_fn(self)
+            }
+        }
+    };
+}
+
+impl_recordable_publication!(AeronPublication, stop_recording_publication);
+impl_recordable_publication!(
+    AeronExclusivePublication,
+    stop_recording_exclusive_publication
+);
+
+/// Publication which is being recorded by the archive, created with
+/// [`AeronArchive::add_recorded_publication`] or [`AeronArchive::add_recorded_exclusive_publication`].
+///
+/// Tracks the archive's recording position so a publisher can wait for data to be persisted
+/// or hold back when the archive falls too far behind. Recording is stopped when dropped unless
+/// [`RecordedPublication::detach`] is called.
+pub struct RecordedPublication<P: RecordablePublication> {
+    archive: AeronArchive,
+    publication: P,
+    counters_reader: AeronCountersReader,
+    session_id: i32,
+    recording_counter_id: Cell<i32>,
+    max_archive_lag: Option<i64>,
+    stopped: bool,
+}
+
+impl<P: RecordablePublication> std::fmt::Debug for RecordedPublication<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RecordedPublication")
+            .field("session_id", &self.session_id)
+            .field("recording_counter_id", &self.recording_counter_id.get())
+            .field("max_archive_lag", &self.max_archive_lag)
+            .field("stopped", &self.stopped)
+            .finish()
+    }
+}
+
+impl<P: RecordablePublication> std::ops::Deref for RecordedPublication<P> {
+    type Target = P;
+
+    fn deref(&self) -> &Self::Target {
+        &self.publication
+    }
+}
+
+impl<P: RecordablePublication> RecordedPublication<P> {
+    fn new(archive: &AeronArchive, publication: P) -> Result<Self, AeronCError> {
+        Ok(Self {
+            counters_reader: archive.aeron().counters_reader(),
+            session_id: publication.publication_session_id()?,
+            archive: archive.clone(),
+            publication,
+            recording_counter_id: Cell::new(AERON_NULL_COUNTER_ID),
+            max_archive_lag: None,
+            stopped: false,
+        })
+    }
+
+    /// Only allow [`RecordedPublication::offer`] to succeed while the archive is less than
+    /// `max_lag` bytes behind the publication.
+    pub fn with_max_archive_lag(mut self, max_lag: i64) -> Self {
+        self.max_archive_lag = Some(max_lag);
+        self
+    }
+
+    #[inline]
+    pub fn publication(&self) -> &P {
+        &self.publication
+    }
+
+    #[inline]
+    pub fn max_archive_lag(&self) -> Option<i64> {
+        self.max_archive_lag
+    }
+
+    /// Id of the archive's recording position counter, errors until the recording has started.
+    pub fn recording_counter_id(&self) -> Result<i32, AeronCError> {
+        let mut counter_id = self.recording_counter_id.get();
+        if counter_id == AERON_NULL_COUNTER_ID {
+            counter_id =
+                RecordingPos::find_counter_id_by_session(&self.counters_reader, self.session_id);
+            if counter_id < 0 {
+                return Err(AeronCError::from_code(counter_id));
+            }
+            self.recording_counter_id.set(counter_id);
+        }
+        Ok(counter_id)
+    }
+
+    pub fn recording_id(&self) -> Result<i64, AeronCError> {
+        RecordingPos::get_recording_id(&self.counters_reader, self.recording_counter_id()?)
+    }
+
+    /// Position the archive has recorded up to.
+    pub fn archive_position(&self) -> Result<i64, AeronCError> {
+        let position = self
+            .counters_reader
+            .get_counter_value(self.recording_counter_id()?);
+        if position < 0 {
+            return Err(AeronCError::from_code(position as i32));
+        }
+        Ok(position)
+    }
+
+    /// Number of bytes published but not yet recorded by the archive.
+    pub fn archive_lag(&self) -> Result<i64, AeronCError> {
+        Ok(self.publication.publication_position() - self.archive_position()?)
+    }
+
+    /// Offers the buffer, unless a max archive lag is set and the archive is too far behind
+    /// (or has not started recording yet) in which case `AERON_PUBLICATION_BACK_PRESSURED` is returned.
+    pub fn offer(&self, buffer: &[u8]) -> i64 {
+        if let Some(max_lag) = self.max_archive_lag {
+            match self.archive_lag() {
+                Ok(lag) if lag < max_lag => {}
+                _ => return AERON_PUBLICATION_BACK_PRESSURED as i64,
+            }
+        }
+        self.publication.offer_bytes(buffer)
+    }
+
+    /// Blocks until the archive has recorded up to `position`, failing on an archive error
+    /// response received meanwhile.
+    pub fn wait_until_archived(&self, position: i64, timeout: Duration) -> Result<(), AeronCError> {
+        let start = Instant::now();
+        loop {
+            if matches!(self.archive_position(), Ok(archived) if archived >= position) {
+                return Ok(());
+            }
+            let error = self.archive.poll_for_error_response_as_string(4096)?;
+            if !error.is_empty() {
+                log::error!(
+                    "archive error waiting for position {} [{:?}]: {}",
+                    position,
+                    self,
+                    error
+                );
+                return Err(AeronCError::from_code(-1));
+            }
+            if start.elapsed() > timeout {
+                log::error!(
+                    "timed out waiting for archive to reach position {} [{:?}]",
+                    position,
+                    self
+                );
+                return Err(AeronErrorType::TimedOut.into());
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// Stops the recording, calling this more than once is a no-op.
+    pub fn stop_recording(&mut self) -> Result<(), AeronCError> {
+        if !self.stopped {
+            self.stopped = true;
+            self.publication.stop_recording_with(&self.archive)?;
+        }
+        Ok(())
+    }
+
+    /// Leaves the recording running when dropped and returns the underlying publication.
+    pub fn detach(mut self) -> P {
+        self.stopped = true;
+        self.publication.clone()
+    }
+}
+
+impl<P: RecordablePublication> Drop for RecordedPublication<P> {
+    fn drop(&mut self) {
+        if let Err(e) = self.stop_recording() {
+            log::warn!(
+                "failed to stop recording [sessionId={}]: {:?}",
+                self.session_id,
+                e
+            );
+        }
+    }
+}
+
+macro_rules! impl_add_recorded_publication {
+    ($method:ident, $pub_type:ident, $c_type:ty, $add_fn:ident) => {
+        impl AeronArchive {
+            /// Adds a publication and starts a local recording of it, see [`RecordedPublication`].
+            pub fn $method(
+                &self,
+                channel: &std::ffi::CStr,
+                stream_id: i32,
+            ) -> Result<RecordedPublication<$pub_type>, AeronCError> {
+                let archive = self.get_inner();
+                let resource = ManagedCResource::<$c_type>::new(
+                    move |ctx_field| unsafe {
+                        $add_fn(ctx_field, archive, channel.as_ptr(), stream_id)
+                    },
+                    None,
+                    false,
+                    None,
+                )?;
+                resource.auto_close.set(true);
+                resource.add_dependency(self.aeron());
+                let publication = $pub_type {
+                    inner: CResource::OwnedOnHeap(std::rc::Rc::new(resource)),
+                };
+                RecordedPublication::new(self, publication)
+            }
+        }
+    };
+}
+
+impl_add_recorded_publication!(
+    add_recorded_publication,
+    AeronPublication,
+    aeron_publication_t,
+    aeron_archive_add_recorded_publication
+);
+impl_add_recorded_publication!(
+    add_recorded_exclusive_publication,
+    AeronExclusivePublication,
+    aeron_exclusive_publication_t,
+    aeron_archive_add_recorded_exclusive_publication
+);
+
+/// Owned copy of an [`AeronArchiveRecordingSubscriptionDescriptor`] which, unlike the descriptor
+/// passed to the listing callback, can be kept after the callback returns.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RecordingSubscriptionDescriptor {
+    pub control_session_id: i64,
+    pub correlation_id: i64,
+    /// can be used with [`AeronArchive::stop_recording_subscription`]
+    pub subscription_id: i64,
+    pub stream_id: i32,
+    pub stripped_channel: String,
+}
+
+impl From<&AeronArchiveRecordingSubscriptionDescriptor> for RecordingSubscriptionDescriptor {
+    fn from(d: &AeronArchiveRecordingSubscriptionDescriptor) -> Self {
+        Self {
+            control_session_id: d.control_session_id,
+            correlation_id: d.correlation_id,
+            subscription_id: d.subscription_id,
+            stream_id: d.stream_id,
+            stripped_channel: d.stripped_channel().to_string(),
+        }
+    }
+}
+
+impl RecordingSubscriptionDescriptor {
+    /// true if this subscription records `channel`, i.e. the media matches and every parameter
+    /// kept in the archive's stripped channel is present in `channel` with the same value.
+    pub fn matches_channel(&self, channel: &str) -> bool {
+        fn split(channel: &str) -> (&str, Vec<&str>) {
+            match channel.split_once('?') {
+                Some((media, params)) => (media, params.split('|').collect()),
+                None => (channel, vec![]),
+            }
+        }
+
+        let (media, params) = split(&self.stripped_channel);
+        let (other_media, other_params) = split(channel);
+        media == other_media
+            && params
+                .iter()
+                .filter(|p| !p.is_empty())
+                .all(|p| other_params.contains(p))
+    }
+}
+
+impl AeronArchive {
+    /// Lists the active recording subscriptions in the archive as owned descriptors.
+    ///
+    /// `pseudo_index` is the index of the first subscription to return, `channel_fragment` is used
+    /// for a "contains" match against the stripped channel and `stream_id` is only applied when
+    /// `apply_stream_id` is true.
+    pub fn recording_subscriptions(
+        &self,
+        pseudo_index: i32,
+        subscription_count: i32,
+        channel_fragment: &str,
+        stream_id: i32,
+        apply_stream_id: bool,
+    ) -> Result<Vec<RecordingSubscriptionDescriptor>, AeronCError> {
+        let mut descriptors = vec![];
+        let mut count = 0;
+        self.list_recording_subscriptions_once(
+            &mut count,
+            pseudo_index,
+            subscription_count,
+            &channel_fragment.into_c_string(),
+            stream_id,
+            apply_stream_id,
+            |d| descriptors.push(RecordingSubscriptionDescriptor::from(&d)),
+        )?;
+        Ok(descriptors)
+    }
+
+    /// Starts recording `channel` and `stream_id` unless the archive already has a matching
+    /// recording subscription. Returns the subscription id of the existing or new recording.
+    pub fn ensure_recording(
+        &self,
+        channel: &str,
+        stream_id: i32,
+        source_location: aeron_archive_source_location_t,
+    ) -> Result<i64, AeronCError> {
+        let existing = self
+            .recording_subscriptions(0, i32::MAX, "", stream_id, true)?
+            .into_iter()
+            .find(|d| d.matches_channel(channel));
+        if let Some(existing) = existing {
+            log::debug!("already recording {} [{:?}]", channel, existing);
+            return Ok(existing.subscription_id);
+        }
+        self.start_recording(&channel.into_c_string(), stream_id, source_location, true)
+    }
+}
+
 impl AeronArchiveContext {
     // The method below sets no credentials supplier, which is essential for the operation
     // of the Aeron Archive Context. The `set_credentials_supplier` must be set to prevent
@@ -224,6 +883,45 @@ impl AeronArchiveContext {
         )
     }
 
+    /// Uses `supplier` to provide credentials when connecting to the archive. The buffers handed
+    /// to the C client are owned by this wrapper and released via the free callback, and the
+    /// supplier is released when the context is dropped.
+    pub fn set_credentials<S: CredentialsSupplier + 'static>(
+        &self,
+        supplier: S,
+    ) -> Result<i32, AeronCError> {
+        let mut handler = Handler::leak(CredentialsSupplierHandler(supplier));
+        let result = self.set_credentials_supplier(
+            Some(credentials_supplier_encoded_credentials::<S>),
+            Some(credentials_supplier_on_challenge::<S>),
+            Some(&handler),
+        );
+        match result {
+            Ok(_) => self.inner.add_dependency(OwnedHandler(handler)),
+            Err(_) => handler.release(),
+        }
+        result
+    }
+
+    /// Same as [`AeronArchiveContext::new_with_no_credentials_supplier`] but authenticates
+    /// using `supplier`.
+    pub fn new_with_credentials_supplier<S: CredentialsSupplier + 'static>(
+        aeron: &Aeron,
+        request_control_channel: &str,
+        response_control_channel: &str,
+        recording_events_channel: &str,
+        supplier: S,
+    ) -> Result<AeronArchiveContext, AeronCError> {
+        let context = Self::new_with_no_credentials_supplier(
+            aeron,
+            request_control_channel,
+            response_control_channel,
+            recording_events_channel,
+        )?;
+        context.set_credentials(supplier)?;
+        Ok(context)
+    }
+
     /// This method creates a new `AeronArchiveContext` with a no-op credentials supplier.
     /// If you do not set a credentials supplier, it will segfault.
     /// This method ensures that a non-functional credentials supplier is set to avoid the segfault.
@@ -313,10 +1011,90 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_replay_params_builder() -> Result<(), AeronCError> {
+        let defaults = AeronArchiveReplayParams::default();
+        defaults.init()?;
+        let params = ReplayParams::new().build()?;
+        assert_eq!(defaults.deref(), params.deref());
+        assert!(!ReplayParams::new().is_bounded());
+
+        let params = ReplayParams::new()
+            .range(1024, 4096)
+            .file_io_max_length(65536)
+            .replay_token(7);
+        let built = params.build()?;
+        assert_eq!(1024, built.position());
+        assert_eq!(3072, built.length());
+        assert_eq!(65536, built.file_io_max_length());
+        assert_eq!(7, built.replay_token());
+
+        let params = ReplayParams::new()
+            .position(1024)
+            .replay_to_live()
+            .bounding_limit_counter_id(3);
+        assert!(params.is_bounded());
+        assert!(params.is_replay_to_live());
+        let built = params.build()?;
+        assert_eq!(REPLAY_ALL_AND_FOLLOW, built.length());
+        assert_eq!(3, built.bounding_limit_counter_id());
+        Ok(())
+    }
+
+    #[test]
+    fn test_retention_policy_evaluate() {
+        use crate::retention::*;
+
+        const TERM: i32 = 64 * 1024;
+        const SEGMENT: i32 = 4 * TERM;
+        let recording = RecordingSegments {
+            recording_id: 5,
+            stream_id: STREAM_ID,
+            start_timestamp: 1_000,
+            stop_timestamp: 10_000,
+            start_position: 0,
+            stop_position: 10 * SEGMENT as i64 + 100,
+            term_buffer_length: TERM,
+            segment_file_length: SEGMENT,
+            stripped_channel: "aeron:ipc".to_string(),
+        };
+        let stop = recording.stop_position;
+
+        // keep at most 3 segments worth of data, rounded down to a segment boundary
+        let removal = RetentionPolicy::new()
+            .max_bytes(3 * SEGMENT as i64)
+            .evaluate(&recording, stop, 20_000)
+            .expect("segments to remove");
+        assert_eq!(7 * SEGMENT as i64, removal.new_start_position);
+        assert_eq!(7, removal.segments);
+        assert_eq!(7 * SEGMENT as i64, removal.bytes());
+
+        // expired recordings lose every segment except the one holding the stop position
+        let removal = RetentionPolicy::new()
+            .max_age(Duration::from_millis(5_000))
+            .max_bytes(9 * SEGMENT as i64)
+            .evaluate(&recording, stop, 20_000)
+            .expect("segments to remove");
+        assert_eq!(10 * SEGMENT as i64, removal.new_start_position);
+
+        // not old enough and within a single segment means nothing to do
+        let policy = RetentionPolicy::new()
+            .max_age(Duration::from_secs(60))
+            .before_position(5, SEGMENT as i64 - 1);
+        assert_eq!(None, policy.evaluate(&recording, stop, 20_000));
+
+        // filters and other recordings are ignored
+        let policy = RetentionPolicy::new()
+            .before_position(6, 5 * SEGMENT as i64)
+            .stream_id(STREAM_ID);
+        assert_eq!(None, policy.evaluate(&recording, stop, 20_000));
+        let policy = RetentionPolicy::new().max_bytes(0).stream_id(STREAM_ID + 1);
+        assert_eq!(None, policy.evaluate(&recording, stop, 20_000));
+    }
+
     pub const STREAM_ID: i32 = 1033;
     pub const MESSAGE_PREFIX: &str = "Message-Prefix-";
     pub const CONTROL_ENDPOINT: &str = "localhost:23265";
-    pub const RECORDING_ENDPOINT: &str = "localhost:23266";
     pub const LIVE_ENDPOINT: &str = "localhost:23267";
     pub const REPLAY_ENDPOINT: &str = "localhost:0";
     // pub const REPLAY_ENDPOINT: &str = "localhost:23268";
@@ -333,7 +1111,6 @@ mod tests {
             .expect("failed to kill all java processes");
 
         assert!(is_udp_port_available(23265));
-        assert!(is_udp_port_available(23266));
         assert!(is_udp_port_available(23267));
         assert!(is_udp_port_available(23268));
         let id = Aeron::nano_clock();
@@ -361,7 +1138,7 @@ mod tests {
         assert!(!aeron.is_closed());
 
         let (session_id, publisher_thread) =
-            reply_merge_publisher(&archive, aeron.clone(), running.clone())?;
+            reply_merge_publisher(&archive, media_driver.aeron_dir.clone(), running.clone())?;
 
         {
             let context = AeronContext::new()?;
@@ -392,54 +1169,58 @@ mod tests {
 
     fn reply_merge_publisher(
         archive: &AeronArchive,
-        aeron: Aeron,
+        aeron_dir: std::ffi::CString,
         running: Arc<AtomicBool>,
     ) -> Result<(i32, JoinHandle<()>), AeronCError> {
-        let publication = aeron.add_publication(
-            // &format!("aeron:udp?control={CONTROL_ENDPOINT}|control-mode=dynamic|term-length=65536|fc=tagged,g:99901/1,t:5s"),
-            &format!("aeron:udp?control={CONTROL_ENDPOINT}|control-mode=dynamic|term-length=65536")
-                .into_c_string(),
-            STREAM_ID,
-            Duration::from_secs(5),
-        )?;
+        // the archive client is not Send so the publisher thread connects its own
+        let archive_context = archive.get_archive_context();
+        let control_request_channel = archive_context.get_control_request_channel().to_string();
+        let control_response_channel = archive_context.get_control_response_channel().to_string();
+        let recording_events_channel = archive_context.get_recording_events_channel().to_string();
+        let (session_id_tx, session_id_rx) = std::sync::mpsc::channel();
 
-        info!(
-            "publication {} [status={:?}]",
-            publication.channel(),
-            publication.channel_status()
-        );
-        assert_eq!(1, publication.channel_status());
+        let publisher_thread = thread::spawn(move || {
+            let context = AeronContext::new().expect("failed to create context");
+            context.set_dir(&aeron_dir).expect("failed to set dir");
+            let aeron = Aeron::new(&context).expect("failed to create aeron");
+            aeron.start().expect("failed to start aeron");
+            let archive_context = AeronArchiveContext::new_with_no_credentials_supplier(
+                &aeron,
+                &control_request_channel,
+                &control_response_channel,
+                &recording_events_channel,
+            )
+            .expect("failed to create archive context");
+            let archive = AeronArchiveAsyncConnect::new_with_aeron(&archive_context, &aeron)
+                .expect("failed to connect to archive")
+                .poll_blocking(Duration::from_secs(30))
+                .expect("failed to connect to archive");
 
-        let session_id = publication.session_id();
-        let recording_channel = format!(
-            // "aeron:udp?endpoint={RECORDING_ENDPOINT}|control={CONTROL_ENDPOINT}|session-id={session_id}|gtag=99901"
-            "aeron:udp?endpoint={RECORDING_ENDPOINT}|control={CONTROL_ENDPOINT}|session-id={session_id}"
-        );
-        info!("recording channel {}", recording_channel);
-        archive.start_recording(
-            &recording_channel.into_c_string(),
-            STREAM_ID,
-            SOURCE_LOCATION_REMOTE,
-            true,
-        )?;
+            let publication = archive
+                .add_recorded_publication(
+                    // &format!("aeron:udp?control={CONTROL_ENDPOINT}|control-mode=dynamic|term-length=65536|fc=tagged,g:99901/1,t:5s"),
+                    &format!(
+                        "aeron:udp?control={CONTROL_ENDPOINT}|control-mode=dynamic|term-length=65536"
+                    )
+                    .into_c_string(),
+                    STREAM_ID,
+                )
+                .expect("failed to add recorded publication");
+            info!(
+                "publication {} [status={:?}]",
+                publication.channel(),
+                publication.channel_status()
+            );
+            assert_eq!(1, publication.channel_status());
+            session_id_tx
+                .send(publication.session_id())
+                .expect("failed to send session id");
 
-        info!("waiting for publisher to be connected");
-        while !publication.is_connected() {
-            thread::sleep(Duration::from_millis(100));
-        }
-        info!("publisher to be connected");
-        let counters_reader = aeron.counters_reader();
-        let mut caught_up_count = 0;
-        let publisher_thread = thread::spawn(move || {
             let mut message_count = 0;
-
+            let mut caught_up_count = 0;
             while running.load(Ordering::Acquire) {
                 let message = format!("{}{}", MESSAGE_PREFIX, message_count);
-                while publication.offer(
-                    message.as_bytes(),
-                    Handlers::no_reserved_value_supplier_handler(),
-                ) <= 0
-                {
+                while publication.offer(message.as_bytes()) <= 0 {
                     thread::sleep(Duration::from_millis(10));
                 }
                 message_count += 1;
@@ -453,15 +1234,19 @@ mod tests {
                 // slow down publishing so can catch up
                 if message_count > 10_000 {
                     // ensure archiver is caught up
-                    while !publication.is_archive_position_with(0) {
-                        thread::sleep(Duration::from_micros(300));
-                    }
+                    publication
+                        .wait_until_archived(publication.position(), Duration::from_secs(5))
+                        .expect("archive did not catch up with publication");
                     caught_up_count += 1;
                 }
             }
             assert!(caught_up_count > 0);
             info!("Publisher thread terminated");
         });
+
+        let session_id = session_id_rx
+            .recv_timeout(Duration::from_secs(30))
+            .map_err(|_| AeronCError::from(AeronErrorType::TimedOut))?;
         Ok((session_id, publisher_thread))
     }
 
@@ -917,6 +1702,152 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    #[serial]
+    fn test_recorded_publication() -> Result<(), Box<dyn Error>> {
+        let (aeron, archive_context, _media_driver) = start_aeron_archive()?;
+        let archive_connector =
+            AeronArchiveAsyncConnect::new_with_aeron(&archive_context.clone(), &aeron)?;
+        let archive = archive_connector
+            .poll_blocking(Duration::from_secs(30))
+            .expect("failed to connect to archive");
+
+        let publication = archive
+            .add_recorded_exclusive_publication(&"aeron:ipc".into_c_string(), STREAM_ID)?
+            .with_max_archive_lag(64 * 1024);
+
+        let start = Instant::now();
+        while publication.recording_counter_id().is_err() {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(publication.recording_id()? >= 0);
+
+        let mut sent = 0;
+        while sent < 1000 {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            if publication.offer(format!("{MESSAGE_PREFIX}{sent}").as_bytes()) > 0 {
+                sent += 1;
+            }
+            assert!(publication.archive_lag().unwrap_or(0) <= 64 * 1024 + 1024);
+        }
+
+        publication.wait_until_archived(publication.position(), Duration::from_secs(10))?;
+        assert_eq!(0, publication.archive_lag()?);
+
+        let mut publication = publication;
+        publication.stop_recording()?;
+        // stopping twice is a no-op
+        publication.stop_recording()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_credentials_supplier_callbacks() {
+        #[derive(Default)]
+        struct Recording {
+            challenges: Vec<Vec<u8>>,
+            freed: Vec<Vec<u8>>,
+        }
+        impl CredentialsSupplier for Recording {
+            fn encoded_credentials(&mut self) -> Vec<u8> {
+                b"admin:secret".to_vec()
+            }
+            fn on_challenge(&mut self, encoded_challenge: &[u8]) -> Vec<u8> {
+                self.challenges.push(encoded_challenge.to_vec());
+                b"response".to_vec()
+            }
+            fn on_free(&mut self, credentials: &mut [u8]) {
+                self.freed.push(credentials.to_vec());
+            }
+        }
+
+        let mut handler = CredentialsSupplierHandler(Recording::default());
+        let clientd = &mut handler as *mut _ as *mut std::os::raw::c_void;
+        unsafe {
+            let credentials = credentials_supplier_encoded_credentials::<Recording>(clientd);
+            let bytes = std::slice::from_raw_parts(
+                (*credentials).data as *const u8,
+                (*credentials).length as usize,
+            );
+            assert_eq!(b"admin:secret", bytes);
+            handler.handle_aeron_archive_credentials_free_func(credentials.into());
+
+            let challenge = b"nonce";
+            let mut encoded_challenge = aeron_archive_encoded_credentials_t {
+                data: challenge.as_ptr() as *const _,
+                length: challenge.len() as u32,
+            };
+            let clientd = &mut handler as *mut _ as *mut std::os::raw::c_void;
+            let response =
+                credentials_supplier_on_challenge::<Recording>(&mut encoded_challenge, clientd);
+            assert_eq!(8, (*response).length);
+            handler.handle_aeron_archive_credentials_free_func(response.into());
+        }
+
+        assert_eq!(vec![b"nonce".to_vec()], handler.0.challenges);
+        assert_eq!(
+            vec![b"admin:secret".to_vec(), b"response".to_vec()],
+            handler.0.freed
+        );
+
+        let mut supplier = UsernamePasswordCredentialsSupplier::new("admin", "secret");
+        assert_eq!(b"admin:secret".to_vec(), supplier.encoded_credentials());
+        assert!(!format!("{:?}", supplier).contains("secret"));
+        let mut credentials = supplier.on_challenge(b"nonce");
+        supplier.on_free(&mut credentials);
+        assert!(credentials.iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn test_recording_subscription_matches_channel() {
+        let descriptor = RecordingSubscriptionDescriptor {
+            control_session_id: 1,
+            correlation_id: 2,
+            subscription_id: 3,
+            stream_id: STREAM_ID,
+            stripped_channel: "aeron:udp?endpoint=localhost:8010".to_string(),
+        };
+        assert!(descriptor.matches_channel("aeron:udp?endpoint=localhost:8010"));
+        assert!(descriptor.matches_channel("aeron:udp?term-length=65536|endpoint=localhost:8010"));
+        assert!(!descriptor.matches_channel("aeron:udp?endpoint=localhost:8011"));
+        assert!(!descriptor.matches_channel("aeron:ipc"));
+    }
+
+    #[test]
+    #[serial]
+    fn test_ensure_recording() -> Result<(), Box<dyn Error>> {
+        let (aeron, archive_context, _media_driver) = start_aeron_archive()?;
+        let archive_connector =
+            AeronArchiveAsyncConnect::new_with_aeron(&archive_context.clone(), &aeron)?;
+        let archive = archive_connector
+            .poll_blocking(Duration::from_secs(30))
+            .expect("failed to connect to archive");
+
+        let channel = "aeron:ipc?term-length=65536";
+        assert!(archive
+            .recording_subscriptions(0, 10, "ipc", STREAM_ID, true)?
+            .is_empty());
+
+        let subscription_id =
+            archive.ensure_recording(channel, STREAM_ID, SOURCE_LOCATION_LOCAL)?;
+        assert_eq!(
+            subscription_id,
+            archive.ensure_recording(channel, STREAM_ID, SOURCE_LOCATION_LOCAL)?
+        );
+
+        let subscriptions = archive.recording_subscriptions(0, 10, "ipc", STREAM_ID, true)?;
+        assert_eq!(1, subscriptions.len());
+        assert_eq!(subscription_id, subscriptions[0].subscription_id);
+        assert_eq!(STREAM_ID, subscriptions[0].stream_id);
+
+        archive.stop_recording_subscription(subscription_id)?;
+        assert!(archive
+            .recording_subscriptions(0, 10, "ipc", STREAM_ID, true)?
+            .is_empty());
+        Ok(())
+    }
+
     #[test]
     #[serial]
     fn test_replay_with_invalid_recording_id() -> Result<(), Box<dyn Error>> {
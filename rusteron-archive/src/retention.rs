@@ -0,0 +1,385 @@
+//! Segment retention for archive recordings.
+//!
+//! A [`RetentionPolicy`] is evaluated against the recording catalog and works out, per recording,
+//! the segment boundary that old data can be removed up to. Boundaries are always computed with
+//! [`AeronArchive::segment_file_base_position`] so the archive is only ever asked to detach or purge
+//! whole segments. Running the policy in dry-run mode only reports what would be removed.
+//!
+//! ```no_run
+//! # use rusteron_archive::*;
+//! # use rusteron_archive::retention::*;
+//! # use std::time::Duration;
+//! # fn run(archive: &AeronArchive) -> Result<(), AeronCError> {
+//! let report = RetentionPolicy::new()
+//!     .max_age(Duration::from_secs(7 * 24 * 60 * 60))
+//!     .max_bytes(64 * 1024 * 1024 * 1024)
+//!     .action(RetentionAction::Purge)
+//!     .dry_run(true)
+//!     .apply(archive)?;
+//! println!("would remove {} bytes", report.total_bytes());
+//! # Ok(())
+//! # }
+//! ```
+use crate::{AeronArchive, AeronArchiveRecordingDescriptor, AeronCError};
+use log::{debug, warn};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A single rule of a [`RetentionPolicy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetentionRule {
+    /// Remove every complete segment of a recording which stopped longer ago than the given age.
+    /// Active recordings are never aged out.
+    MaxAge(Duration),
+    /// Keep at most the given number of bytes per recording, removing the oldest segments first.
+    MaxBytes(i64),
+    /// Remove the segments before the given position, for a single recording or, when
+    /// `recording_id` is `None`, for every recording matched by the policy.
+    BeforePosition {
+        recording_id: Option<i64>,
+        position: i64,
+    },
+}
+
+/// What to do with the segments selected by a [`RetentionPolicy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RetentionAction {
+    /// Detach the segments from the recording but leave the files on disk so they can be
+    /// re-attached with [`AeronArchive::attach_segments`] or removed later with
+    /// [`AeronArchive::delete_detached_segments`].
+    Detach,
+    /// Detach and delete the segment files.
+    #[default]
+    Purge,
+}
+
+/// The parts of a recording descriptor needed to evaluate a retention policy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordingSegments {
+    pub recording_id: i64,
+    pub stream_id: i32,
+    pub start_timestamp: i64,
+    pub stop_timestamp: i64,
+    pub start_position: i64,
+    pub stop_position: i64,
+    pub term_buffer_length: i32,
+    pub segment_file_length: i32,
+    pub stripped_channel: String,
+}
+
+impl RecordingSegments {
+    /// true if the recording is still active, i.e. has no stop position in the catalog
+    pub fn is_active(&self) -> bool {
+        self.stop_position < 0
+    }
+
+    /// Position of the recorded stream at the base of the segment file containing `position`.
+    pub fn segment_base_position(&self, position: i64) -> i64 {
+        AeronArchive::segment_file_base_position(
+            self.start_position,
+            position,
+            self.term_buffer_length,
+            self.segment_file_length,
+        )
+    }
+}
+
+impl From<&AeronArchiveRecordingDescriptor> for RecordingSegments {
+    fn from(d: &AeronArchiveRecordingDescriptor) -> Self {
+        Self {
+            recording_id: d.recording_id,
+            stream_id: d.stream_id,
+            start_timestamp: d.start_timestamp,
+            stop_timestamp: d.stop_timestamp,
+            start_position: d.start_position,
+            stop_position: d.stop_position,
+            term_buffer_length: d.term_buffer_length,
+            segment_file_length: d.segment_file_length,
+            stripped_channel: d.stripped_channel().to_string(),
+        }
+    }
+}
+
+/// Segments of one recording selected for removal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentRemoval {
+    pub recording_id: i64,
+    pub stream_id: i32,
+    pub stripped_channel: String,
+    /// current start position of the recording
+    pub start_position: i64,
+    /// start position of the recording once the segments are removed, always a segment boundary
+    pub new_start_position: i64,
+    /// number of segment files which will be removed
+    pub segments: i64,
+}
+
+impl SegmentRemoval {
+    /// number of recorded bytes which will no longer be available for replay
+    pub fn bytes(&self) -> i64 {
+        self.new_start_position - self.start_position
+    }
+}
+
+/// Result of applying a [`SegmentRemoval`] to the archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RetentionOutcome {
+    /// nothing was changed as the policy was run in dry-run mode
+    DryRun,
+    /// segments were detached but left on disk
+    Detached,
+    /// segments were detached and deleted, with the number of segment files deleted
+    Purged(i64),
+    /// the archive rejected the request, e.g. as the segments are being replayed
+    Failed(AeronCError),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetentionEntry {
+    pub removal: SegmentRemoval,
+    pub outcome: RetentionOutcome,
+}
+
+/// Report produced by [`RetentionPolicy::apply`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RetentionReport {
+    pub dry_run: bool,
+    pub entries: Vec<RetentionEntry>,
+}
+
+impl RetentionReport {
+    /// total number of recorded bytes removed (or which would be removed in dry-run mode)
+    pub fn total_bytes(&self) -> i64 {
+        self.succeeded().map(|e| e.removal.bytes()).sum()
+    }
+
+    /// total number of segment files removed (or which would be removed in dry-run mode)
+    pub fn total_segments(&self) -> i64 {
+        self.succeeded().map(|e| e.removal.segments).sum()
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &RetentionEntry> {
+        self.entries
+            .iter()
+            .filter(|e| matches!(e.outcome, RetentionOutcome::Failed(_)))
+    }
+
+    fn succeeded(&self) -> impl Iterator<Item = &RetentionEntry> {
+        self.entries
+            .iter()
+            .filter(|e| !matches!(e.outcome, RetentionOutcome::Failed(_)))
+    }
+
+    /// Re-attach segments which were detached (but not deleted) by this report.
+    /// Returns the number of segments attached.
+    pub fn reattach(&self, archive: &AeronArchive) -> Result<i64, AeronCError> {
+        let mut attached = 0;
+        for entry in &self.entries {
+            if entry.outcome == RetentionOutcome::Detached {
+                attached += archive.attach_segments(entry.removal.recording_id)?;
+            }
+        }
+        Ok(attached)
+    }
+}
+
+/// Set of rules deciding which segments of which recordings can be removed.
+///
+/// When several rules are configured the most aggressive one wins, i.e. a recording is truncated
+/// up to the furthest segment boundary selected by any rule.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RetentionPolicy {
+    rules: Vec<RetentionRule>,
+    action: RetentionAction,
+    dry_run: bool,
+    stream_id: Option<i32>,
+    channel_fragment: Option<String>,
+}
+
+impl RetentionPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn rule(mut self, rule: RetentionRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    pub fn max_age(self, max_age: Duration) -> Self {
+        self.rule(RetentionRule::MaxAge(max_age))
+    }
+
+    pub fn max_bytes(self, max_bytes: i64) -> Self {
+        self.rule(RetentionRule::MaxBytes(max_bytes))
+    }
+
+    pub fn before_position(self, recording_id: i64, position: i64) -> Self {
+        self.rule(RetentionRule::BeforePosition {
+            recording_id: Some(recording_id),
+            position,
+        })
+    }
+
+    pub fn action(mut self, action: RetentionAction) -> Self {
+        self.action = action;
+        self
+    }
+
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// only consider recordings of the given stream id
+    pub fn stream_id(mut self, stream_id: i32) -> Self {
+        self.stream_id = Some(stream_id);
+        self
+    }
+
+    /// only consider recordings whose stripped channel contains the given fragment
+    pub fn channel_fragment(mut self, fragment: &str) -> Self {
+        self.channel_fragment = Some(fragment.to_string());
+        self
+    }
+
+    pub fn rules(&self) -> &[RetentionRule] {
+        &self.rules
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    fn matches(&self, recording: &RecordingSegments) -> bool {
+        self.stream_id.map_or(true, |s| s == recording.stream_id)
+            && self
+                .channel_fragment
+                .as_deref()
+                .map_or(true, |f| recording.stripped_channel.contains(f))
+    }
+
+    /// Work out which segments of `recording` the policy removes.
+    ///
+    /// `stop_position` is the highest recorded position, which for an active recording should be
+    /// taken from [`AeronArchive::get_max_recorded_position`]. `now_ms` is the current epoch time
+    /// in milliseconds, the same clock the archive uses for its catalog timestamps.
+    pub fn evaluate(
+        &self,
+        recording: &RecordingSegments,
+        stop_position: i64,
+        now_ms: i64,
+    ) -> Option<SegmentRemoval> {
+        if !self.matches(recording)
+            || recording.segment_file_length <= 0
+            || recording.term_buffer_length <= 0
+            || stop_position <= recording.start_position
+        {
+            return None;
+        }
+
+        let target = self
+            .rules
+            .iter()
+            .filter_map(|rule| match *rule {
+                RetentionRule::MaxAge(max_age) => {
+                    let expired = !recording.is_active()
+                        && recording.stop_timestamp >= 0
+                        && now_ms.saturating_sub(recording.stop_timestamp)
+                            > max_age.as_millis() as i64;
+                    expired.then_some(stop_position)
+                }
+                RetentionRule::MaxBytes(max_bytes) => {
+                    Some(stop_position.saturating_sub(max_bytes.max(0)))
+                }
+                RetentionRule::BeforePosition {
+                    recording_id,
+                    position,
+                } => recording_id
+                    .map_or(true, |id| id == recording.recording_id)
+                    .then_some(position),
+            })
+            .max()?
+            .min(stop_position);
+
+        // the archive only accepts the first byte of a segment after the current start position
+        let new_start_position = recording.segment_base_position(target);
+        if new_start_position <= recording.start_position {
+            return None;
+        }
+
+        let first_segment = recording.segment_base_position(recording.start_position);
+        Some(SegmentRemoval {
+            recording_id: recording.recording_id,
+            stream_id: recording.stream_id,
+            stripped_channel: recording.stripped_channel.clone(),
+            start_position: recording.start_position,
+            new_start_position,
+            segments: (new_start_position - first_segment) / recording.segment_file_length as i64,
+        })
+    }
+
+    /// Evaluate the policy against the recording catalog without changing anything.
+    pub fn plan(&self, archive: &AeronArchive) -> Result<Vec<SegmentRemoval>, AeronCError> {
+        let mut recordings = vec![];
+        let mut count = 0;
+        archive.list_recordings_once(&mut count, 0, i32::MAX, |d| {
+            recordings.push(RecordingSegments::from(&d));
+        })?;
+
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or_default();
+
+        let mut removals = vec![];
+        for recording in recordings.iter().filter(|r| self.matches(r)) {
+            let stop_position = if recording.is_active() {
+                archive.get_max_recorded_position(recording.recording_id)?
+            } else {
+                recording.stop_position
+            };
+            if let Some(removal) = self.evaluate(recording, stop_position, now_ms) {
+                removals.push(removal);
+            }
+        }
+        Ok(removals)
+    }
+
+    /// Evaluate the policy and, unless in dry-run mode, detach or purge the selected segments.
+    ///
+    /// A recording the archive refuses to truncate (for example because it is being replayed) is
+    /// reported as [`RetentionOutcome::Failed`] and does not stop the remaining recordings from
+    /// being processed.
+    pub fn apply(&self, archive: &AeronArchive) -> Result<RetentionReport, AeronCError> {
+        let mut report = RetentionReport {
+            dry_run: self.dry_run,
+            entries: vec![],
+        };
+
+        for removal in self.plan(archive)? {
+            let outcome = if self.dry_run {
+                RetentionOutcome::DryRun
+            } else {
+                let result = match self.action {
+                    RetentionAction::Detach => archive
+                        .detach_segments(removal.recording_id, removal.new_start_position)
+                        .map(|_| RetentionOutcome::Detached),
+                    RetentionAction::Purge => archive
+                        .purge_segments(removal.recording_id, removal.new_start_position)
+                        .map(RetentionOutcome::Purged),
+                };
+                result.unwrap_or_else(|e| {
+                    warn!(
+                        "failed to remove segments of recording {} up to {}: {:?}",
+                        removal.recording_id, removal.new_start_position, e
+                    );
+                    RetentionOutcome::Failed(e)
+                })
+            };
+            debug!("retention {:?} -> {:?}", removal, outcome);
+            report.entries.push(RetentionEntry { removal, outcome });
+        }
+
+        Ok(report)
+    }
+}
@@ -0,0 +1,125 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rusteron_client::*;
+use rusteron_media_driver::{AeronDriver, AeronDriverContext};
+use std::hint::black_box;
+use std::io::IoSlice;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+const STREAM_ID: i32 = 1005;
+const HEADER_LENGTH: usize = 16;
+const PAYLOAD_LENGTHS: [usize; 3] = [32, 256, 1024];
+const FRAGMENT_COUNT_LIMIT: usize = 256;
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let ctx = AeronDriverContext::new().unwrap();
+    ctx.set_dir(&format!("{}{}", ctx.get_dir(), Aeron::nano_clock()).into_c_string())
+        .unwrap();
+    ctx.set_dir_delete_on_start(true).unwrap();
+    ctx.set_dir_delete_on_shutdown(true).unwrap();
+    let dir = ctx.get_dir().to_string().leak();
+    let dir2 = ctx.get_dir().to_string().leak();
+    let (stop, _handle) = AeronDriver::launch_embedded(ctx.clone(), false);
+    let stop2 = stop.clone();
+    let _drain_thread = thread::Builder::new()
+        .name("drain".to_string())
+        .spawn(move || run_drain(stop2, dir).unwrap())
+        .unwrap();
+
+    let context = AeronContext::new().unwrap();
+    context.set_idle_sleep_duration_ns(0).unwrap();
+    context.set_dir(&dir2.into_c_string()).unwrap();
+    let aeron = Aeron::new(&context).unwrap();
+    aeron.start().unwrap();
+
+    let publication = aeron
+        .async_add_exclusive_publication(AERON_IPC_STREAM, STREAM_ID)
+        .unwrap()
+        .poll_blocking(Duration::from_secs(5))
+        .unwrap();
+    while !publication.is_connected() {
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    let header = [1u8; HEADER_LENGTH];
+    let mut group = c.benchmark_group("offer_header_and_payload");
+    for payload_length in PAYLOAD_LENGTHS {
+        let payload = vec![2u8; payload_length];
+        let mut contiguous = vec![0u8; HEADER_LENGTH + payload_length];
+
+        group.bench_with_input(
+            BenchmarkId::new("copy_then_offer", payload_length),
+            &payload,
+            |b, payload| {
+                b.iter(|| {
+                    contiguous[..HEADER_LENGTH].copy_from_slice(&header);
+                    contiguous[HEADER_LENGTH..].copy_from_slice(payload);
+                    while publication
+                        .offer(&contiguous, Handlers::no_reserved_value_supplier_handler())
+                        < 0
+                    {}
+                });
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("offer_vectored", payload_length),
+            &payload,
+            |b, payload| {
+                b.iter(|| {
+                    let segments: [&[u8]; 2] = [&header, payload];
+                    while publication
+                        .offer_vectored(&segments, Handlers::no_reserved_value_supplier_handler())
+                        < 0
+                    {}
+                });
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("offer_io_slices", payload_length),
+            &payload,
+            |b, payload| {
+                b.iter(|| {
+                    let segments = [IoSlice::new(&header), IoSlice::new(payload)];
+                    while publication
+                        .offer_io_slices(&segments, Handlers::no_reserved_value_supplier_handler())
+                        < 0
+                    {}
+                });
+            },
+        );
+    }
+    group.finish();
+
+    stop.store(true, Ordering::SeqCst);
+}
+
+fn run_drain(stop: Arc<AtomicBool>, dir: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let context = AeronContext::new()?;
+    context.set_dir(&dir.into_c_string())?;
+    context.set_idle_sleep_duration_ns(0)?;
+    let aeron = Aeron::new(&context)?;
+    aeron.start()?;
+    let subscription = aeron
+        .async_add_subscription(
+            AERON_IPC_STREAM,
+            STREAM_ID,
+            Handlers::no_available_image_handler(),
+            Handlers::no_unavailable_image_handler(),
+        )?
+        .poll_blocking(Duration::from_secs(5))?;
+
+    while !stop.load(Ordering::Acquire) {
+        let _ = subscription.poll_once(
+            |buffer, _header| {
+                black_box(buffer);
+            },
+            FRAGMENT_COUNT_LIMIT,
+        );
+    }
+    Ok(())
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
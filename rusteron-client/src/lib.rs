@@ -19,6 +19,8 @@ pub mod bindings {
 
 use bindings::*;
 
+pub mod stream_monitor;
+
 include!(concat!(env!("OUT_DIR"), "/aeron.rs"));
 include!(concat!(env!("OUT_DIR"), "/aeron_custom.rs"));
 
@@ -535,6 +537,558 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    #[serial]
+    pub fn typed_counters() -> Result<(), Box<dyn error::Error>> {
+        counter_key! {
+            #[derive(Debug, Clone, Copy, PartialEq)]
+            struct StrategyKey {
+                strategy_id: i64,
+                venue: u16,
+                enabled: bool,
+            }
+        }
+        const STRATEGY_FILLS: i32 = 1101;
+
+        let (media_driver_ctx, stop, driver_handle) = start_media_driver(43)?;
+        let (_ctx, aeron) = create_client(&media_driver_ctx)?;
+
+        let key = StrategyKey {
+            strategy_id: 7,
+            venue: 3,
+            enabled: true,
+        };
+        let fills =
+            aeron.add_typed_counter(STRATEGY_FILLS, key, "fills", Duration::from_secs(5))?;
+        let other = aeron.add_typed_counter(
+            STRATEGY_FILLS,
+            StrategyKey {
+                strategy_id: 8,
+                ..key
+            },
+            "fills",
+            Duration::from_secs(5),
+        )?;
+
+        assert_eq!(0, fills.increment());
+        assert_eq!(1, fills.get_and_add(10));
+        assert_eq!(11, fills.get());
+        fills.set(20);
+        other.set_ordered(5);
+        assert_eq!(20, fills.get());
+
+        let reader = aeron.counters_reader();
+        let found = reader.find::<StrategyKey>(STRATEGY_FILLS);
+        assert_eq!(
+            vec![
+                (fills.counter_id(), key),
+                (other.counter_id(), *other.key())
+            ],
+            found
+        );
+        assert_eq!(
+            Some(other.counter_id()),
+            reader.find_by_key(STRATEGY_FILLS, other.key())
+        );
+        assert_eq!(5, reader.get_counter_value(other.counter_id()));
+        assert_eq!(None, reader.find_by_key(STRATEGY_FILLS + 1, &key));
+
+        assert!(aeron
+            .add_typed_counter(
+                STRATEGY_FILLS,
+                [0i64; 15],
+                "too long",
+                Duration::from_secs(5)
+            )
+            .is_err());
+
+        stop.store(true, Ordering::SeqCst);
+        let _ = driver_handle.join().unwrap();
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    pub fn counters_reader_iter() -> Result<(), Box<dyn error::Error>> {
+        let (media_driver_ctx, stop, driver_handle) = start_media_driver(44)?;
+        let (_ctx, aeron) = create_client(&media_driver_ctx)?;
+
+        let publisher = aeron.add_publication(AERON_IPC_STREAM, 1001, Duration::from_secs(5))?;
+        let counter = aeron.add_typed_counter(1102, 42i64, "typed", Duration::from_secs(5))?;
+        counter.set(7);
+
+        let reader = aeron.counters_reader();
+        let counters = reader.iter().collect::<Vec<_>>();
+        assert!(counters
+            .iter()
+            .any(|info| { info.system_counter_type() == Some(AeronSystemCounterType::BytesSent) }));
+        assert!(counters
+            .windows(2)
+            .all(|pair| pair[0].counter_id < pair[1].counter_id));
+
+        let limit_id = publisher.get_constants()?.publication_limit_counter_id();
+        let limit = reader.counter_info(limit_id).expect("publisher limit");
+        assert_eq!(PUBLISHER_LIMIT_TYPE_ID, limit.type_id);
+        let key = limit.stream_key().expect("stream key");
+        assert_eq!((1001, "aeron:ipc"), (key.stream_id, key.channel.as_str()));
+        assert!(reader
+            .by_type_id(PUBLISHER_LIMIT_TYPE_ID)
+            .any(|info| info.counter_id == limit_id));
+        assert_eq!(None, limit.recording_position_key());
+
+        let info = reader
+            .by_registration_id(1102, counter.registration_id())
+            .expect("typed counter");
+        assert_eq!(counter.counter_id(), info.counter_id);
+        assert_eq!(CounterState::Allocated, info.state);
+        assert_eq!(None, info.free_for_reuse_deadline_ms);
+        assert_eq!(aeron.client_id(), info.owner_id);
+        assert_eq!((7, "typed"), (info.value, info.label.as_str()));
+        assert_eq!(Some(42i64), info.key_as());
+        assert_eq!(None, reader.by_registration_id(1102, -1));
+        assert_eq!(None, reader.counter_info(reader.max_counter_id() + 1));
+
+        // keys are zero padded, so archives without an id must not decode as archive id 0
+        let mut recording_key = RecordingPositionKey {
+            recording_id: 3,
+            session_id: 4,
+            source_identity: "127.0.0.1:20121".to_string(),
+            archive_id: None,
+        };
+        for archive_id in [None, Some(9)] {
+            recording_key.archive_id = archive_id;
+            let mut key = vec![];
+            recording_key.encode(&mut key);
+            key.resize(112, 0);
+            assert_eq!(
+                Some(recording_key.clone()),
+                RecordingPositionKey::decode(&mut key.as_slice())
+            );
+        }
+
+        stop.store(true, Ordering::SeqCst);
+        let _ = driver_handle.join().unwrap();
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    pub fn stream_monitor() -> Result<(), Box<dyn error::Error>> {
+        use crate::stream_monitor::*;
+
+        let (media_driver_ctx, stop, driver_handle) = start_media_driver(45)?;
+        let (_ctx, aeron) = create_client(&media_driver_ctx)?;
+
+        let publisher = aeron.add_publication(AERON_IPC_STREAM, 1002, Duration::from_secs(5))?;
+        let subscription = aeron.add_subscription(
+            AERON_IPC_STREAM,
+            1002,
+            Handlers::no_available_image_handler(),
+            Handlers::no_unavailable_image_handler(),
+            Duration::from_secs(5),
+        )?;
+        let start = Instant::now();
+        while !publisher.is_connected() && start.elapsed() < Duration::from_secs(5) {
+            sleep(Duration::from_millis(10));
+        }
+
+        let alerts = Arc::new(AtomicUsize::new(0));
+        let mut monitor = StreamMonitor::new(aeron.counters_reader(), "aeron:ipc", 1002);
+        {
+            let alerts = alerts.clone();
+            monitor.on_lag_exceeded(LagKind::Subscriber, 1024, move |_session, lag| {
+                assert!(lag > 1024);
+                alerts.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+
+        let sessions = monitor.poll();
+        assert_eq!(1, sessions.len());
+        let session = &sessions[0];
+        assert_eq!(publisher.session_id(), session.session_id);
+        assert_eq!(1, session.subscribers.len());
+        assert_eq!(Some(0), session.subscriber_lag());
+        assert!(session.publisher_window().unwrap_or_default() > 0);
+        assert_eq!(None, session.sender_position);
+
+        let message = [1u8; 256];
+        for _ in 0..16 {
+            while publisher.offer(&message, Handlers::no_reserved_value_supplier_handler()) < 0 {
+                sleep(Duration::from_millis(1));
+            }
+        }
+        monitor.poll();
+        monitor.poll();
+        let session = monitor.session(publisher.session_id()).unwrap();
+        assert!(session.subscriber_lag().unwrap() > 1024);
+        assert!(session.publish_rate.is_some());
+        assert_eq!(1, alerts.load(Ordering::SeqCst));
+
+        let start = Instant::now();
+        while monitor.poll()[0].subscriber_lag() != Some(0)
+            && start.elapsed() < Duration::from_secs(5)
+        {
+            subscription.poll_once(|_msg, _header| {}, 100)?;
+        }
+        assert_eq!(
+            Some(0),
+            monitor
+                .session(publisher.session_id())
+                .unwrap()
+                .subscriber_lag()
+        );
+        assert!(
+            StreamMonitor::new(aeron.counters_reader(), "aeron:ipc", 1003)
+                .poll()
+                .is_empty()
+        );
+
+        stop.store(true, Ordering::SeqCst);
+        let _ = driver_handle.join().unwrap();
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    pub fn publication_error_on_image_reject() -> Result<(), Box<dyn error::Error>> {
+        let (media_driver_ctx, stop, driver_handle) = start_media_driver(46)?;
+        let ctx = AeronContext::new()?;
+        ctx.set_dir(&media_driver_ctx.get_dir().into_c_string())?;
+        ctx.set_error_handler(Some(&Handler::leak(TestErrorCount::default())))?;
+        let errors = Arc::new(std::sync::Mutex::new(vec![]));
+        {
+            let errors = errors.clone();
+            ctx.on_publication_error(move |error| errors.lock().unwrap().push(error))?;
+        }
+        let aeron = Aeron::new(&ctx)?;
+        aeron.start()?;
+
+        let channel = "aeron:udp?endpoint=localhost:21520".into_c_string();
+        let publisher = aeron.add_publication(&channel, 1004, Duration::from_secs(5))?;
+        let subscription = aeron.add_subscription(
+            &channel,
+            1004,
+            Handlers::no_available_image_handler(),
+            Handlers::no_unavailable_image_handler(),
+            Duration::from_secs(5),
+        )?;
+        let start = Instant::now();
+        while !publisher.is_connected() && start.elapsed() < Duration::from_secs(5) {
+            sleep(Duration::from_millis(10));
+        }
+        assert!(subscription.reject_image(-1, "no such image").is_err());
+
+        while publisher.offer(b"poison", Handlers::no_reserved_value_supplier_handler()) < 0 {
+            sleep(Duration::from_millis(1));
+        }
+        let mut poisoned = None;
+        let start = Instant::now();
+        while poisoned.is_none() && start.elapsed() < Duration::from_secs(5) {
+            subscription.poll_once(
+                |msg, header| {
+                    if msg == b"poison" {
+                        poisoned = header.get_values().ok().map(|v| v.frame().session_id());
+                    }
+                },
+                10,
+            )?;
+        }
+        let session_id = poisoned.expect("poison message");
+        subscription.reject_image(session_id, "poison message")?;
+
+        let start = Instant::now();
+        while errors.lock().unwrap().is_empty() && start.elapsed() < Duration::from_secs(5) {
+            sleep(Duration::from_millis(10));
+        }
+        let error = errors
+            .lock()
+            .unwrap()
+            .first()
+            .cloned()
+            .expect("error frame");
+        assert_eq!(
+            (publisher.session_id(), 1004),
+            (error.session_id, error.stream_id)
+        );
+        assert!(error.error_message.contains("poison message"));
+        assert!(error.source_address.is_some());
+
+        stop.store(true, Ordering::SeqCst);
+        let _ = driver_handle.join().unwrap();
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    pub fn offer_vectored() -> Result<(), Box<dyn error::Error>> {
+        let (media_driver_ctx, stop, driver_handle) = start_media_driver(47)?;
+        let (_ctx, aeron) = create_client(&media_driver_ctx)?;
+
+        let publisher = aeron.add_publication(AERON_IPC_STREAM, 1006, Duration::from_secs(5))?;
+        let exclusive =
+            aeron.add_exclusive_publication(AERON_IPC_STREAM, 1006, Duration::from_secs(5))?;
+        let subscription = aeron.add_subscription(
+            AERON_IPC_STREAM,
+            1006,
+            Handlers::no_available_image_handler(),
+            Handlers::no_unavailable_image_handler(),
+            Duration::from_secs(5),
+        )?;
+        let start = Instant::now();
+        while !(publisher.is_connected() && exclusive.is_connected())
+            && start.elapsed() < Duration::from_secs(5)
+        {
+            sleep(Duration::from_millis(10));
+        }
+
+        let header = b"header:".as_slice();
+        let many = vec![b"x".as_slice(); OFFER_VECTORED_STACK_SEGMENTS + 1];
+        let offer = |result: &dyn Fn() -> i64| {
+            while result() < 0 {
+                sleep(Duration::from_millis(1));
+            }
+        };
+        offer(&|| {
+            publisher.offer_vectored(
+                &[header, b"shared"],
+                Handlers::no_reserved_value_supplier_handler(),
+            )
+        });
+        offer(&|| {
+            exclusive.offer_io_slices(
+                &[
+                    std::io::IoSlice::new(header),
+                    std::io::IoSlice::new(b"exclusive"),
+                ],
+                Handlers::no_reserved_value_supplier_handler(),
+            )
+        });
+        offer(&|| exclusive.offer_vectored(&many, Handlers::no_reserved_value_supplier_handler()));
+
+        let mut received = vec![];
+        let start = Instant::now();
+        while received.len() < 3 && start.elapsed() < Duration::from_secs(5) {
+            subscription.poll_once(
+                |msg, _header| received.push(String::from_utf8_lossy(msg).to_string()),
+                10,
+            )?;
+        }
+        received.sort();
+        assert_eq!(
+            vec![
+                "header:exclusive".to_string(),
+                "header:shared".to_string(),
+                "x".repeat(OFFER_VECTORED_STACK_SEGMENTS + 1)
+            ],
+            received
+        );
+
+        stop.store(true, Ordering::SeqCst);
+        let _ = driver_handle.join().unwrap();
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    pub fn reserved_value_suppliers() -> Result<(), Box<dyn error::Error>> {
+        let (media_driver_ctx, stop, driver_handle) = start_media_driver(48)?;
+        let (_ctx, aeron) = create_client(&media_driver_ctx)?;
+
+        let publisher = aeron.add_publication(AERON_IPC_STREAM, 1007, Duration::from_secs(5))?;
+        let subscription = aeron.add_subscription(
+            AERON_IPC_STREAM,
+            1007,
+            Handlers::no_available_image_handler(),
+            Handlers::no_unavailable_image_handler(),
+            Duration::from_secs(5),
+        )?;
+        let start = Instant::now();
+        while !publisher.is_connected() && start.elapsed() < Duration::from_secs(5) {
+            sleep(Duration::from_millis(10));
+        }
+
+        let crc32 = Handler::leak(ReservedValueSupplier::crc32());
+        let timestamp = Handler::leak(ReservedValueSupplier::timestamp(
+            ReservedValueClock::NanoClock,
+        ));
+        while publisher.offer(b"checksummed", Some(&crc32)) < 0 {
+            sleep(Duration::from_millis(1));
+        }
+        while publisher.offer(b"timestamped", Some(&timestamp)) < 0 {
+            sleep(Duration::from_millis(1));
+        }
+        while publisher.offer_with_reserved_value(b"closure", |payload| payload.len() as i64) < 0 {
+            sleep(Duration::from_millis(1));
+        }
+
+        let mut received = vec![];
+        let start = Instant::now();
+        while received.len() < 3 && start.elapsed() < Duration::from_secs(5) {
+            subscription.poll_once(
+                |msg, header| {
+                    received.push((
+                        String::from_utf8_lossy(msg).to_string(),
+                        header.verify_crc32(msg),
+                        header.latency(ReservedValueClock::NanoClock),
+                        header.reserved_value().unwrap(),
+                    ))
+                },
+                10,
+            )?;
+        }
+        assert_eq!(3, received.len());
+        assert_eq!("checksummed", received[0].0);
+        assert!(received[0].1);
+        assert_eq!("timestamped", received[1].0);
+        assert!(!received[1].1);
+        assert!(received[1]
+            .2
+            .is_some_and(|latency| latency < Duration::from_secs(5)));
+        assert_eq!(
+            ("closure".to_string(), 7),
+            (received[2].0.clone(), received[2].3)
+        );
+
+        stop.store(true, Ordering::SeqCst);
+        let _ = driver_handle.join().unwrap();
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    pub fn exclusive_publication_blocks() -> Result<(), Box<dyn error::Error>> {
+        let (media_driver_ctx, stop, driver_handle) = start_media_driver(49)?;
+        let (_ctx, aeron) = create_client(&media_driver_ctx)?;
+
+        let term_length = TermLength::new(64 * 1024)?;
+        let resume_position = 2 * 64 * 1024 + 1024;
+        let publisher = AeronExclusivePublication::resume_at(
+            &aeron,
+            AERON_IPC_STREAM,
+            1008,
+            resume_position,
+            100,
+            term_length,
+            Duration::from_secs(5),
+        )?;
+        assert_eq!(resume_position, publisher.position());
+        let subscription = aeron.add_subscription(
+            AERON_IPC_STREAM,
+            1008,
+            Handlers::no_available_image_handler(),
+            Handlers::no_unavailable_image_handler(),
+            Duration::from_secs(5),
+        )?;
+        let start = Instant::now();
+        while !publisher.is_connected() && start.elapsed() < Duration::from_secs(5) {
+            sleep(Duration::from_millis(10));
+        }
+
+        let target = publisher.block_target()?;
+        assert_eq!(102, target.term_id);
+        assert_eq!(1024, target.term_offset);
+        let payload = b"pre-framed";
+        let frame_length = (DATA_HEADER_LENGTH + payload.len()) as i32;
+        let mut block = vec![0u8; (frame_length as usize).next_multiple_of(FRAME_ALIGNMENT)];
+        block[0..4].copy_from_slice(&frame_length.to_le_bytes());
+        block[5] = 0xC0; // unfragmented
+        block[6..8].copy_from_slice(&HDR_TYPE_DATA.to_le_bytes());
+        block[8..12].copy_from_slice(&target.term_offset.to_le_bytes());
+        block[12..16].copy_from_slice(&target.session_id.to_le_bytes());
+        block[16..20].copy_from_slice(&target.stream_id.to_le_bytes());
+        block[20..24].copy_from_slice(&target.term_id.to_le_bytes());
+        block[DATA_HEADER_LENGTH..frame_length as usize].copy_from_slice(payload);
+
+        let mut wrong_session = block.clone();
+        wrong_session[12..16].copy_from_slice(&(target.session_id + 1).to_le_bytes());
+        assert!(matches!(
+            publisher.offer_block_checked(&wrong_session),
+            Err(BlockError::Mismatch {
+                field: "session id",
+                ..
+            })
+        ));
+        assert_eq!(
+            resume_position + block.len() as i64,
+            publisher.offer_block_checked(&block)?
+        );
+        assert!(matches!(
+            publisher.append_padding_checked(usize::MAX),
+            Err(BlockError::InvalidPaddingLength { .. })
+        ));
+        let padded = publisher.append_padding_checked(64)?;
+        assert!(padded > resume_position + block.len() as i64);
+
+        let mut received = vec![];
+        let start = Instant::now();
+        while received.is_empty() && start.elapsed() < Duration::from_secs(5) {
+            subscription.poll_once(|msg, _header| received.push(msg.to_vec()), 10)?;
+        }
+        assert_eq!(vec![payload.to_vec()], received);
+
+        stop.store(true, Ordering::SeqCst);
+        let _ = driver_handle.join().unwrap();
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    pub fn image_unavailable_reasons() -> Result<(), Box<dyn error::Error>> {
+        let (media_driver_ctx, stop, driver_handle) = start_media_driver(50)?;
+        let (_ctx, aeron) = create_client(&media_driver_ctx)?;
+
+        let reasons = Arc::new(std::sync::Mutex::new(vec![]));
+        let on_unavailable = Handler::leak(UnavailableImageWithReason::new({
+            let reasons = reasons.clone();
+            move |_subscription, _image, reason| reasons.lock().unwrap().push(reason)
+        }));
+        let subscription = aeron.add_subscription(
+            AERON_IPC_STREAM,
+            1009,
+            Handlers::no_available_image_handler(),
+            Some(&on_unavailable),
+            Duration::from_secs(5),
+        )?;
+        let await_reasons = |count: usize| {
+            let start = Instant::now();
+            while reasons.lock().unwrap().len() < count && start.elapsed() < Duration::from_secs(10)
+            {
+                let _ = subscription.poll_once(|_msg, _header| {}, 10);
+                sleep(Duration::from_millis(10));
+            }
+        };
+
+        let publisher =
+            aeron.add_exclusive_publication(AERON_IPC_STREAM, 1009, Duration::from_secs(5))?;
+        while !publisher.is_connected() {
+            sleep(Duration::from_millis(10));
+        }
+        publisher.close_with_no_args()?;
+        await_reasons(1);
+
+        let publisher =
+            aeron.add_exclusive_publication(AERON_IPC_STREAM, 1009, Duration::from_secs(5))?;
+        while !publisher.is_connected() {
+            sleep(Duration::from_millis(10));
+        }
+        publisher.revoke_on_drop(true);
+        assert!(publisher.is_revoke_on_drop());
+        drop(publisher);
+        await_reasons(2);
+
+        assert_eq!(
+            vec![
+                ImageUnavailableReason::EndOfStream,
+                ImageUnavailableReason::Revoked
+            ],
+            *reasons.lock().unwrap()
+        );
+
+        stop.store(true, Ordering::SeqCst);
+        let _ = driver_handle.join().unwrap();
+        Ok(())
+    }
+
     /// A simple error counter for testing error callback invocation.
     #[derive(Default, Debug)]
     struct TestErrorCount {
@@ -0,0 +1,293 @@
+//! Position and lag monitoring of a stream, built on the driver's per stream counters.
+//!
+//! [`StreamMonitor`] correlates the publisher position and limit, sender position, receiver high
+//! water mark and subscriber positions of each session of a channel and stream id, so lag can be
+//! read without knowing counter type ids or parsing labels.
+//!
+//! ```no_run
+//! # use rusteron_client::*;
+//! # use rusteron_client::stream_monitor::*;
+//! # fn run(aeron: &Aeron) {
+//! let mut monitor = StreamMonitor::new(aeron.counters_reader(), "aeron:ipc", 1001);
+//! monitor.on_lag_exceeded(LagKind::Subscriber, 1024 * 1024, |session, lag| {
+//!     log::warn!("session {} subscriber lagging by {lag} bytes", session.session_id);
+//! });
+//! for session in monitor.poll() {
+//!     println!("{session:?} lag={:?}", session.subscriber_lag());
+//! }
+//! # }
+//! ```
+use crate::{
+    AeronCountersReader, ChannelUri, Media, PUBLISHER_LIMIT_TYPE_ID, PUBLISHER_POSITION_TYPE_ID,
+    RECEIVER_HWM_TYPE_ID, RECEIVER_POSITION_TYPE_ID, SENDER_POSITION_TYPE_ID,
+    SUBSCRIBER_POSITION_TYPE_ID,
+};
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+
+/// Position of one subscription's image of a session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubscriberPosition {
+    /// registration id of the subscription
+    pub registration_id: i64,
+    pub counter_id: i32,
+    pub position: i64,
+}
+
+/// Positions of a session of the monitored stream, `None` for counters the driver doesn't have,
+/// e.g. sender and receiver positions of an IPC stream.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionPositions {
+    pub session_id: i32,
+    pub publisher_position: Option<i64>,
+    /// position the publisher can publish up to before it's back pressured
+    pub publisher_limit: Option<i64>,
+    pub sender_position: Option<i64>,
+    pub receiver_hwm: Option<i64>,
+    pub receiver_position: Option<i64>,
+    pub subscribers: Vec<SubscriberPosition>,
+    /// bytes per second the head advanced since the previous poll
+    pub publish_rate: Option<f64>,
+    /// bytes per second the slowest subscriber advanced since the previous poll
+    pub consume_rate: Option<f64>,
+}
+
+impl SessionPositions {
+    fn new(session_id: i32) -> Self {
+        Self {
+            session_id,
+            publisher_position: None,
+            publisher_limit: None,
+            sender_position: None,
+            receiver_hwm: None,
+            receiver_position: None,
+            subscribers: vec![],
+            publish_rate: None,
+            consume_rate: None,
+        }
+    }
+
+    /// Furthest known position of the stream, from the publisher position, or the receiver high
+    /// water mark when the publisher is in another driver.
+    pub fn head(&self) -> Option<i64> {
+        [
+            self.publisher_position,
+            self.receiver_hwm,
+            self.sender_position,
+        ]
+        .into_iter()
+        .flatten()
+        .max()
+    }
+
+    pub fn slowest_subscriber(&self) -> Option<&SubscriberPosition> {
+        self.subscribers.iter().min_by_key(|s| s.position)
+    }
+
+    /// Bytes the slowest subscriber is behind the head.
+    pub fn subscriber_lag(&self) -> Option<i64> {
+        Some(self.head()? - self.slowest_subscriber()?.position)
+    }
+
+    /// Bytes published but not yet sent.
+    pub fn sender_lag(&self) -> Option<i64> {
+        Some(self.publisher_position? - self.sender_position?)
+    }
+
+    /// Bytes the publisher can publish before it's back pressured.
+    pub fn publisher_window(&self) -> Option<i64> {
+        Some(self.publisher_limit? - self.publisher_position?)
+    }
+
+    pub fn lag(&self, kind: LagKind) -> Option<i64> {
+        match kind {
+            LagKind::Subscriber => self.subscriber_lag(),
+            LagKind::Sender => self.sender_lag(),
+        }
+    }
+}
+
+/// Lag a [`StreamMonitor::on_lag_exceeded`] threshold applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LagKind {
+    /// [`SessionPositions::subscriber_lag`]
+    Subscriber,
+    /// [`SessionPositions::sender_lag`]
+    Sender,
+}
+
+struct LagAlert {
+    kind: LagKind,
+    threshold: i64,
+    callback: Box<dyn FnMut(&SessionPositions, i64)>,
+    /// sessions over the threshold, which are alerted again once they recover
+    exceeded: HashSet<i32>,
+}
+
+/// Media, endpoint and control address, which identify a channel regardless of other params.
+type ChannelIdentity = (Option<Media>, Option<String>, Option<String>);
+
+fn channel_identity(uri: &ChannelUri) -> ChannelIdentity {
+    (
+        uri.media(),
+        uri.endpoint().map(str::to_string),
+        uri.control().map(str::to_string),
+    )
+}
+
+/// Positions and lag of each session of a channel and stream id, refreshed by
+/// [`poll`](Self::poll).
+pub struct StreamMonitor {
+    counters_reader: AeronCountersReader,
+    channel: String,
+    identity: Option<ChannelIdentity>,
+    stream_id: i32,
+    sessions: Vec<SessionPositions>,
+    previous: HashMap<i32, (Instant, Option<i64>, Option<i64>)>,
+    alerts: Vec<LagAlert>,
+}
+
+impl StreamMonitor {
+    /// Monitors `stream_id` on `channel`. Counters of other channels with the same media,
+    /// endpoint and control address are included, e.g. a subscription's channel with different
+    /// params to the publication's.
+    pub fn new(counters_reader: AeronCountersReader, channel: &str, stream_id: i32) -> Self {
+        Self {
+            counters_reader,
+            channel: channel.to_string(),
+            identity: ChannelUri::parse(channel)
+                .ok()
+                .map(|uri| channel_identity(&uri)),
+            stream_id,
+            sessions: vec![],
+            previous: HashMap::new(),
+            alerts: vec![],
+        }
+    }
+
+    pub fn channel(&self) -> &str {
+        &self.channel
+    }
+
+    pub fn stream_id(&self) -> i32 {
+        self.stream_id
+    }
+
+    /// Calls `callback` with the session and its lag when a session's lag goes above `threshold`
+    /// bytes. It isn't called again for the session until the lag has dropped back to `threshold`.
+    pub fn on_lag_exceeded(
+        &mut self,
+        kind: LagKind,
+        threshold: i64,
+        callback: impl FnMut(&SessionPositions, i64) + 'static,
+    ) -> &mut Self {
+        self.alerts.push(LagAlert {
+            kind,
+            threshold,
+            callback: Box::new(callback),
+            exceeded: HashSet::new(),
+        });
+        self
+    }
+
+    /// Sessions as of the last [`poll`](Self::poll), ordered by session id.
+    pub fn sessions(&self) -> &[SessionPositions] {
+        &self.sessions
+    }
+
+    pub fn session(&self, session_id: i32) -> Option<&SessionPositions> {
+        self.sessions.iter().find(|s| s.session_id == session_id)
+    }
+
+    /// Reads the stream's counters, updating rates and calling lag callbacks.
+    pub fn poll(&mut self) -> &[SessionPositions] {
+        let now = Instant::now();
+        let mut sessions: HashMap<i32, SessionPositions> = HashMap::new();
+        for info in self.counters_reader.iter() {
+            if !info.is_allocated() {
+                continue;
+            }
+            let Some(key) = info.stream_key() else {
+                continue;
+            };
+            if key.stream_id != self.stream_id || !self.is_same_channel(&key.channel) {
+                continue;
+            }
+            let session = sessions
+                .entry(key.session_id)
+                .or_insert_with(|| SessionPositions::new(key.session_id));
+            match info.type_id {
+                PUBLISHER_POSITION_TYPE_ID => session.publisher_position = Some(info.value),
+                PUBLISHER_LIMIT_TYPE_ID => session.publisher_limit = Some(info.value),
+                SENDER_POSITION_TYPE_ID => session.sender_position = Some(info.value),
+                RECEIVER_HWM_TYPE_ID => session.receiver_hwm = Some(info.value),
+                RECEIVER_POSITION_TYPE_ID => session.receiver_position = Some(info.value),
+                SUBSCRIBER_POSITION_TYPE_ID => session.subscribers.push(SubscriberPosition {
+                    registration_id: key.registration_id,
+                    counter_id: info.counter_id,
+                    position: info.value,
+                }),
+                _ => {}
+            }
+        }
+
+        let mut sessions = sessions.into_values().collect::<Vec<_>>();
+        sessions.sort_by_key(|s| s.session_id);
+        let rate = |from: Option<i64>, to: Option<i64>, seconds: f64| match (from, to) {
+            (Some(from), Some(to)) if seconds > 0.0 => Some((to - from) as f64 / seconds),
+            _ => None,
+        };
+        for session in &mut sessions {
+            let head = session.head();
+            let tail = session.slowest_subscriber().map(|s| s.position);
+            if let Some((at, previous_head, previous_tail)) = self.previous.get(&session.session_id)
+            {
+                let seconds = now.duration_since(*at).as_secs_f64();
+                session.publish_rate = rate(*previous_head, head, seconds);
+                session.consume_rate = rate(*previous_tail, tail, seconds);
+            }
+            self.previous.insert(session.session_id, (now, head, tail));
+        }
+        self.previous
+            .retain(|session_id, _| sessions.iter().any(|s| s.session_id == *session_id));
+
+        for alert in &mut self.alerts {
+            alert
+                .exceeded
+                .retain(|session_id| sessions.iter().any(|s| s.session_id == *session_id));
+            for session in &sessions {
+                match session.lag(alert.kind) {
+                    Some(lag) if lag > alert.threshold => {
+                        if alert.exceeded.insert(session.session_id) {
+                            (alert.callback)(session, lag);
+                        }
+                    }
+                    _ => {
+                        alert.exceeded.remove(&session.session_id);
+                    }
+                }
+            }
+        }
+
+        self.sessions = sessions;
+        &self.sessions
+    }
+
+    fn is_same_channel(&self, channel: &str) -> bool {
+        match (&self.identity, ChannelUri::parse(channel)) {
+            (Some(identity), Ok(uri)) => *identity == channel_identity(&uri),
+            // channels are truncated to fit counter keys
+            _ => channel == self.channel || self.channel.starts_with(channel),
+        }
+    }
+}
+
+impl std::fmt::Debug for StreamMonitor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StreamMonitor")
+            .field("channel", &self.channel)
+            .field("stream_id", &self.stream_id)
+            .field("sessions", &self.sessions)
+            .finish()
+    }
+}
@@ -73,6 +73,20 @@ impl AeronCnc {
     }
 }
 
+impl AeronContext {
+    /// Asks the driver using `aeron_dir` to terminate. The driver only terminates if its
+    /// termination validator accepts `token`, by default every request is denied.
+    pub fn request_driver_termination_with_token(
+        aeron_dir: &str,
+        token: &[u8],
+    ) -> Result<(), AeronCError> {
+        let dir = std::ffi::CString::new(aeron_dir)
+            .map_err(|_| AeronCError::from_code(PARSE_CSTR_ERROR_CODE))?;
+        Self::request_driver_termination(&dir, token.as_ptr(), token.len())?;
+        Ok(())
+    }
+}
+
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
 pub enum AeronSystemCounterType {
@@ -293,6 +307,221 @@ impl AeronSubscription {
     }
 }
 
+impl AeronSubscription {
+    /// Address the driver bound this subscription's endpoint to, with a wildcard port (`:0`)
+    /// resolved to the port picked. `None` until the driver has bound the endpoint, or if the
+    /// channel has no endpoint.
+    pub fn resolved_endpoint_address(&self) -> Option<std::net::SocketAddr> {
+        let mut buffer = [0u8; AERON_CLIENT_MAX_LOCAL_ADDRESS_STR_LEN as usize];
+        let count = unsafe {
+            aeron_subscription_resolved_endpoint(
+                self.get_inner(),
+                buffer.as_mut_ptr() as *const _,
+                buffer.len(),
+            )
+        };
+        if count <= 0 {
+            return None;
+        }
+        std::ffi::CStr::from_bytes_until_nul(&buffer)
+            .ok()?
+            .to_str()
+            .ok()?
+            .parse()
+            .ok()
+    }
+
+    /// Port the driver bound this subscription's endpoint to, see
+    /// [`resolved_endpoint_address`](Self::resolved_endpoint_address).
+    pub fn resolved_port(&self) -> Option<u16> {
+        self.resolved_endpoint_address().map(|addr| addr.port())
+    }
+
+    /// Rejects the image of `session_id`, see [`AeronImage::reject_with_reason`].
+    pub fn reject_image(&self, session_id: i32, reason: &str) -> Result<(), AeronCError> {
+        let image = self.image_by_session_id(session_id);
+        if image.get_inner().is_null() {
+            log::error!("no image for session {session_id} to reject [reason={reason}]");
+            return Err(AeronCError::from_code(-1));
+        }
+        let result = image.reject_with_reason(reason);
+        self.image_release(&image)?;
+        result
+    }
+}
+
+impl AeronImage {
+    /// Rejects the image, e.g. after a poison message. The driver stops delivering it and sends
+    /// an error frame with `reason` to the publication, see [`AeronContext::on_publication_error`].
+    pub fn reject_with_reason(&self, reason: &str) -> Result<(), AeronCError> {
+        self.reject(&reason.into_c_string())?;
+        Ok(())
+    }
+}
+
+/// Why an image became unavailable, see [`AeronImage::unavailable_reason`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageUnavailableReason {
+    /// the publication was closed and sent end of stream, a clean shutdown
+    EndOfStream,
+    /// the publication was revoked, see [`AeronExclusivePublication::close_and_revoke`]
+    Revoked,
+    /// the image timed out without end of stream, e.g. the publisher crashed or lost its
+    /// connection
+    Timeout,
+}
+
+impl AeronImage {
+    /// Why the image became unavailable, to call from an unavailable image handler.
+    pub fn unavailable_reason(&self) -> ImageUnavailableReason {
+        if self.is_publication_revoked() {
+            ImageUnavailableReason::Revoked
+        } else if self.end_of_stream_position() != i64::MAX {
+            ImageUnavailableReason::EndOfStream
+        } else {
+            ImageUnavailableReason::Timeout
+        }
+    }
+}
+
+/// Unavailable image handler calling a closure with the [`ImageUnavailableReason`].
+///
+/// ```no_run
+/// # use rusteron_client::*;
+/// # fn run(aeron: &Aeron) -> Result<(), AeronCError> {
+/// let on_unavailable = Handler::leak(UnavailableImageWithReason::new(|_, image, reason| {
+///     if reason != ImageUnavailableReason::EndOfStream {
+///         log::warn!("lost image {:?}: {reason:?}", image.get_constants());
+///     }
+/// }));
+/// let subscription = aeron.add_subscription(
+///     AERON_IPC_STREAM,
+///     1001,
+///     Handlers::no_available_image_handler(),
+///     Some(&on_unavailable),
+///     std::time::Duration::from_secs(5),
+/// )?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct UnavailableImageWithReason<
+    F: FnMut(AeronSubscription, AeronImage, ImageUnavailableReason),
+>(F);
+
+impl<F: FnMut(AeronSubscription, AeronImage, ImageUnavailableReason)>
+    UnavailableImageWithReason<F>
+{
+    pub fn new(handler: F) -> Self {
+        Self(handler)
+    }
+}
+
+impl<F: FnMut(AeronSubscription, AeronImage, ImageUnavailableReason)> AeronUnavailableImageCallback
+    for UnavailableImageWithReason<F>
+{
+    fn handle_aeron_on_unavailable_image(
+        &mut self,
+        subscription: AeronSubscription,
+        image: AeronImage,
+    ) -> () {
+        let reason = image.unavailable_reason();
+        (self.0)(subscription, image, reason)
+    }
+}
+
+/// An error frame a receiver sent to a publication, e.g. after rejecting its image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicationError {
+    /// registration id of the publication
+    pub registration_id: i64,
+    /// registration id of the destination, for multi destination publications
+    pub destination_registration_id: i64,
+    pub session_id: i32,
+    pub stream_id: i32,
+    pub receiver_id: i64,
+    pub group_tag: i64,
+    /// address the error frame was sent from
+    pub source_address: Option<std::net::SocketAddr>,
+    pub error_code: i32,
+    pub error_message: String,
+}
+
+impl From<&AeronPublicationErrorValues> for PublicationError {
+    fn from(values: &AeronPublicationErrorValues) -> Self {
+        let ptr = values.get_inner();
+        let values = values.get_inner_ref();
+        let port = values.source_port;
+        let source_address = match values.address_type as u32 {
+            AERON_RESPONSE_ADDRESS_TYPE_IPV4 => {
+                let octets: [u8; 4] = values.source_address[..4].try_into().unwrap();
+                Some(std::net::SocketAddr::from((octets, port)))
+            }
+            AERON_RESPONSE_ADDRESS_TYPE_IPV6 => {
+                Some(std::net::SocketAddr::from((values.source_address, port)))
+            }
+            _ => None,
+        };
+        // the message is a flexible array member, so it's read through the struct pointer
+        let error_message = unsafe {
+            std::slice::from_raw_parts(
+                std::ptr::addr_of!((*ptr).error_message) as *const u8,
+                values.error_message_length.max(0) as usize,
+            )
+        };
+        Self {
+            registration_id: values.registration_id,
+            destination_registration_id: values.destination_registration_id,
+            session_id: values.session_id,
+            stream_id: values.stream_id,
+            receiver_id: values.receiver_id,
+            group_tag: values.group_tag,
+            source_address,
+            error_code: values.error_code,
+            error_message: String::from_utf8_lossy(error_message).into_owned(),
+        }
+    }
+}
+
+struct PublicationErrorClosure<F: FnMut(PublicationError)>(F);
+
+impl<F: FnMut(PublicationError)> AeronPublicationErrorFrameHandlerCallback
+    for PublicationErrorClosure<F>
+{
+    fn handle_aeron_publication_error_frame_handler(
+        &mut self,
+        error_frame: AeronPublicationErrorValues,
+    ) -> () {
+        (self.0)(PublicationError::from(&error_frame))
+    }
+}
+
+/// Releases a handler owned by a context when the context is dropped.
+struct OwnedHandler<T>(Handler<T>);
+
+impl<T> Drop for OwnedHandler<T> {
+    fn drop(&mut self) {
+        self.0.release();
+    }
+}
+
+impl AeronContext {
+    /// Calls `handler` on the client conductor thread with the error frames receivers send to
+    /// this client's publications. Must be called before the client is created.
+    pub fn on_publication_error<F: FnMut(PublicationError) + Send + 'static>(
+        &self,
+        handler: F,
+    ) -> Result<(), AeronCError> {
+        let handler = Handler::leak(PublicationErrorClosure(handler));
+        if let Err(e) = self.set_publication_error_frame_handler(Some(&handler)) {
+            let mut handler = handler;
+            handler.release();
+            return Err(e);
+        }
+        self.inner.add_dependency(OwnedHandler(handler));
+        Ok(())
+    }
+}
+
 impl AeronPublication {
     pub fn close_with_no_args(&self) -> Result<(), AeronCError> {
         self.close(Handlers::no_notification_handler())?;
@@ -307,12 +536,54 @@ impl AeronPublication {
     }
 }
 
+/// Whether a publication is revoked when it's dropped, kept as a dependency of the publication.
+/// Atomic as the publication is `Send + Sync`.
+#[derive(Clone)]
+struct RevokeOnDrop(std::sync::Arc<std::sync::atomic::AtomicBool>);
+
 impl AeronExclusivePublication {
+    /// Closes the publication, which is also what happens when the last handle is dropped. So
+    /// once [`revoke_on_drop`](Self::revoke_on_drop) is set this revokes it too, as
+    /// [`close_and_revoke`](Self::close_and_revoke) does.
     pub fn close_with_no_args(&self) -> Result<(), AeronCError> {
+        if self.is_revoke_on_drop() {
+            return self.close_and_revoke();
+        }
         self.close(Handlers::no_notification_handler())?;
         Ok(())
     }
 
+    /// Closes the publication and revokes it, so subscribers' images become unavailable with
+    /// [`ImageUnavailableReason::Revoked`] straight away, without draining unconsumed messages.
+    pub fn close_and_revoke(&self) -> Result<(), AeronCError> {
+        self.revoke(Handlers::no_notification_handler())?;
+        // only once revoked, so a failed revoke still closes the publication on drop
+        if let Some(inner) = self.inner.as_owned() {
+            inner.close_already_called.set(true);
+        }
+        Ok(())
+    }
+
+    /// Whether dropping the last handle to the publication revokes it rather than just closing
+    /// it, see [`close_and_revoke`](Self::close_and_revoke). A borrowed publication isn't closed
+    /// on drop, so it's revoked when its owner closes it instead, which can't be undone.
+    pub fn revoke_on_drop(&self, revoke: bool) {
+        match self.inner.get_dependency::<RevokeOnDrop>() {
+            Some(flag) => flag.0.store(revoke, std::sync::atomic::Ordering::Release),
+            None if self.inner.as_owned().is_some() => self.inner.add_dependency(RevokeOnDrop(
+                std::sync::Arc::new(std::sync::atomic::AtomicBool::new(revoke)),
+            )),
+            None if revoke => self.revoke_on_close(),
+            None => {}
+        }
+    }
+
+    pub fn is_revoke_on_drop(&self) -> bool {
+        self.inner
+            .get_dependency::<RevokeOnDrop>()
+            .is_some_and(|flag| flag.0.load(std::sync::atomic::Ordering::Acquire))
+    }
+
     /// sometimes when you first connect, is_connected = true, but you get backpressure as position is 0
     /// this will check if both publication is connected and position > 0
     #[inline]
@@ -321,6 +592,391 @@ impl AeronExclusivePublication {
     }
 }
 
+/// Segments [`AeronPublication::offer_vectored`] gathers on the stack, more are collected into a
+/// `Vec`.
+pub const OFFER_VECTORED_STACK_SEGMENTS: usize = 16;
+
+#[inline]
+fn with_iovecs<S: std::ops::Deref<Target = [u8]>, R>(
+    segments: &[S],
+    f: impl FnOnce(*mut aeron_iovec_t, usize) -> R,
+) -> R {
+    let iovec = |segment: &S| aeron_iovec_t {
+        iov_base: segment.as_ptr() as *mut u8,
+        iov_len: segment.len(),
+    };
+    if segments.len() <= OFFER_VECTORED_STACK_SEGMENTS {
+        let mut iov = [aeron_iovec_t {
+            iov_base: std::ptr::null_mut(),
+            iov_len: 0,
+        }; OFFER_VECTORED_STACK_SEGMENTS];
+        for (iov, segment) in iov.iter_mut().zip(segments) {
+            *iov = iovec(segment);
+        }
+        f(iov.as_mut_ptr(), segments.len())
+    } else {
+        let mut iov = segments.iter().map(iovec).collect::<Vec<_>>();
+        f(iov.as_mut_ptr(), iov.len())
+    }
+}
+
+/// `IoSlice` has the same layout as `aeron_iovec_t` on unix, so slices are passed as is.
+#[inline]
+fn with_io_slice_iovecs<R>(
+    segments: &[std::io::IoSlice<'_>],
+    f: impl FnOnce(*mut aeron_iovec_t, usize) -> R,
+) -> R {
+    #[cfg(unix)]
+    {
+        f(segments.as_ptr() as *mut aeron_iovec_t, segments.len())
+    }
+    #[cfg(not(unix))]
+    {
+        with_iovecs(segments, f)
+    }
+}
+
+impl AeronPublication {
+    /// Offers the concatenation of `segments` as one message, e.g. a header and a payload from
+    /// separate buffers, without copying them into a contiguous buffer first. Up to
+    /// [`OFFER_VECTORED_STACK_SEGMENTS`] segments don't allocate.
+    #[inline]
+    pub fn offer_vectored<
+        S: std::ops::Deref<Target = [u8]>,
+        AeronReservedValueSupplierHandlerImpl: AeronReservedValueSupplierCallback,
+    >(
+        &self,
+        segments: &[S],
+        reserved_value_supplier: Option<&Handler<AeronReservedValueSupplierHandlerImpl>>,
+    ) -> i64 {
+        with_iovecs(segments, |iov, iovcnt| unsafe {
+            self.offerv_raw(iov, iovcnt, reserved_value_supplier)
+        })
+    }
+
+    /// [`offer_vectored`](Self::offer_vectored) for `IoSlice`s, which never allocates on unix.
+    #[inline]
+    pub fn offer_io_slices<
+        AeronReservedValueSupplierHandlerImpl: AeronReservedValueSupplierCallback,
+    >(
+        &self,
+        segments: &[std::io::IoSlice<'_>],
+        reserved_value_supplier: Option<&Handler<AeronReservedValueSupplierHandlerImpl>>,
+    ) -> i64 {
+        with_io_slice_iovecs(segments, |iov, iovcnt| unsafe {
+            self.offerv_raw(iov, iovcnt, reserved_value_supplier)
+        })
+    }
+
+    #[inline]
+    unsafe fn offerv_raw<
+        AeronReservedValueSupplierHandlerImpl: AeronReservedValueSupplierCallback,
+    >(
+        &self,
+        iov: *mut aeron_iovec_t,
+        iovcnt: usize,
+        reserved_value_supplier: Option<&Handler<AeronReservedValueSupplierHandlerImpl>>,
+    ) -> i64 {
+        aeron_publication_offerv(
+            self.get_inner(),
+            iov,
+            iovcnt,
+            {
+                let callback: aeron_reserved_value_supplier_t = if reserved_value_supplier.is_none()
+                {
+                    None
+                } else {
+                    Some(
+                        aeron_reserved_value_supplier_t_callback::<
+                            AeronReservedValueSupplierHandlerImpl,
+                        >,
+                    )
+                };
+                callback
+            },
+            reserved_value_supplier
+                .map(|m| m.as_raw())
+                .unwrap_or_else(std::ptr::null_mut),
+        )
+    }
+}
+
+impl AeronExclusivePublication {
+    /// Offers the concatenation of `segments` as one message, e.g. a header and a payload from
+    /// separate buffers, without copying them into a contiguous buffer first. Up to
+    /// [`OFFER_VECTORED_STACK_SEGMENTS`] segments don't allocate.
+    #[inline]
+    pub fn offer_vectored<
+        S: std::ops::Deref<Target = [u8]>,
+        AeronReservedValueSupplierHandlerImpl: AeronReservedValueSupplierCallback,
+    >(
+        &self,
+        segments: &[S],
+        reserved_value_supplier: Option<&Handler<AeronReservedValueSupplierHandlerImpl>>,
+    ) -> i64 {
+        with_iovecs(segments, |iov, iovcnt| unsafe {
+            self.offerv_raw(iov, iovcnt, reserved_value_supplier)
+        })
+    }
+
+    /// [`offer_vectored`](Self::offer_vectored) for `IoSlice`s, which never allocates on unix.
+    #[inline]
+    pub fn offer_io_slices<
+        AeronReservedValueSupplierHandlerImpl: AeronReservedValueSupplierCallback,
+    >(
+        &self,
+        segments: &[std::io::IoSlice<'_>],
+        reserved_value_supplier: Option<&Handler<AeronReservedValueSupplierHandlerImpl>>,
+    ) -> i64 {
+        with_io_slice_iovecs(segments, |iov, iovcnt| unsafe {
+            self.offerv_raw(iov, iovcnt, reserved_value_supplier)
+        })
+    }
+
+    #[inline]
+    unsafe fn offerv_raw<
+        AeronReservedValueSupplierHandlerImpl: AeronReservedValueSupplierCallback,
+    >(
+        &self,
+        iov: *mut aeron_iovec_t,
+        iovcnt: usize,
+        reserved_value_supplier: Option<&Handler<AeronReservedValueSupplierHandlerImpl>>,
+    ) -> i64 {
+        aeron_exclusive_publication_offerv(
+            self.get_inner(),
+            iov,
+            iovcnt,
+            {
+                let callback: aeron_reserved_value_supplier_t = if reserved_value_supplier.is_none()
+                {
+                    None
+                } else {
+                    Some(
+                        aeron_reserved_value_supplier_t_callback::<
+                            AeronReservedValueSupplierHandlerImpl,
+                        >,
+                    )
+                };
+                callback
+            },
+            reserved_value_supplier
+                .map(|m| m.as_raw())
+                .unwrap_or_else(std::ptr::null_mut),
+        )
+    }
+}
+
+/// Payload of the frame a reserved value supplier is called with.
+#[inline]
+unsafe fn reserved_value_payload<'a>(buffer: *mut u8, frame_length: usize) -> &'a [u8] {
+    if buffer.is_null() || frame_length <= DATA_HEADER_LENGTH {
+        &[]
+    } else {
+        std::slice::from_raw_parts(
+            buffer.add(DATA_HEADER_LENGTH),
+            frame_length - DATA_HEADER_LENGTH,
+        )
+    }
+}
+
+/// Clock a timestamp reserved value is taken from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReservedValueClock {
+    /// nanoseconds since the unix epoch, comparable between hosts with synchronised clocks
+    EpochNanos,
+    /// [`Aeron::nano_clock`], only comparable on the same host
+    NanoClock,
+}
+
+impl ReservedValueClock {
+    #[inline]
+    pub fn now(self) -> i64 {
+        match self {
+            ReservedValueClock::EpochNanos => std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_nanos() as i64)
+                .unwrap_or_default(),
+            ReservedValueClock::NanoClock => Aeron::nano_clock(),
+        }
+    }
+}
+
+/// Reserved value supplier calling a closure with the payload of each frame, to pass to `offer`
+/// as a [`Handler`]. `offer_with_reserved_value` takes the closure directly.
+///
+/// The supplier is called for each fragment of a message larger than the MTU, with the
+/// fragment's payload.
+///
+/// ```no_run
+/// # use rusteron_client::*;
+/// # fn run(publication: &AeronPublication) {
+/// publication.offer_with_reserved_value(b"hello", |payload| crc32(payload) as i64);
+///
+/// // a handler is reused across offers and must be released once no longer used
+/// let mut supplier = Handler::leak(ReservedValueSupplier::crc32());
+/// publication.offer(b"hello", Some(&supplier));
+/// supplier.release();
+/// # }
+/// ```
+pub struct ReservedValueSupplier<F: FnMut(&[u8]) -> i64>(F);
+
+impl<F: FnMut(&[u8]) -> i64> ReservedValueSupplier<F> {
+    pub fn new(supplier: F) -> Self {
+        Self(supplier)
+    }
+}
+
+impl ReservedValueSupplier<fn(&[u8]) -> i64> {
+    /// [`crc32`] of the payload, checked by [`AeronHeader::verify_crc32`].
+    pub fn crc32() -> Self {
+        Self(|payload| crc32(payload) as i64)
+    }
+
+    /// Time the frame is published, read by [`AeronHeader::latency`].
+    pub fn timestamp(clock: ReservedValueClock) -> Self {
+        match clock {
+            ReservedValueClock::EpochNanos => Self(|_| ReservedValueClock::EpochNanos.now()),
+            ReservedValueClock::NanoClock => Self(|_| ReservedValueClock::NanoClock.now()),
+        }
+    }
+}
+
+impl<F: FnMut(&[u8]) -> i64> AeronReservedValueSupplierCallback for ReservedValueSupplier<F> {
+    #[inline]
+    fn handle_aeron_reserved_value_supplier(
+        &mut self,
+        buffer: *mut u8,
+        frame_length: usize,
+    ) -> i64 {
+        (self.0)(unsafe { reserved_value_payload(buffer, frame_length) })
+    }
+}
+
+impl AeronPublication {
+    /// Offers `buffer` with the reserved value `supplier` returns for the payload of each frame.
+    #[inline]
+    pub fn offer_with_reserved_value<F: FnMut(&[u8]) -> i64>(
+        &self,
+        buffer: &[u8],
+        mut supplier: F,
+    ) -> i64 {
+        self.offer_once(buffer, |frame, frame_length| {
+            supplier(unsafe { reserved_value_payload(frame, frame_length) })
+        })
+    }
+}
+
+impl AeronExclusivePublication {
+    /// Offers `buffer` with the reserved value `supplier` returns for the payload of each frame.
+    #[inline]
+    pub fn offer_with_reserved_value<F: FnMut(&[u8]) -> i64>(
+        &self,
+        buffer: &[u8],
+        mut supplier: F,
+    ) -> i64 {
+        self.offer_once(buffer, |frame, frame_length| {
+            supplier(unsafe { reserved_value_payload(frame, frame_length) })
+        })
+    }
+}
+
+impl AeronHeader {
+    #[inline]
+    pub fn reserved_value(&self) -> Result<i64, AeronCError> {
+        Ok(self.get_values()?.frame().reserved_value())
+    }
+
+    /// Whether the reserved value is the [`crc32`] of `payload`, i.e. it was published with
+    /// [`ReservedValueSupplier::crc32`]. Checks fragments, so use it before reassembling messages.
+    #[inline]
+    pub fn verify_crc32(&self, payload: &[u8]) -> bool {
+        self.reserved_value()
+            .is_ok_and(|value| value == crc32(payload) as i64)
+    }
+
+    /// Time since a [`ReservedValueSupplier::timestamp`] of `clock` was published, `None` if it
+    /// can't be read or is in the future.
+    #[inline]
+    pub fn latency(&self, clock: ReservedValueClock) -> Option<std::time::Duration> {
+        let published = self.reserved_value().ok()?;
+        u64::try_from(clock.now() - published)
+            .ok()
+            .map(std::time::Duration::from_nanos)
+    }
+}
+
+impl AeronExclusivePublication {
+    /// Adds an exclusive publication starting at `position` of a stream with `initial_term_id` and
+    /// `term_length`, e.g. to continue a recording from its stop position as archive extension
+    /// does. The term params are set on `channel`, replacing any it has.
+    pub fn resume_at(
+        aeron: &Aeron,
+        channel: &std::ffi::CStr,
+        stream_id: i32,
+        position: i64,
+        initial_term_id: i32,
+        term_length: TermLength,
+        timeout: std::time::Duration,
+    ) -> Result<Self, AeronCError> {
+        let channel = channel
+            .to_str()
+            .map_err(|_| AeronCError::from_code(PARSE_CSTR_ERROR_CODE))?;
+        let mut uri = ChannelUri::parse(channel).map_err(|e| {
+            log::error!("invalid channel to resume at {position}: {e}");
+            AeronCError::from_code(-1)
+        })?;
+        uri.set_initial_position(position, initial_term_id, term_length)
+            .map_err(|e| {
+                log::error!("can't resume {channel} at {position}: {e}");
+                AeronCError::from_code(-1)
+            })?;
+        aeron.add_exclusive_publication(&uri.to_string().into_c_string(), stream_id, timeout)
+    }
+
+    /// Term position and ids a block offered now must have.
+    pub fn block_target(&self) -> Result<BlockTarget, AeronCError> {
+        let constants = self.get_constants()?;
+        let position = self.position();
+        if position < 0 {
+            return Err(AeronCError::from_code(position as i32));
+        }
+        let term_length = constants.term_buffer_length();
+        Ok(BlockTarget {
+            session_id: constants.session_id(),
+            stream_id: constants.stream_id(),
+            term_id: constants
+                .initial_term_id()
+                .wrapping_add((position >> constants.position_bits_to_shift()) as i32),
+            term_offset: (position & (term_length as i64 - 1)) as i32,
+            term_length: term_length as i32,
+        })
+    }
+
+    /// [`offer_block`](Self::offer_block) after checking every frame of `block` with
+    /// [`BlockTarget::validate`], where the C client only checks the first. Returns the new
+    /// position, or a negative offer result such as back pressure.
+    pub fn offer_block_checked(&self, block: &[u8]) -> Result<i64, BlockError> {
+        self.block_target()
+            .map_err(BlockError::Publication)?
+            .validate(block)?;
+        Ok(self.offer_block(block))
+    }
+
+    /// [`append_padding`](Self::append_padding) of `length` bytes, which must be at most the
+    /// max message length. Returns the new position, or a negative offer result such as back
+    /// pressure.
+    pub fn append_padding_checked(&self, length: usize) -> Result<i64, BlockError> {
+        let max_length = self
+            .get_constants()
+            .map_err(BlockError::Publication)?
+            .max_message_length();
+        if length > max_length {
+            return Err(BlockError::InvalidPaddingLength { length, max_length });
+        }
+        Ok(self.append_padding(length))
+    }
+}
+
 impl AeronCounter {
     pub fn close_with_no_args(&self) -> Result<(), AeronCError> {
         self.close(Handlers::no_notification_handler())?;
@@ -649,6 +1305,9 @@ impl AeronUriStringBuilder {
         self.put_string(key, value)?;
         Ok(self)
     }
+    pub fn congestion_control(&self, value: CongestionControl) -> Result<&Self, AeronCError> {
+        self.cc(value.as_str())
+    }
     pub fn spies_simulate_connection(&self, value: bool) -> Result<&Self, AeronCError> {
         let key = std::ffi::CStr::from_bytes_until_nul(AERON_URI_SPIES_SIMULATE_CONNECTION_KEY)
             .map_err(|_| AeronCError::from_code(PARSE_CSTR_ERROR_CODE))?;
@@ -845,6 +1504,465 @@ impl AeronCountersReader {
     }
 }
 
+/// Max length of a counter's key, longer keys are rejected.
+pub const COUNTER_MAX_KEY_LENGTH: usize = 112;
+/// Max length of a counter's label, longer labels are truncated.
+pub const COUNTER_MAX_LABEL_LENGTH: usize = 380;
+
+/// A typed counter key, serialized into the counter's metadata so tools and other processes can
+/// find the counter with [`AeronCountersReader::find`].
+///
+/// Implemented for numbers, `bool`, `()`, `String` and arrays. Structs use
+/// [`counter_key!`](crate::counter_key) to encode their fields in order, numbers are little endian.
+pub trait CounterKey: Sized {
+    fn encode(&self, key: &mut Vec<u8>);
+
+    /// Decodes a key from the front of `key`, advancing past the bytes read.
+    fn decode(key: &mut &[u8]) -> Option<Self>;
+}
+
+macro_rules! impl_counter_key_for_number {
+    ($($t:ty),*) => {
+        $(
+            impl CounterKey for $t {
+                fn encode(&self, key: &mut Vec<u8>) {
+                    key.extend_from_slice(&self.to_le_bytes());
+                }
+
+                fn decode(key: &mut &[u8]) -> Option<Self> {
+                    const SIZE: usize = std::mem::size_of::<$t>();
+                    let bytes = key.get(..SIZE)?;
+                    *key = &key[SIZE..];
+                    Some(<$t>::from_le_bytes(bytes.try_into().ok()?))
+                }
+            }
+        )*
+    };
+}
+
+impl_counter_key_for_number!(i8, i16, i32, i64, u8, u16, u32, u64, f32, f64);
+
+impl CounterKey for bool {
+    fn encode(&self, key: &mut Vec<u8>) {
+        key.push(*self as u8);
+    }
+
+    fn decode(key: &mut &[u8]) -> Option<Self> {
+        u8::decode(key).map(|b| b != 0)
+    }
+}
+
+impl CounterKey for () {
+    fn encode(&self, _key: &mut Vec<u8>) {}
+
+    fn decode(_key: &mut &[u8]) -> Option<Self> {
+        Some(())
+    }
+}
+
+/// Encoded as an `i32` length followed by the bytes, the format Aeron uses for channels in keys.
+impl CounterKey for String {
+    fn encode(&self, key: &mut Vec<u8>) {
+        (self.len() as i32).encode(key);
+        key.extend_from_slice(self.as_bytes());
+    }
+
+    fn decode(key: &mut &[u8]) -> Option<Self> {
+        let length = usize::try_from(i32::decode(key)?).ok()?;
+        let bytes = key.get(..length)?;
+        *key = &key[length..];
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+}
+
+impl<T: CounterKey, const N: usize> CounterKey for [T; N] {
+    fn encode(&self, key: &mut Vec<u8>) {
+        self.iter().for_each(|t| t.encode(key));
+    }
+
+    fn decode(key: &mut &[u8]) -> Option<Self> {
+        let items = (0..N)
+            .map(|_| T::decode(key))
+            .collect::<Option<Vec<T>>>()?;
+        items.try_into().ok()
+    }
+}
+
+/// Declares a struct implementing [`CounterKey`], encoding its fields in order.
+///
+/// ```ignore
+/// counter_key! {
+///     #[derive(Debug, Clone, Copy, PartialEq)]
+///     pub struct StrategyKey {
+///         pub strategy_id: i64,
+///         pub venue: u16,
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! counter_key {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $($(#[$field_meta:meta])* $field_vis:vis $field:ident : $ty:ty),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $($(#[$field_meta])* $field_vis $field: $ty),*
+        }
+
+        impl $crate::CounterKey for $name {
+            fn encode(&self, key: &mut Vec<u8>) {
+                $($crate::CounterKey::encode(&self.$field, key);)*
+            }
+
+            fn decode(key: &mut &[u8]) -> Option<Self> {
+                Some(Self {
+                    $($field: <$ty as $crate::CounterKey>::decode(key)?),*
+                })
+            }
+        }
+    };
+}
+
+/// An application counter created with [`Aeron::add_typed_counter`], with the key it was
+/// registered with.
+#[derive(Debug, Clone)]
+pub struct Counter<K> {
+    counter: AeronCounter,
+    key: K,
+    counter_id: i32,
+    registration_id: i64,
+}
+
+impl<K: CounterKey> Counter<K> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn counter(&self) -> &AeronCounter {
+        &self.counter
+    }
+
+    pub fn counter_id(&self) -> i32 {
+        self.counter_id
+    }
+
+    pub fn registration_id(&self) -> i64 {
+        self.registration_id
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.counter.is_closed()
+    }
+
+    #[inline]
+    pub fn get(&self) -> i64 {
+        self.counter
+            .addr_atomic()
+            .load(std::sync::atomic::Ordering::Acquire)
+    }
+
+    #[inline]
+    pub fn set(&self, value: i64) {
+        self.counter
+            .addr_atomic()
+            .store(value, std::sync::atomic::Ordering::SeqCst)
+    }
+
+    /// Sets the value with release ordering, cheaper than [`set`](Self::set) when there is a
+    /// single writer.
+    #[inline]
+    pub fn set_ordered(&self, value: i64) {
+        self.counter
+            .addr_atomic()
+            .store(value, std::sync::atomic::Ordering::Release)
+    }
+
+    /// Adds 1, returning the previous value.
+    #[inline]
+    pub fn increment(&self) -> i64 {
+        self.get_and_add(1)
+    }
+
+    /// Adds `delta`, returning the previous value.
+    #[inline]
+    pub fn get_and_add(&self, delta: i64) -> i64 {
+        self.counter
+            .addr_atomic()
+            .fetch_add(delta, std::sync::atomic::Ordering::SeqCst)
+    }
+}
+
+impl Aeron {
+    /// Adds a counter whose key is the encoded `key`, waiting up to `timeout` for the driver.
+    pub fn add_typed_counter<K: CounterKey>(
+        &self,
+        type_id: i32,
+        key: K,
+        label: &str,
+        timeout: std::time::Duration,
+    ) -> Result<Counter<K>, AeronCError> {
+        let mut key_buffer = Vec::with_capacity(COUNTER_MAX_KEY_LENGTH);
+        key.encode(&mut key_buffer);
+        if key_buffer.len() > COUNTER_MAX_KEY_LENGTH {
+            log::error!(
+                "counter key length {} exceeds max length {COUNTER_MAX_KEY_LENGTH} [label={label}]",
+                key_buffer.len()
+            );
+            return Err(AeronCError::from_code(-1));
+        }
+        let counter = self.add_counter(type_id, &key_buffer, label, timeout)?;
+        let constants = counter.get_constants()?;
+        Ok(Counter {
+            counter_id: constants.counter_id(),
+            registration_id: constants.registration_id(),
+            counter,
+            key,
+        })
+    }
+}
+
+impl AeronCountersReader {
+    /// Allocated counters of `type_id` with their decoded keys, skipping keys which don't decode
+    /// as `K`.
+    pub fn find<K: CounterKey>(&self, type_id: i32) -> Vec<(i32, K)> {
+        let mut found = vec![];
+        self.foreach_counter_once(|_value, counter_id, counter_type_id, mut key, _label| {
+            if counter_type_id == type_id {
+                if let Some(key) = K::decode(&mut key) {
+                    found.push((counter_id, key));
+                }
+            }
+        });
+        found
+    }
+
+    /// Id of the first allocated counter of `type_id` whose key is `key`.
+    pub fn find_by_key<K: CounterKey + PartialEq>(&self, type_id: i32, key: &K) -> Option<i32> {
+        self.find::<K>(type_id)
+            .into_iter()
+            .find(|(_, k)| k == key)
+            .map(|(counter_id, _)| counter_id)
+    }
+}
+
+/// Type id of the driver's system counters, see [`AeronSystemCounterType`].
+pub const SYSTEM_COUNTER_TYPE_ID: i32 = 0;
+/// Type id of a publication's limit, keyed by [`StreamCounterKey`].
+pub const PUBLISHER_LIMIT_TYPE_ID: i32 = 1;
+/// Type id of a network publication's sender position, keyed by [`StreamCounterKey`].
+pub const SENDER_POSITION_TYPE_ID: i32 = 2;
+/// Type id of an image's receiver high water mark, keyed by [`StreamCounterKey`].
+pub const RECEIVER_HWM_TYPE_ID: i32 = 3;
+/// Type id of a subscriber's position in an image, keyed by [`StreamCounterKey`].
+pub const SUBSCRIBER_POSITION_TYPE_ID: i32 = 4;
+/// Type id of an image's receiver position, keyed by [`StreamCounterKey`].
+pub const RECEIVER_POSITION_TYPE_ID: i32 = 5;
+/// Type id of a network publication's sender limit, keyed by [`StreamCounterKey`].
+pub const SENDER_LIMIT_TYPE_ID: i32 = 9;
+/// Type id of a publication's position, keyed by [`StreamCounterKey`].
+pub const PUBLISHER_POSITION_TYPE_ID: i32 = 12;
+/// Type id of an archive recording's position, keyed by [`RecordingPositionKey`].
+pub const RECORDING_POSITION_TYPE_ID: i32 = 100;
+
+counter_key! {
+    /// Key of the driver's per stream counters, e.g. [`PUBLISHER_LIMIT_TYPE_ID`] and
+    /// [`SUBSCRIBER_POSITION_TYPE_ID`].
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    pub struct StreamCounterKey {
+        /// registration id of the publication, or of the subscription for subscriber positions
+        pub registration_id: i64,
+        pub session_id: i32,
+        pub stream_id: i32,
+        /// channel, truncated to fit the key
+        pub channel: String,
+    }
+}
+
+/// Key of an archive recording's position counter, [`RECORDING_POSITION_TYPE_ID`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RecordingPositionKey {
+    pub recording_id: i64,
+    pub session_id: i32,
+    /// source of the recorded stream, e.g. the address of a remote publisher
+    pub source_identity: String,
+    /// `None` for archives which predate archive ids, whose keys are zero padded after the source
+    /// identity
+    pub archive_id: Option<i64>,
+}
+
+impl CounterKey for RecordingPositionKey {
+    fn encode(&self, key: &mut Vec<u8>) {
+        self.recording_id.encode(key);
+        self.session_id.encode(key);
+        self.source_identity.encode(key);
+        if let Some(archive_id) = self.archive_id {
+            archive_id.encode(key);
+        }
+    }
+
+    fn decode(key: &mut &[u8]) -> Option<Self> {
+        Some(Self {
+            recording_id: i64::decode(key)?,
+            session_id: i32::decode(key)?,
+            source_identity: String::decode(key)?,
+            archive_id: if key.iter().all(|b| *b == 0) {
+                None
+            } else {
+                i64::decode(key)
+            },
+        })
+    }
+}
+
+/// State of a counter's record in the counters buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CounterState {
+    Unused,
+    Allocated,
+    /// freed and waiting for its free for reuse deadline before it can be allocated again
+    Reclaimed,
+}
+
+impl CounterState {
+    pub fn from_code(state: i32) -> Option<Self> {
+        match state {
+            s if s == AERON_COUNTER_RECORD_UNUSED as i32 => Some(CounterState::Unused),
+            s if s == AERON_COUNTER_RECORD_ALLOCATED as i32 => Some(CounterState::Allocated),
+            AERON_COUNTER_RECORD_RECLAIMED => Some(CounterState::Reclaimed),
+            _ => None,
+        }
+    }
+}
+
+/// Snapshot of a counter's value and metadata, from [`AeronCountersReader::iter`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CounterInfo {
+    pub counter_id: i32,
+    pub type_id: i32,
+    pub state: CounterState,
+    pub value: i64,
+    pub registration_id: i64,
+    /// typically the client id of the client which added the counter
+    pub owner_id: i64,
+    /// typically the registration id of the publication, subscription or image the counter is for
+    pub reference_id: i64,
+    /// epoch ms after which a reclaimed counter can be reused, `None` if not reclaimed
+    pub free_for_reuse_deadline_ms: Option<i64>,
+    pub key: [u8; COUNTER_MAX_KEY_LENGTH],
+    pub label: String,
+}
+
+impl CounterInfo {
+    pub fn is_allocated(&self) -> bool {
+        self.state == CounterState::Allocated
+    }
+
+    /// The key decoded as `K`, regardless of the counter's type id.
+    pub fn key_as<K: CounterKey>(&self) -> Option<K> {
+        K::decode(&mut &self.key[..])
+    }
+
+    pub fn is_stream_counter(&self) -> bool {
+        matches!(
+            self.type_id,
+            PUBLISHER_LIMIT_TYPE_ID
+                | SENDER_POSITION_TYPE_ID
+                | RECEIVER_HWM_TYPE_ID
+                | SUBSCRIBER_POSITION_TYPE_ID
+                | RECEIVER_POSITION_TYPE_ID
+                | SENDER_LIMIT_TYPE_ID
+                | PUBLISHER_POSITION_TYPE_ID
+        )
+    }
+
+    /// The key of a publisher limit, sender position, receiver position or subscriber position
+    /// counter.
+    pub fn stream_key(&self) -> Option<StreamCounterKey> {
+        self.is_stream_counter()
+            .then(|| self.key_as())
+            .flatten()
+    }
+
+    pub fn recording_position_key(&self) -> Option<RecordingPositionKey> {
+        (self.type_id == RECORDING_POSITION_TYPE_ID)
+            .then(|| self.key_as())
+            .flatten()
+    }
+
+    pub fn system_counter_type(&self) -> Option<AeronSystemCounterType> {
+        if self.type_id != SYSTEM_COUNTER_TYPE_ID {
+            return None;
+        }
+        i32::decode(&mut &self.key[..]).and_then(|id| AeronSystemCounterType::try_from(id).ok())
+    }
+}
+
+impl AeronCountersReader {
+    /// Value and metadata of `counter_id`, `None` if the id is out of range.
+    pub fn counter_info(&self, counter_id: i32) -> Option<CounterInfo> {
+        if counter_id < 0 || counter_id > self.max_counter_id() {
+            return None;
+        }
+        let state = CounterState::from_code(self.counter_state(counter_id).ok()?)?;
+        let mut key = [0u8; COUNTER_MAX_KEY_LENGTH];
+        let mut key_ptr: *mut u8 = std::ptr::null_mut();
+        unsafe {
+            if bindings::aeron_counters_reader_metadata_key(
+                self.get_inner(),
+                counter_id,
+                &mut key_ptr,
+            ) >= 0
+                && !key_ptr.is_null()
+            {
+                std::ptr::copy_nonoverlapping(key_ptr, key.as_mut_ptr(), key.len());
+            }
+        }
+        let mut label = String::with_capacity(COUNTER_MAX_LABEL_LENGTH);
+        let _ = self.get_counter_label_into(counter_id, &mut label);
+        let free_for_reuse_deadline_ms = self
+            .free_for_reuse_deadline_ms(counter_id)
+            .ok()
+            .filter(|deadline| *deadline != AERON_COUNTER_NOT_FREE_TO_REUSE as i64);
+        Some(CounterInfo {
+            counter_id,
+            type_id: self.counter_type_id(counter_id).ok()?,
+            state,
+            value: self.get_counter_value(counter_id),
+            registration_id: self.counter_registration_id(counter_id).ok()?,
+            owner_id: self.counter_owner_id(counter_id).ok()?,
+            reference_id: self.counter_reference_id(counter_id).ok()?,
+            free_for_reuse_deadline_ms,
+            key,
+            label,
+        })
+    }
+
+    /// Counters which are allocated or reclaimed, in id order.
+    pub fn iter(&self) -> impl Iterator<Item = CounterInfo> + '_ {
+        (0..=self.max_counter_id())
+            .filter_map(|counter_id| self.counter_info(counter_id))
+            .filter(|info| info.state != CounterState::Unused)
+    }
+
+    /// Allocated counters of `type_id`.
+    pub fn by_type_id(&self, type_id: i32) -> impl Iterator<Item = CounterInfo> + '_ {
+        self.iter()
+            .filter(move |info| info.is_allocated() && info.type_id == type_id)
+    }
+
+    /// The allocated counter of `type_id` registered with `registration_id`.
+    pub fn by_registration_id(&self, type_id: i32, registration_id: i64) -> Option<CounterInfo> {
+        let counter_id = self.find_by_type_id_and_registration_id(type_id, registration_id);
+        if counter_id == AERON_NULL_COUNTER_ID {
+            return None;
+        }
+        self.counter_info(counter_id)
+    }
+}
+
 impl Aeron {
     pub fn new_blocking(
         context: &AeronContext,
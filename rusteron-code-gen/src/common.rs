@@ -541,13 +541,1282 @@ pub fn is_udp_port_available(port: u16) -> bool {
     std::net::UdpSocket::bind(("127.0.0.1", port)).is_ok()
 }
 
-/// Represents the Aeron URI parser and handler.
-pub struct ChannelUri {}
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// CRC-32 (IEEE, as used by zlib and `java.util.zip.CRC32`) of `data`.
+///
+/// ```
+/// # use rusteron_code_gen::*;
+/// assert_eq!(0xCBF4_3926, crc32(b"123456789"));
+/// ```
+pub fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, byte| {
+        CRC32_TABLE[((crc ^ *byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Alignment of frames in a term.
+pub const FRAME_ALIGNMENT: usize = 32;
+/// Length of a data frame header.
+pub const DATA_HEADER_LENGTH: usize = 32;
+pub const HDR_TYPE_PAD: u16 = 0x00;
+pub const HDR_TYPE_DATA: u16 = 0x01;
+
+/// Term position and ids the frames of a block offered to an exclusive publication must have,
+/// see [`BlockTarget::validate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BlockTarget {
+    pub session_id: i32,
+    pub stream_id: i32,
+    pub term_id: i32,
+    /// offset the block must start at, i.e. the publication's current term offset
+    pub term_offset: i32,
+    pub term_length: i32,
+}
+
+/// Why a block of frames or padding can't be appended to an exclusive publication.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockError {
+    Empty,
+    /// the publication's position couldn't be read, e.g. it's closed
+    Publication(AeronCError),
+    InvalidPaddingLength {
+        length: usize,
+        max_length: usize,
+    },
+    /// the block doesn't fit in the rest of the term
+    ExceedsTerm {
+        term_offset: i32,
+        length: usize,
+        term_length: i32,
+    },
+    /// the frame at `offset` has a length shorter than a header or longer than the rest of the
+    /// block
+    InvalidFrameLength {
+        offset: usize,
+        frame_length: i32,
+    },
+    /// the first frame must be a data frame, later frames data or padding
+    InvalidFrameType {
+        offset: usize,
+        frame_type: u16,
+    },
+    /// a header field of the frame at `offset` doesn't match the target
+    Mismatch {
+        offset: usize,
+        field: &'static str,
+        expected: i32,
+        actual: i32,
+    },
+}
+
+impl std::fmt::Display for BlockError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BlockError::Empty => write!(f, "block is empty"),
+            BlockError::Publication(e) => write!(f, "publication unavailable: {e}"),
+            BlockError::InvalidPaddingLength { length, max_length } => write!(
+                f,
+                "padding length {length} exceeds max message length {max_length}"
+            ),
+            BlockError::ExceedsTerm {
+                term_offset,
+                length,
+                term_length,
+            } => write!(
+                f,
+                "block of {length} bytes at term offset {term_offset} exceeds term length {term_length}"
+            ),
+            BlockError::InvalidFrameLength {
+                offset,
+                frame_length,
+            } => write!(f, "invalid frame length {frame_length} at offset {offset}"),
+            BlockError::InvalidFrameType { offset, frame_type } => {
+                write!(f, "invalid frame type {frame_type:#x} at offset {offset}")
+            }
+            BlockError::Mismatch {
+                offset,
+                field,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{field} {actual} of frame at offset {offset} doesn't match {expected}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BlockError {}
+
+impl BlockTarget {
+    /// Checks `block` is a sequence of aligned frames for this target, starting with a data frame
+    /// at [`term_offset`](Self::term_offset), as `aeron_exclusive_publication_offer_block`
+    /// copies it into the term as is.
+    pub fn validate(&self, block: &[u8]) -> Result<(), BlockError> {
+        if block.is_empty() {
+            return Err(BlockError::Empty);
+        }
+        if self.term_offset as i64 + block.len() as i64 > self.term_length as i64 {
+            return Err(BlockError::ExceedsTerm {
+                term_offset: self.term_offset,
+                length: block.len(),
+                term_length: self.term_length,
+            });
+        }
+        let read_i32 = |offset: usize| {
+            i32::from_le_bytes(block[offset..offset + 4].try_into().unwrap_or_default())
+        };
+        let mut offset = 0;
+        while offset < block.len() {
+            if block.len() - offset < DATA_HEADER_LENGTH {
+                return Err(BlockError::InvalidFrameLength {
+                    offset,
+                    frame_length: (block.len() - offset) as i32,
+                });
+            }
+            let frame_length = read_i32(offset);
+            if frame_length < DATA_HEADER_LENGTH as i32
+                || (frame_length as usize).next_multiple_of(FRAME_ALIGNMENT) > block.len() - offset
+            {
+                return Err(BlockError::InvalidFrameLength {
+                    offset,
+                    frame_length,
+                });
+            }
+            let frame_type = u16::from_le_bytes([block[offset + 6], block[offset + 7]]);
+            if frame_type != HDR_TYPE_DATA && (offset == 0 || frame_type != HDR_TYPE_PAD) {
+                return Err(BlockError::InvalidFrameType { offset, frame_type });
+            }
+            for (field, field_offset, expected) in [
+                ("term offset", 8, self.term_offset + offset as i32),
+                ("session id", 12, self.session_id),
+                ("stream id", 16, self.stream_id),
+                ("term id", 20, self.term_id),
+            ] {
+                let actual = read_i32(offset + field_offset);
+                if actual != expected {
+                    return Err(BlockError::Mismatch {
+                        offset,
+                        field,
+                        expected,
+                        actual,
+                    });
+                }
+            }
+            offset += (frame_length as usize).next_multiple_of(FRAME_ALIGNMENT);
+        }
+        Ok(())
+    }
+}
+
+/// A channel URI, e.g. `aeron:udp?endpoint=localhost:20121|term-length=64k`, parsed and validated
+/// in Rust without the Aeron C library.
+///
+/// Parameters keep their order so [`Display`](std::fmt::Display) round trips the parsed string.
+/// Known parameters are validated when parsed or [`set`](ChannelUri::set), unknown parameters are
+/// kept as is.
+///
+/// ```
+/// # use rusteron_code_gen::*;
+/// let uri: ChannelUri = "aeron:udp?endpoint=localhost:20121|term-length=64k".parse().unwrap();
+/// assert_eq!(Some(Media::Udp), uri.media());
+/// assert_eq!(Some("localhost:20121"), uri.endpoint());
+/// assert_eq!(Some(64 * 1024), uri.term_length());
+/// assert_eq!("aeron:udp?endpoint=localhost:20121|term-length=64k", uri.to_string());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChannelUri {
+    spy: bool,
+    media: String,
+    params: Vec<(String, String)>,
+}
+
+/// Why a [`ChannelUri`] could not be parsed or a parameter set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChannelUriError {
+    /// the uri doesn't start with `aeron:` or `aeron-spy:aeron:`
+    InvalidScheme(String),
+    /// the media isn't `udp` or `ipc`
+    UnknownMedia(String),
+    /// a parameter without a `=`, or with an empty key or value
+    MalformedParam(String),
+    DuplicateParam(String),
+    InvalidValue {
+        key: String,
+        value: String,
+        reason: String,
+    },
+    TooLong(usize),
+    /// params which are valid on their own but not together, found by [`ChannelUriBuilder::build`]
+    InvalidCombination(String),
+}
+
+impl std::fmt::Display for ChannelUriError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChannelUriError::InvalidScheme(uri) => write!(
+                f,
+                "channel uri {uri:?} must start with {:?} or {:?}",
+                ChannelUri::AERON_PREFIX,
+                ChannelUri::SPY_PREFIX
+            ),
+            ChannelUriError::UnknownMedia(media) => {
+                write!(f, "unknown media {media:?}, expected \"udp\" or \"ipc\"")
+            }
+            ChannelUriError::MalformedParam(param) => {
+                write!(f, "malformed param {param:?}, expected key=value")
+            }
+            ChannelUriError::DuplicateParam(key) => write!(f, "duplicate param {key:?}"),
+            ChannelUriError::InvalidValue { key, value, reason } => {
+                write!(f, "invalid value {value:?} for {key}: {reason}")
+            }
+            ChannelUriError::TooLong(length) => write!(
+                f,
+                "channel uri length {length} exceeds max length {}",
+                ChannelUri::MAX_URI_LENGTH
+            ),
+            ChannelUriError::InvalidCombination(reason) => {
+                write!(f, "invalid param combination: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ChannelUriError {}
+
+/// Parses a size with an optional `k`, `m` or `g` suffix, e.g. `64k`.
+fn parse_uri_size(value: &str) -> Result<u64, String> {
+    let (digits, multiplier) = match value.as_bytes().last() {
+        Some(b'k' | b'K') => (&value[..value.len() - 1], 1u64 << 10),
+        Some(b'm' | b'M') => (&value[..value.len() - 1], 1 << 20),
+        Some(b'g' | b'G') => (&value[..value.len() - 1], 1 << 30),
+        _ => (value, 1),
+    };
+    digits
+        .parse::<u64>()
+        .map_err(|e| e.to_string())?
+        .checked_mul(multiplier)
+        .ok_or_else(|| "size overflows u64".to_string())
+}
+
+/// Parses a duration in ns with an optional `ns`, `us`, `ms` or `s` suffix, e.g. `5s`.
+fn parse_uri_duration(value: &str) -> Result<std::time::Duration, String> {
+    let (digits, nanos) = [
+        ("ns", 1u64),
+        ("us", 1_000),
+        ("ms", 1_000_000),
+        ("s", 1_000_000_000),
+    ]
+    .iter()
+    .find_map(|(suffix, nanos)| value.strip_suffix(suffix).map(|d| (d, *nanos)))
+    .unwrap_or((value, 1));
+    digits
+        .parse::<u64>()
+        .map_err(|e| e.to_string())?
+        .checked_mul(nanos)
+        .map(std::time::Duration::from_nanos)
+        .ok_or_else(|| "duration overflows u64 ns".to_string())
+}
+
+/// Formats a duration using the largest unit which represents it exactly.
+fn format_uri_duration(duration: std::time::Duration) -> String {
+    let nanos = duration.as_nanos();
+    [(1_000_000_000, "s"), (1_000_000, "ms"), (1_000, "us")]
+        .iter()
+        .find(|(unit, _)| nanos % unit == 0)
+        .map(|(unit, suffix)| format!("{}{}", nanos / unit, suffix))
+        .unwrap_or_else(|| format!("{nanos}ns"))
+}
+
+/// Formats a size using the largest `k`, `m` or `g` suffix which represents it exactly.
+fn format_uri_size(size: u64) -> String {
+    [(1u64 << 30, "g"), (1 << 20, "m"), (1 << 10, "k")]
+        .iter()
+        .find(|(unit, _)| size != 0 && size % unit == 0)
+        .map(|(unit, suffix)| format!("{}{}", size / unit, suffix))
+        .unwrap_or_else(|| size.to_string())
+}
+
+fn parse_uri_bool(value: &str) -> Result<bool, String> {
+    match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err("expected true or false".to_string()),
+    }
+}
+
+fn parse_uri_number<T: std::str::FromStr>(value: &str) -> Result<T, String>
+where
+    T::Err: std::fmt::Display,
+{
+    value.parse::<T>().map_err(|e| e.to_string())
+}
+
+/// A `session-id` param, either an id or `tag:` referencing a tagged publication's session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SessionId {
+    Id(i32),
+    Tag(i64),
+}
+
+impl SessionId {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value.strip_prefix(TAG_PREFIX) {
+            Some(tag) => parse_uri_number(tag).map(SessionId::Tag),
+            None => parse_uri_number(value).map(SessionId::Id),
+        }
+    }
+}
+
+impl std::fmt::Display for SessionId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SessionId::Id(id) => write!(f, "{id}"),
+            SessionId::Tag(tag) => write!(f, "{TAG_PREFIX}{tag}"),
+        }
+    }
+}
+
+/// Where a `media-rcv-ts-offset`, `channel-rcv-ts-offset` or `channel-snd-ts-offset` param
+/// writes its timestamp: the frame's reserved value or a byte offset into the message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimestampOffset {
+    Reserved,
+    Offset(u32),
+}
+
+impl TimestampOffset {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "reserved" => Ok(TimestampOffset::Reserved),
+            _ => parse_uri_number(value).map(TimestampOffset::Offset),
+        }
+    }
+}
+
+impl std::fmt::Display for TimestampOffset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimestampOffset::Reserved => f.write_str("reserved"),
+            TimestampOffset::Offset(offset) => write!(f, "{offset}"),
+        }
+    }
+}
+
+/// A `term-length` param, a power of 2 between [`ChannelUri::MIN_TERM_LENGTH`] and
+/// [`ChannelUri::MAX_TERM_LENGTH`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TermLength(u32);
+
+impl TermLength {
+    pub fn new(length: u32) -> Result<Self, ChannelUriError> {
+        Self::check(length as u64)
+            .map(|_| TermLength(length))
+            .map_err(|reason| ChannelUriError::InvalidValue {
+                key: ChannelUri::TERM_LENGTH.to_string(),
+                value: length.to_string(),
+                reason,
+            })
+    }
+
+    pub fn get(&self) -> u32 {
+        self.0
+    }
+
+    fn check(length: u64) -> Result<(), String> {
+        if !length.is_power_of_two()
+            || !(ChannelUri::MIN_TERM_LENGTH..=ChannelUri::MAX_TERM_LENGTH).contains(&length)
+        {
+            Err(format!(
+                "term length must be a power of 2 between {} and {}",
+                ChannelUri::MIN_TERM_LENGTH,
+                ChannelUri::MAX_TERM_LENGTH
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl std::fmt::Display for TermLength {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&format_uri_size(self.0 as u64))
+    }
+}
+
+/// An `mtu` param, a multiple of 32 between [`ChannelUri::MIN_MTU_LENGTH`] and
+/// [`ChannelUri::MAX_MTU_LENGTH`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Mtu(u32);
+
+impl Mtu {
+    pub fn new(length: u32) -> Result<Self, ChannelUriError> {
+        Self::check(length as u64)
+            .map(|_| Mtu(length))
+            .map_err(|reason| ChannelUriError::InvalidValue {
+                key: ChannelUri::MTU_LENGTH.to_string(),
+                value: length.to_string(),
+                reason,
+            })
+    }
+
+    pub fn get(&self) -> u32 {
+        self.0
+    }
+
+    fn check(mtu: u64) -> Result<(), String> {
+        if mtu % 32 != 0
+            || !(ChannelUri::MIN_MTU_LENGTH..=ChannelUri::MAX_MTU_LENGTH).contains(&mtu)
+        {
+            Err(format!(
+                "mtu must be a multiple of 32 between {} and {}",
+                ChannelUri::MIN_MTU_LENGTH,
+                ChannelUri::MAX_MTU_LENGTH
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl std::fmt::Display for Mtu {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&format_uri_size(self.0 as u64))
+    }
+}
 
 impl ChannelUri {
     pub const AERON_SCHEME: &'static str = "aeron";
     pub const SPY_QUALIFIER: &'static str = "aeron-spy";
     pub const MAX_URI_LENGTH: usize = 4095;
+    pub const AERON_PREFIX: &'static str = "aeron:";
+    pub const SPY_PREFIX: &'static str = "aeron-spy:aeron:";
+
+    pub const ENDPOINT: &'static str = "endpoint";
+    pub const INTERFACE: &'static str = "interface";
+    pub const CONTROL: &'static str = "control";
+    pub const CONTROL_MODE: &'static str = "control-mode";
+    pub const TTL: &'static str = "ttl";
+    pub const RELIABLE: &'static str = "reliable";
+    pub const TAGS: &'static str = "tags";
+    pub const ALIAS: &'static str = "alias";
+    pub const INITIAL_TERM_ID: &'static str = "init-term-id";
+    pub const TERM_ID: &'static str = "term-id";
+    pub const TERM_OFFSET: &'static str = "term-offset";
+    pub const TERM_LENGTH: &'static str = "term-length";
+    pub const MTU_LENGTH: &'static str = "mtu";
+    pub const LINGER: &'static str = "linger";
+    pub const SPARSE: &'static str = "sparse";
+    pub const EOS: &'static str = "eos";
+    pub const TETHER: &'static str = "tether";
+    pub const SESSION_ID: &'static str = "session-id";
+    pub const GROUP: &'static str = "group";
+    pub const REJOIN: &'static str = "rejoin";
+    pub const FC: &'static str = "fc";
+    pub const GTAG: &'static str = "gtag";
+    pub const CC: &'static str = "cc";
+    pub const SPIES_SIMULATE_CONNECTION: &'static str = "ssc";
+    pub const ATS: &'static str = "ats";
+    pub const SOCKET_SNDBUF: &'static str = "so-sndbuf";
+    pub const SOCKET_RCVBUF: &'static str = "so-rcvbuf";
+    pub const RECEIVER_WINDOW: &'static str = "rcv-wnd";
+    pub const MEDIA_RCV_TIMESTAMP_OFFSET: &'static str = "media-rcv-ts-offset";
+    pub const CHANNEL_RCV_TIMESTAMP_OFFSET: &'static str = "channel-rcv-ts-offset";
+    pub const CHANNEL_SND_TIMESTAMP_OFFSET: &'static str = "channel-snd-ts-offset";
+    pub const RESPONSE_CORRELATION_ID: &'static str = "response-correlation-id";
+    pub const NAK_DELAY: &'static str = "nak-delay";
+    pub const UNTETHERED_WINDOW_LIMIT_TIMEOUT: &'static str = "untethered-window-limit-timeout";
+    pub const UNTETHERED_LINGER_TIMEOUT: &'static str = "untethered-linger-timeout";
+    pub const UNTETHERED_RESTING_TIMEOUT: &'static str = "untethered-resting-timeout";
+    pub const MAX_RESEND: &'static str = "max-resend";
+    pub const STREAM_ID: &'static str = "stream-id";
+    pub const PUBLICATION_WINDOW: &'static str = "pub-wnd";
+
+    pub const MIN_TERM_LENGTH: u64 = 64 * 1024;
+    pub const MAX_TERM_LENGTH: u64 = 1024 * 1024 * 1024;
+    pub const MIN_MTU_LENGTH: u64 = 32;
+    pub const MAX_MTU_LENGTH: u64 = 65504;
+
+    /// A uri for `media` without params.
+    pub fn new(media: Media) -> Self {
+        Self {
+            spy: false,
+            media: media.as_str().to_string(),
+            params: vec![],
+        }
+    }
+
+    pub fn parse(uri: &str) -> Result<Self, ChannelUriError> {
+        if uri.len() > Self::MAX_URI_LENGTH {
+            return Err(ChannelUriError::TooLong(uri.len()));
+        }
+        let (spy, rest) = match uri.strip_prefix(Self::SPY_PREFIX) {
+            Some(rest) => (true, rest),
+            None => (
+                false,
+                uri.strip_prefix(Self::AERON_PREFIX)
+                    .ok_or_else(|| ChannelUriError::InvalidScheme(uri.to_string()))?,
+            ),
+        };
+        let (media, params) = rest.split_once('?').unwrap_or((rest, ""));
+        if media != Media::Udp.as_str() && media != Media::Ipc.as_str() {
+            return Err(ChannelUriError::UnknownMedia(media.to_string()));
+        }
+
+        let mut channel = Self {
+            spy,
+            media: media.to_string(),
+            params: vec![],
+        };
+        for param in params.split('|').filter(|p| !p.is_empty()) {
+            let (key, value) = param
+                .split_once('=')
+                .filter(|(key, value)| !key.is_empty() && !value.is_empty())
+                .ok_or_else(|| ChannelUriError::MalformedParam(param.to_string()))?;
+            if channel.get(key).is_some() {
+                return Err(ChannelUriError::DuplicateParam(key.to_string()));
+            }
+            channel.set(key, value)?;
+        }
+        Ok(channel)
+    }
+
+    /// Checks `value` is valid for the known param `key`, unknown params are always valid.
+    pub fn validate_param(key: &str, value: &str) -> Result<(), ChannelUriError> {
+        let result = match key {
+            Self::CONTROL_MODE => match value {
+                "manual" | "dynamic" | "response" => Ok(()),
+                _ => Err("expected manual, dynamic or response".to_string()),
+            },
+            Self::CC => match value {
+                "static" | "cubic" => Ok(()),
+                _ => Err("expected static or cubic".to_string()),
+            },
+            Self::TERM_LENGTH => parse_uri_size(value).and_then(TermLength::check),
+            Self::MTU_LENGTH => parse_uri_size(value).and_then(Mtu::check),
+            Self::SOCKET_SNDBUF
+            | Self::SOCKET_RCVBUF
+            | Self::RECEIVER_WINDOW
+            | Self::PUBLICATION_WINDOW => parse_uri_size(value).and_then(|size| {
+                i32::try_from(size)
+                    .map(|_| ())
+                    .map_err(|_| "size exceeds i32::MAX".to_string())
+            }),
+            Self::LINGER
+            | Self::NAK_DELAY
+            | Self::UNTETHERED_WINDOW_LIMIT_TIMEOUT
+            | Self::UNTETHERED_LINGER_TIMEOUT
+            | Self::UNTETHERED_RESTING_TIMEOUT => parse_uri_duration(value).map(|_| ()),
+            Self::RELIABLE
+            | Self::SPARSE
+            | Self::EOS
+            | Self::TETHER
+            | Self::GROUP
+            | Self::REJOIN
+            | Self::SPIES_SIMULATE_CONNECTION
+            | Self::ATS => parse_uri_bool(value).map(|_| ()),
+            Self::TTL => parse_uri_number::<u8>(value).map(|_| ()),
+            Self::INITIAL_TERM_ID
+            | Self::TERM_ID
+            | Self::TERM_OFFSET
+            | Self::MAX_RESEND
+            | Self::STREAM_ID => parse_uri_number::<i32>(value).map(|_| ()),
+            Self::GTAG | Self::RESPONSE_CORRELATION_ID => {
+                parse_uri_number::<i64>(value).map(|_| ())
+            }
+            Self::SESSION_ID => SessionId::parse(value).map(|_| ()),
+            Self::MEDIA_RCV_TIMESTAMP_OFFSET
+            | Self::CHANNEL_RCV_TIMESTAMP_OFFSET
+            | Self::CHANNEL_SND_TIMESTAMP_OFFSET => TimestampOffset::parse(value).map(|_| ()),
+            Self::FC => FlowControl::parse_str(value).and_then(|fc| match fc {
+                FlowControl::Unicast => Err("unicast is not a valid fc param".to_string()),
+                _ => Ok(()),
+            }),
+            Self::TAGS => {
+                let tags = value.split(',').collect::<Vec<_>>();
+                if tags.len() > 2 {
+                    Err("expected at most a channel tag and a subscription tag".to_string())
+                } else {
+                    tags.iter()
+                        .filter(|tag| !tag.is_empty())
+                        .try_for_each(|tag| parse_uri_number::<i64>(tag).map(|_| ()))
+                }
+            }
+            _ => Ok(()),
+        };
+        result.map_err(|reason| ChannelUriError::InvalidValue {
+            key: key.to_string(),
+            value: value.to_string(),
+            reason,
+        })
+    }
+
+    /// Sets param `key`, replacing its value if it's already set.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<&mut Self, ChannelUriError> {
+        if key.is_empty() || key.contains(['=', '|', '?']) || value.contains('|') {
+            return Err(ChannelUriError::MalformedParam(format!("{key}={value}")));
+        }
+        Self::validate_param(key, value)?;
+        match self.params.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value.to_string(),
+            None => self.params.push((key.to_string(), value.to_string())),
+        }
+        Ok(self)
+    }
+
+    /// Removes param `key`, returning its value.
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        let index = self.params.iter().position(|(k, _)| k == key)?;
+        Some(self.params.remove(index).1)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Params in the order they appear in the uri.
+    pub fn params(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.params.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    pub fn is_spy(&self) -> bool {
+        self.spy
+    }
+
+    pub fn set_spy(&mut self, spy: bool) -> &mut Self {
+        self.spy = spy;
+        self
+    }
+
+    pub fn media(&self) -> Option<Media> {
+        match self.media.as_str() {
+            "udp" => Some(Media::Udp),
+            "ipc" => Some(Media::Ipc),
+            _ => None,
+        }
+    }
+
+    pub fn is_udp(&self) -> bool {
+        self.media() == Some(Media::Udp)
+    }
+
+    pub fn is_ipc(&self) -> bool {
+        self.media() == Some(Media::Ipc)
+    }
+
+    fn get_parsed<T>(&self, key: &str, parse: impl Fn(&str) -> Result<T, String>) -> Option<T> {
+        self.get(key).and_then(|value| parse(value).ok())
+    }
+
+    pub fn endpoint(&self) -> Option<&str> {
+        self.get(Self::ENDPOINT)
+    }
+
+    pub fn interface(&self) -> Option<&str> {
+        self.get(Self::INTERFACE)
+    }
+
+    pub fn control(&self) -> Option<&str> {
+        self.get(Self::CONTROL)
+    }
+
+    pub fn control_mode(&self) -> Option<ControlMode> {
+        match self.get(Self::CONTROL_MODE)? {
+            "manual" => Some(ControlMode::Manual),
+            "dynamic" => Some(ControlMode::Dynamic),
+            "response" => Some(ControlMode::Response),
+            _ => None,
+        }
+    }
+
+    pub fn ttl(&self) -> Option<u8> {
+        self.get_parsed(Self::TTL, parse_uri_number)
+    }
+
+    pub fn reliable(&self) -> Option<bool> {
+        self.get_parsed(Self::RELIABLE, parse_uri_bool)
+    }
+
+    pub fn tags(&self) -> Option<&str> {
+        self.get(Self::TAGS)
+    }
+
+    /// First of the `tags`, identifying the channel's endpoint.
+    pub fn channel_tag(&self) -> Option<i64> {
+        let tag = self.tags()?.split(',').next()?;
+        parse_uri_number(tag).ok()
+    }
+
+    /// Second of the `tags`, identifying the subscription.
+    pub fn subscription_tag(&self) -> Option<i64> {
+        let tag = self.tags()?.split(',').nth(1)?;
+        parse_uri_number(tag).ok()
+    }
+
+    pub fn alias(&self) -> Option<&str> {
+        self.get(Self::ALIAS)
+    }
+
+    pub fn initial_term_id(&self) -> Option<i32> {
+        self.get_parsed(Self::INITIAL_TERM_ID, parse_uri_number)
+    }
+
+    pub fn term_id(&self) -> Option<i32> {
+        self.get_parsed(Self::TERM_ID, parse_uri_number)
+    }
+
+    pub fn term_offset(&self) -> Option<i32> {
+        self.get_parsed(Self::TERM_OFFSET, parse_uri_number)
+    }
+
+    pub fn term_length(&self) -> Option<u32> {
+        self.get_parsed(Self::TERM_LENGTH, parse_uri_size)
+            .map(|length| length as u32)
+    }
+
+    pub fn mtu_length(&self) -> Option<u32> {
+        self.get_parsed(Self::MTU_LENGTH, parse_uri_size)
+            .map(|mtu| mtu as u32)
+    }
+
+    pub fn linger(&self) -> Option<std::time::Duration> {
+        self.get_parsed(Self::LINGER, parse_uri_duration)
+    }
+
+    pub fn sparse(&self) -> Option<bool> {
+        self.get_parsed(Self::SPARSE, parse_uri_bool)
+    }
+
+    pub fn eos(&self) -> Option<bool> {
+        self.get_parsed(Self::EOS, parse_uri_bool)
+    }
+
+    pub fn tether(&self) -> Option<bool> {
+        self.get_parsed(Self::TETHER, parse_uri_bool)
+    }
+
+    pub fn session_id(&self) -> Option<SessionId> {
+        self.get_parsed(Self::SESSION_ID, SessionId::parse)
+    }
+
+    pub fn group(&self) -> Option<bool> {
+        self.get_parsed(Self::GROUP, parse_uri_bool)
+    }
+
+    pub fn rejoin(&self) -> Option<bool> {
+        self.get_parsed(Self::REJOIN, parse_uri_bool)
+    }
+
+    /// Flow control strategy and its options, e.g. `min,g:100/3,t:5s`.
+    pub fn fc(&self) -> Option<&str> {
+        self.get(Self::FC)
+    }
+
+    pub fn flow_control(&self) -> Option<FlowControl> {
+        self.get_parsed(Self::FC, FlowControl::parse_str)
+    }
+
+    pub fn gtag(&self) -> Option<i64> {
+        self.get_parsed(Self::GTAG, parse_uri_number)
+    }
+
+    pub fn congestion_control(&self) -> Option<CongestionControl> {
+        match self.get(Self::CC)? {
+            "static" => Some(CongestionControl::StaticWindow),
+            "cubic" => Some(CongestionControl::Cubic),
+            _ => None,
+        }
+    }
+
+    pub fn spies_simulate_connection(&self) -> Option<bool> {
+        self.get_parsed(Self::SPIES_SIMULATE_CONNECTION, parse_uri_bool)
+    }
+
+    pub fn ats(&self) -> Option<bool> {
+        self.get_parsed(Self::ATS, parse_uri_bool)
+    }
+
+    pub fn socket_sndbuf(&self) -> Option<u32> {
+        self.get_parsed(Self::SOCKET_SNDBUF, parse_uri_size)
+            .map(|size| size as u32)
+    }
+
+    pub fn socket_rcvbuf(&self) -> Option<u32> {
+        self.get_parsed(Self::SOCKET_RCVBUF, parse_uri_size)
+            .map(|size| size as u32)
+    }
+
+    pub fn receiver_window(&self) -> Option<u32> {
+        self.get_parsed(Self::RECEIVER_WINDOW, parse_uri_size)
+            .map(|size| size as u32)
+    }
+
+    pub fn publication_window(&self) -> Option<u32> {
+        self.get_parsed(Self::PUBLICATION_WINDOW, parse_uri_size)
+            .map(|size| size as u32)
+    }
+
+    pub fn media_rcv_timestamp_offset(&self) -> Option<&str> {
+        self.get(Self::MEDIA_RCV_TIMESTAMP_OFFSET)
+    }
+
+    pub fn channel_rcv_timestamp_offset(&self) -> Option<&str> {
+        self.get(Self::CHANNEL_RCV_TIMESTAMP_OFFSET)
+    }
+
+    pub fn channel_snd_timestamp_offset(&self) -> Option<&str> {
+        self.get(Self::CHANNEL_SND_TIMESTAMP_OFFSET)
+    }
+
+    pub fn response_correlation_id(&self) -> Option<i64> {
+        self.get_parsed(Self::RESPONSE_CORRELATION_ID, parse_uri_number)
+    }
+
+    pub fn nak_delay(&self) -> Option<std::time::Duration> {
+        self.get_parsed(Self::NAK_DELAY, parse_uri_duration)
+    }
+
+    pub fn untethered_window_limit_timeout(&self) -> Option<std::time::Duration> {
+        self.get_parsed(Self::UNTETHERED_WINDOW_LIMIT_TIMEOUT, parse_uri_duration)
+    }
+
+    pub fn untethered_linger_timeout(&self) -> Option<std::time::Duration> {
+        self.get_parsed(Self::UNTETHERED_LINGER_TIMEOUT, parse_uri_duration)
+    }
+
+    pub fn untethered_resting_timeout(&self) -> Option<std::time::Duration> {
+        self.get_parsed(Self::UNTETHERED_RESTING_TIMEOUT, parse_uri_duration)
+    }
+
+    pub fn max_resend(&self) -> Option<i32> {
+        self.get_parsed(Self::MAX_RESEND, parse_uri_number)
+    }
+
+    pub fn stream_id(&self) -> Option<i32> {
+        self.get_parsed(Self::STREAM_ID, parse_uri_number)
+    }
+
+    /// Sets `init-term-id`, `term-id`, `term-offset` and `term-length` so a publication starts at
+    /// `position`, e.g. to resume an exclusive publication where a recording stopped.
+    pub fn set_initial_position(
+        &mut self,
+        position: i64,
+        initial_term_id: i32,
+        term_length: TermLength,
+    ) -> Result<&mut Self, ChannelUriError> {
+        let (term_id, term_offset) = initial_position_term(position, initial_term_id, term_length)?;
+        self.set(Self::INITIAL_TERM_ID, &initial_term_id.to_string())?
+            .set(Self::TERM_ID, &term_id.to_string())?
+            .set(Self::TERM_OFFSET, &term_offset.to_string())?
+            .set(Self::TERM_LENGTH, &term_length.to_string())
+    }
+}
+
+/// Term id and offset of `position` in a stream starting at `initial_term_id`.
+fn initial_position_term(
+    position: i64,
+    initial_term_id: i32,
+    term_length: TermLength,
+) -> Result<(i32, i32), ChannelUriError> {
+    if position < 0 || position % FRAME_ALIGNMENT as i64 != 0 {
+        return Err(ChannelUriError::InvalidCombination(format!(
+            "initial position {position} must be a non-negative multiple of {FRAME_ALIGNMENT}"
+        )));
+    }
+    let bits = term_length.get().trailing_zeros();
+    let term_id = initial_term_id.wrapping_add((position >> bits) as i32);
+    let term_offset = (position & (term_length.get() as i64 - 1)) as i32;
+    Ok((term_id, term_offset))
+}
+
+impl std::str::FromStr for ChannelUri {
+    type Err = ChannelUriError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ChannelUri::parse(s)
+    }
+}
+
+impl std::fmt::Display for ChannelUri {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.spy {
+            write!(f, "{}:", Self::SPY_QUALIFIER)?;
+        }
+        write!(f, "{}{}", Self::AERON_PREFIX, self.media)?;
+        for (i, (key, value)) in self.params.iter().enumerate() {
+            write!(f, "{}{key}={value}", if i == 0 { '?' } else { '|' })?;
+        }
+        Ok(())
+    }
+}
+
+/// Typed builder for a [`ChannelUri`], params are checked when set and their combination by
+/// [`build`](ChannelUriBuilder::build).
+///
+/// ```
+/// # use rusteron_code_gen::*;
+/// # use std::time::Duration;
+/// let uri = ChannelUriBuilder::udp()
+///     .endpoint("localhost:20121")
+///     .term_length(TermLength::new(64 * 1024).unwrap())
+///     .flow_control(FlowControl::min())
+///     .linger(Duration::from_millis(500))
+///     .build()
+///     .unwrap();
+/// assert_eq!(
+///     "aeron:udp?endpoint=localhost:20121|term-length=64k|fc=min|linger=500ms",
+///     uri.to_string()
+/// );
+///
+/// let missing_initial_term_id = ChannelUriBuilder::ipc().term_id(7).term_offset(0).build();
+/// assert!(matches!(missing_initial_term_id, Err(ChannelUriError::InvalidCombination(_))));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelUriBuilder {
+    uri: ChannelUri,
+    channel_tag: Option<i64>,
+    subscription_tag: Option<i64>,
+    error: Option<ChannelUriError>,
+}
+
+impl ChannelUriBuilder {
+    pub fn new(media: Media) -> Self {
+        Self {
+            uri: ChannelUri::new(media),
+            channel_tag: None,
+            subscription_tag: None,
+            error: None,
+        }
+    }
+
+    pub fn udp() -> Self {
+        Self::new(Media::Udp)
+    }
+
+    pub fn ipc() -> Self {
+        Self::new(Media::Ipc)
+    }
+
+    fn with(mut self, key: &str, value: impl std::fmt::Display) -> Self {
+        if let Err(e) = self.uri.set(key, &value.to_string()) {
+            self.error.get_or_insert(e);
+        }
+        self
+    }
+
+    fn with_duration(self, key: &str, duration: std::time::Duration) -> Self {
+        self.with(key, format_uri_duration(duration))
+    }
+
+    fn with_size(self, key: &str, size: u32) -> Self {
+        self.with(key, format_uri_size(size as u64))
+    }
+
+    pub fn spy(mut self, spy: bool) -> Self {
+        self.uri.set_spy(spy);
+        self
+    }
+
+    /// Sets any param, including ones without a typed setter.
+    pub fn param(self, key: &str, value: &str) -> Self {
+        self.with(key, value)
+    }
+
+    pub fn endpoint(self, endpoint: &str) -> Self {
+        self.with(ChannelUri::ENDPOINT, endpoint)
+    }
+
+    pub fn interface(self, interface: &str) -> Self {
+        self.with(ChannelUri::INTERFACE, interface)
+    }
+
+    pub fn control(self, control: &str) -> Self {
+        self.with(ChannelUri::CONTROL, control)
+    }
+
+    pub fn control_mode(self, control_mode: ControlMode) -> Self {
+        self.with(ChannelUri::CONTROL_MODE, control_mode.as_str())
+    }
+
+    pub fn ttl(self, ttl: u8) -> Self {
+        self.with(ChannelUri::TTL, ttl)
+    }
+
+    pub fn reliable(self, reliable: bool) -> Self {
+        self.with(ChannelUri::RELIABLE, reliable)
+    }
+
+    pub fn channel_tag(mut self, tag: i64) -> Self {
+        self.channel_tag = Some(tag);
+        self
+    }
+
+    pub fn subscription_tag(mut self, tag: i64) -> Self {
+        self.subscription_tag = Some(tag);
+        self
+    }
+
+    pub fn alias(self, alias: &str) -> Self {
+        self.with(ChannelUri::ALIAS, alias)
+    }
+
+    pub fn initial_term_id(self, initial_term_id: i32) -> Self {
+        self.with(ChannelUri::INITIAL_TERM_ID, initial_term_id)
+    }
+
+    pub fn term_id(self, term_id: i32) -> Self {
+        self.with(ChannelUri::TERM_ID, term_id)
+    }
+
+    pub fn term_offset(self, term_offset: i32) -> Self {
+        self.with(ChannelUri::TERM_OFFSET, term_offset)
+    }
+
+    /// Sets `init-term-id`, `term-id`, `term-offset` and `term-length` so a publication starts at
+    /// `position`, e.g. to continue a recorded stream.
+    pub fn initial_position(
+        mut self,
+        position: i64,
+        initial_term_id: i32,
+        term_length: TermLength,
+    ) -> Self {
+        let (term_id, term_offset) =
+            match initial_position_term(position, initial_term_id, term_length) {
+                Ok(term) => term,
+                Err(e) => {
+                    self.error.get_or_insert(e);
+                    return self;
+                }
+            };
+        self.initial_term_id(initial_term_id)
+            .term_id(term_id)
+            .term_offset(term_offset)
+            .term_length(term_length)
+    }
+
+    pub fn term_length(self, term_length: TermLength) -> Self {
+        self.with(ChannelUri::TERM_LENGTH, term_length)
+    }
+
+    pub fn mtu(self, mtu: Mtu) -> Self {
+        self.with(ChannelUri::MTU_LENGTH, mtu)
+    }
+
+    pub fn linger(self, linger: std::time::Duration) -> Self {
+        self.with_duration(ChannelUri::LINGER, linger)
+    }
+
+    pub fn sparse(self, sparse: bool) -> Self {
+        self.with(ChannelUri::SPARSE, sparse)
+    }
+
+    pub fn eos(self, eos: bool) -> Self {
+        self.with(ChannelUri::EOS, eos)
+    }
+
+    pub fn tether(self, tether: bool) -> Self {
+        self.with(ChannelUri::TETHER, tether)
+    }
+
+    pub fn session_id(self, session_id: SessionId) -> Self {
+        self.with(ChannelUri::SESSION_ID, session_id)
+    }
+
+    pub fn group(self, group: bool) -> Self {
+        self.with(ChannelUri::GROUP, group)
+    }
+
+    pub fn rejoin(self, rejoin: bool) -> Self {
+        self.with(ChannelUri::REJOIN, rejoin)
+    }
+
+    pub fn flow_control(self, flow_control: FlowControl) -> Self {
+        self.with(ChannelUri::FC, flow_control)
+    }
+
+    pub fn gtag(self, gtag: i64) -> Self {
+        self.with(ChannelUri::GTAG, gtag)
+    }
+
+    pub fn congestion_control(self, congestion_control: CongestionControl) -> Self {
+        self.with(ChannelUri::CC, congestion_control.as_str())
+    }
+
+    pub fn spies_simulate_connection(self, spies_simulate_connection: bool) -> Self {
+        self.with(
+            ChannelUri::SPIES_SIMULATE_CONNECTION,
+            spies_simulate_connection,
+        )
+    }
+
+    pub fn ats(self, ats: bool) -> Self {
+        self.with(ChannelUri::ATS, ats)
+    }
+
+    pub fn socket_sndbuf(self, size: u32) -> Self {
+        self.with_size(ChannelUri::SOCKET_SNDBUF, size)
+    }
+
+    pub fn socket_rcvbuf(self, size: u32) -> Self {
+        self.with_size(ChannelUri::SOCKET_RCVBUF, size)
+    }
+
+    pub fn receiver_window(self, size: u32) -> Self {
+        self.with_size(ChannelUri::RECEIVER_WINDOW, size)
+    }
+
+    pub fn publication_window(self, size: u32) -> Self {
+        self.with_size(ChannelUri::PUBLICATION_WINDOW, size)
+    }
+
+    /// Timestamp of when the media layer received a packet.
+    pub fn media_rcv_timestamp_offset(self, offset: TimestampOffset) -> Self {
+        self.with(ChannelUri::MEDIA_RCV_TIMESTAMP_OFFSET, offset)
+    }
+
+    /// Timestamp of when the driver's receiver read a packet from the channel.
+    pub fn channel_rcv_timestamp_offset(self, offset: TimestampOffset) -> Self {
+        self.with(ChannelUri::CHANNEL_RCV_TIMESTAMP_OFFSET, offset)
+    }
+
+    /// Timestamp of when the driver's sender wrote a packet to the channel.
+    pub fn channel_snd_timestamp_offset(self, offset: TimestampOffset) -> Self {
+        self.with(ChannelUri::CHANNEL_SND_TIMESTAMP_OFFSET, offset)
+    }
+
+    pub fn response_correlation_id(self, correlation_id: i64) -> Self {
+        self.with(ChannelUri::RESPONSE_CORRELATION_ID, correlation_id)
+    }
+
+    pub fn nak_delay(self, delay: std::time::Duration) -> Self {
+        self.with_duration(ChannelUri::NAK_DELAY, delay)
+    }
+
+    pub fn untethered_window_limit_timeout(self, timeout: std::time::Duration) -> Self {
+        self.with_duration(ChannelUri::UNTETHERED_WINDOW_LIMIT_TIMEOUT, timeout)
+    }
+
+    pub fn untethered_linger_timeout(self, timeout: std::time::Duration) -> Self {
+        self.with_duration(ChannelUri::UNTETHERED_LINGER_TIMEOUT, timeout)
+    }
+
+    pub fn untethered_resting_timeout(self, timeout: std::time::Duration) -> Self {
+        self.with_duration(ChannelUri::UNTETHERED_RESTING_TIMEOUT, timeout)
+    }
+
+    pub fn max_resend(self, max_resend: i32) -> Self {
+        self.with(ChannelUri::MAX_RESEND, max_resend)
+    }
+
+    pub fn stream_id(self, stream_id: i32) -> Self {
+        self.with(ChannelUri::STREAM_ID, stream_id)
+    }
+
+    /// The uri, or the first invalid param or combination of params.
+    pub fn build(self) -> Result<ChannelUri, ChannelUriError> {
+        let mut builder = self;
+        if builder.channel_tag.is_some() || builder.subscription_tag.is_some() {
+            let tag = |tag: Option<i64>| tag.map(|t| t.to_string()).unwrap_or_default();
+            let tags = match builder.subscription_tag {
+                Some(_) => format!(
+                    "{},{}",
+                    tag(builder.channel_tag),
+                    tag(builder.subscription_tag)
+                ),
+                None => tag(builder.channel_tag),
+            };
+            builder = builder.with(ChannelUri::TAGS, tags);
+        }
+        if let Some(e) = builder.error {
+            return Err(e);
+        }
+        let uri = builder.uri;
+        Self::validate(&uri)?;
+        Ok(uri)
+    }
+
+    fn validate(uri: &ChannelUri) -> Result<(), ChannelUriError> {
+        let invalid = |reason: String| Err(ChannelUriError::InvalidCombination(reason));
+
+        if uri.is_ipc() {
+            let udp_only = [
+                ChannelUri::ENDPOINT,
+                ChannelUri::INTERFACE,
+                ChannelUri::CONTROL,
+                ChannelUri::CONTROL_MODE,
+                ChannelUri::TTL,
+            ];
+            if let Some(key) = udp_only.iter().find(|key| uri.get(key).is_some()) {
+                return invalid(format!("{key} is only valid for udp"));
+            }
+        } else {
+            let control_mode = uri.control_mode();
+            if uri.endpoint().is_none()
+                && uri.control().is_none()
+                && !matches!(
+                    control_mode,
+                    Some(ControlMode::Manual | ControlMode::Response)
+                )
+            {
+                return invalid("udp needs an endpoint or control".to_string());
+            }
+            if control_mode == Some(ControlMode::Dynamic) && uri.control().is_none() {
+                return invalid("control-mode=dynamic needs a control endpoint".to_string());
+            }
+        }
+
+        let position = (uri.initial_term_id(), uri.term_id(), uri.term_offset());
+        match position {
+            (None, None, None) => {}
+            (Some(initial_term_id), Some(term_id), Some(term_offset)) => {
+                if term_id.wrapping_sub(initial_term_id) < 0 {
+                    return invalid(format!(
+                        "term-id {term_id} is more than 2^31 - 1 terms after init-term-id {initial_term_id}"
+                    ));
+                }
+                if term_offset < 0 || term_offset % 32 != 0 {
+                    return invalid(format!(
+                        "term-offset {term_offset} must be a non-negative multiple of 32"
+                    ));
+                }
+                if let Some(term_length) = uri.term_length() {
+                    if term_offset as u32 > term_length {
+                        return invalid(format!(
+                            "term-offset {term_offset} exceeds term-length {term_length}"
+                        ));
+                    }
+                }
+            }
+            _ => {
+                return invalid(
+                    "init-term-id, term-id and term-offset must be set together".to_string(),
+                )
+            }
+        }
+        Ok(())
+    }
 }
 
 pub const DRIVER_TIMEOUT_MS_DEFAULT: u64 = 10_000;
@@ -592,6 +1861,148 @@ impl ControlMode {
     }
 }
 
+/// Enum for receiver congestion control strategies, the `cc` uri param.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CongestionControl {
+    StaticWindow,
+    Cubic,
+}
+
+impl CongestionControl {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CongestionControl::StaticWindow => "static",
+            CongestionControl::Cubic => "cubic",
+        }
+    }
+}
+
+/// Options of the `min` and `tagged` flow control strategies, unset options use the driver
+/// context defaults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct FlowControlOptions {
+    /// `g:<tag>`, only receivers with this tag are tracked
+    pub group_tag: Option<i64>,
+    /// `g:<tag>/<n>` or `g:/<n>`, receivers required before the publication is connected
+    pub group_min_size: Option<i32>,
+    /// `t:<duration>`, time after which a silent receiver is removed
+    pub timeout: Option<std::time::Duration>,
+}
+
+/// Flow control strategies built into the driver, the `fc` uri param.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FlowControl {
+    /// sender limit of the fastest receiver, default for multicast
+    Max,
+    /// sender limit of the slowest receiver
+    Min(FlowControlOptions),
+    /// sender limit of the slowest receiver within a tagged group
+    Tagged(FlowControlOptions),
+    /// default for unicast, only valid as the driver's unicast strategy and not as an `fc` uri
+    /// param
+    Unicast,
+}
+
+impl FlowControl {
+    pub fn min() -> Self {
+        FlowControl::Min(FlowControlOptions::default())
+    }
+
+    pub fn tagged(group_tag: i64) -> Self {
+        FlowControl::Tagged(FlowControlOptions {
+            group_tag: Some(group_tag),
+            ..Default::default()
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FlowControl::Max => "max",
+            FlowControl::Min(_) => "min",
+            FlowControl::Tagged(_) => "tagged",
+            FlowControl::Unicast => "unicast",
+        }
+    }
+
+    pub fn options(&self) -> Option<&FlowControlOptions> {
+        match self {
+            FlowControl::Min(options) | FlowControl::Tagged(options) => Some(options),
+            FlowControl::Max | FlowControl::Unicast => None,
+        }
+    }
+
+    /// Parses an `fc` uri param value, e.g. `tagged,g:1001/3,t:5s`.
+    pub fn parse(value: &str) -> Result<Self, AeronCError> {
+        Self::parse_str(value).map_err(|reason| {
+            log::error!("invalid flow control '{value}': {reason}");
+            AeronCError::from_code(-1)
+        })
+    }
+
+    fn parse_str(value: &str) -> Result<Self, String> {
+        let mut parts = value.split(',');
+        let name = parts.next().unwrap_or_default();
+        let mut options = FlowControlOptions::default();
+        for option in parts {
+            if let Some(group) = option.strip_prefix("g:") {
+                let (tag, min_size) = match group.split_once('/') {
+                    Some((tag, min_size)) => (tag, Some(min_size)),
+                    None => (group, None),
+                };
+                if !tag.is_empty() {
+                    options.group_tag = Some(parse_uri_number(tag)?);
+                }
+                if let Some(min_size) = min_size {
+                    options.group_min_size = Some(parse_uri_number(min_size)?);
+                }
+            } else if let Some(timeout) = option.strip_prefix("t:") {
+                options.timeout = Some(parse_uri_duration(timeout)?);
+            } else {
+                return Err(format!("unknown option {option:?}"));
+            }
+        }
+
+        let has_options = options != FlowControlOptions::default();
+        match name {
+            "min" => Ok(FlowControl::Min(options)),
+            "tagged" => Ok(FlowControl::Tagged(options)),
+            "max" | "unicast" if has_options => Err(format!("{name} doesn't take options")),
+            "max" => Ok(FlowControl::Max),
+            "unicast" => Ok(FlowControl::Unicast),
+            _ => Err("expected max, min or tagged".to_string()),
+        }
+    }
+}
+
+impl std::str::FromStr for FlowControl {
+    type Err = AeronCError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        FlowControl::parse(s)
+    }
+}
+
+impl std::fmt::Display for FlowControl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())?;
+        if let Some(options) = self.options() {
+            if options.group_tag.is_some() || options.group_min_size.is_some() {
+                f.write_str(",g:")?;
+                if let Some(tag) = options.group_tag {
+                    write!(f, "{tag}")?;
+                }
+                if let Some(size) = options.group_min_size {
+                    write!(f, "/{size}")?;
+                }
+            }
+            if let Some(timeout) = options.timeout {
+                write!(f, ",t:{}", format_uri_duration(timeout))?;
+            }
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 #[allow(dead_code)]
 pub(crate) mod test_alloc {
@@ -403,4 +403,269 @@ mod test {
         }
         assert!(flag.load(Ordering::SeqCst));
     }
+
+    #[test]
+    fn channel_uri_round_trip() {
+        use crate::{ChannelUri, ChannelUriError, ControlMode, Media, SessionId};
+        use std::time::Duration;
+
+        let uri = "aeron:udp?endpoint=localhost:20121|control=localhost:20122|control-mode=dynamic\
+                   |term-length=64k|mtu=8k|session-id=tag:1001|tags=1,2|linger=5s|reliable=false|alias=x=y";
+        let channel: ChannelUri = uri.parse().unwrap();
+        assert_eq!(uri, channel.to_string());
+        assert_eq!(Some(Media::Udp), channel.media());
+        assert_eq!(Some("localhost:20121"), channel.endpoint());
+        assert_eq!(Some("localhost:20122"), channel.control());
+        assert_eq!(Some(ControlMode::Dynamic), channel.control_mode());
+        assert_eq!(Some(64 * 1024), channel.term_length());
+        assert_eq!(Some(8 * 1024), channel.mtu_length());
+        assert_eq!(Some(SessionId::Tag(1001)), channel.session_id());
+        assert_eq!(
+            (Some(1), Some(2)),
+            (channel.channel_tag(), channel.subscription_tag())
+        );
+        assert_eq!(Some(Duration::from_secs(5)), channel.linger());
+        assert_eq!(Some(false), channel.reliable());
+        assert_eq!(Some("x=y"), channel.alias());
+        assert_eq!(None, channel.interface());
+
+        let spy: ChannelUri = "aeron-spy:aeron:ipc".parse().unwrap();
+        assert!(spy.is_spy() && spy.is_ipc());
+        assert_eq!("aeron-spy:aeron:ipc", spy.to_string());
+
+        let mut channel = ChannelUri::new(Media::Ipc);
+        channel
+            .set("term-length", "1m")
+            .unwrap()
+            .set("eos", "true")
+            .unwrap();
+        assert_eq!("aeron:ipc?term-length=1m|eos=true", channel.to_string());
+        assert_eq!(Some("1m".to_string()), channel.remove("term-length"));
+        assert_eq!("aeron:ipc?eos=true", channel.to_string());
+
+        assert_eq!(
+            Err(ChannelUriError::InvalidScheme(
+                "udp://localhost".to_string()
+            )),
+            "udp://localhost".parse::<ChannelUri>()
+        );
+        assert_eq!(
+            Err(ChannelUriError::UnknownMedia("tcp".to_string())),
+            "aeron:tcp".parse::<ChannelUri>()
+        );
+        assert_eq!(
+            Err(ChannelUriError::MalformedParam("endpoint".to_string())),
+            "aeron:udp?endpoint".parse::<ChannelUri>()
+        );
+        assert_eq!(
+            Err(ChannelUriError::DuplicateParam("mtu".to_string())),
+            "aeron:udp?mtu=1408|mtu=1408".parse::<ChannelUri>()
+        );
+        let error = "aeron:udp?term-length=100k"
+            .parse::<ChannelUri>()
+            .unwrap_err();
+        assert_eq!(
+            "invalid value \"100k\" for term-length: term length must be a power of 2 between 65536 and 1073741824",
+            error.to_string()
+        );
+        assert!("aeron:udp?mtu=1400".parse::<ChannelUri>().is_err());
+        assert!("aeron:udp?session-id=tag:x".parse::<ChannelUri>().is_err());
+        assert!("aeron:udp?cc=reno".parse::<ChannelUri>().is_err());
+    }
+
+    #[test]
+    fn channel_uri_builder() {
+        use crate::{
+            ChannelUri, ChannelUriBuilder, ChannelUriError, ControlMode, FlowControl,
+            FlowControlOptions, Mtu, SessionId, TermLength, TimestampOffset,
+        };
+        use std::time::Duration;
+
+        let uri = ChannelUriBuilder::udp()
+            .endpoint("localhost:20121")
+            .control("localhost:20122")
+            .control_mode(ControlMode::Dynamic)
+            .mtu(Mtu::new(8 * 1024).unwrap())
+            .session_id(SessionId::Tag(1001))
+            .flow_control(FlowControl::Tagged(FlowControlOptions {
+                group_tag: Some(7),
+                group_min_size: Some(2),
+                timeout: Some(Duration::from_millis(250)),
+            }))
+            .channel_tag(1)
+            .subscription_tag(2)
+            .build()
+            .unwrap();
+        assert_eq!(
+            "aeron:udp?endpoint=localhost:20121|control=localhost:20122|control-mode=dynamic\
+             |mtu=8k|session-id=tag:1001|fc=tagged,g:7/2,t:250ms|tags=1,2",
+            uri.to_string()
+        );
+        assert_eq!(uri, uri.to_string().parse::<ChannelUri>().unwrap());
+        assert_eq!(
+            Some(FlowControl::Tagged(FlowControlOptions {
+                group_tag: Some(7),
+                group_min_size: Some(2),
+                timeout: Some(Duration::from_millis(250)),
+            })),
+            uri.flow_control()
+        );
+
+        let uri = ChannelUriBuilder::ipc()
+            .initial_position(3 * 64 * 1024 + 96, 10, TermLength::new(64 * 1024).unwrap())
+            .build()
+            .unwrap();
+        assert_eq!(
+            "aeron:ipc?init-term-id=10|term-id=13|term-offset=96|term-length=64k",
+            uri.to_string()
+        );
+
+        let uri = ChannelUriBuilder::udp()
+            .endpoint("localhost:20121")
+            .media_rcv_timestamp_offset(TimestampOffset::Reserved)
+            .channel_snd_timestamp_offset(TimestampOffset::Offset(8))
+            .build()
+            .unwrap();
+        assert_eq!(
+            "aeron:udp?endpoint=localhost:20121|media-rcv-ts-offset=reserved\
+             |channel-snd-ts-offset=8",
+            uri.to_string()
+        );
+        assert_eq!(Some("reserved"), uri.media_rcv_timestamp_offset());
+        assert_eq!(Some("8"), uri.channel_snd_timestamp_offset());
+        assert_eq!(None, uri.channel_rcv_timestamp_offset());
+        assert!("aeron:udp?channel-rcv-ts-offset=-8"
+            .parse::<ChannelUri>()
+            .is_err());
+
+        assert!(TermLength::new(100 * 1024).is_err());
+        assert!(Mtu::new(1400).is_err());
+        assert!("aeron:udp?fc=unicast".parse::<ChannelUri>().is_err());
+        assert!("aeron:udp?fc=max,t:5s".parse::<ChannelUri>().is_err());
+
+        let invalid = |builder: ChannelUriBuilder| {
+            matches!(builder.build(), Err(ChannelUriError::InvalidCombination(_)))
+        };
+        assert!(invalid(ChannelUriBuilder::ipc().term_id(7).term_offset(0)));
+        assert!(invalid(
+            ChannelUriBuilder::ipc()
+                .initial_term_id(0)
+                .term_id(1)
+                .term_offset(33)
+        ));
+        assert!(invalid(
+            ChannelUriBuilder::ipc().endpoint("localhost:20121")
+        ));
+        assert!(invalid(ChannelUriBuilder::udp()));
+        assert!(invalid(
+            ChannelUriBuilder::udp()
+                .endpoint("localhost:20121")
+                .control_mode(ControlMode::Dynamic)
+        ));
+        assert!(ChannelUriBuilder::udp()
+            .control_mode(ControlMode::Manual)
+            .build()
+            .is_ok());
+        assert!(matches!(
+            ChannelUriBuilder::udp().endpoint("a|b").build(),
+            Err(ChannelUriError::MalformedParam(_))
+        ));
+    }
+
+    #[test]
+    fn crc32_matches_ieee() {
+        use crate::crc32;
+
+        assert_eq!(0, crc32(b""));
+        assert_eq!(0xCBF4_3926, crc32(b"123456789"));
+        assert_eq!(
+            0x414F_A339,
+            crc32(b"The quick brown fox jumps over the lazy dog")
+        );
+    }
+
+    #[test]
+    fn block_target_validate() {
+        use crate::{BlockError, BlockTarget, HDR_TYPE_DATA, HDR_TYPE_PAD};
+
+        let target = BlockTarget {
+            session_id: 7,
+            stream_id: 1001,
+            term_id: 3,
+            term_offset: 64,
+            term_length: 64 * 1024,
+        };
+        let frame = |frame_length: i32, frame_type: u16, term_offset: i32| {
+            let mut frame = vec![0u8; (frame_length as usize).next_multiple_of(32)];
+            frame[0..4].copy_from_slice(&frame_length.to_le_bytes());
+            frame[6..8].copy_from_slice(&frame_type.to_le_bytes());
+            frame[8..12].copy_from_slice(&term_offset.to_le_bytes());
+            frame[12..16].copy_from_slice(&7i32.to_le_bytes());
+            frame[16..20].copy_from_slice(&1001i32.to_le_bytes());
+            frame[20..24].copy_from_slice(&3i32.to_le_bytes());
+            frame
+        };
+
+        let block = [frame(40, HDR_TYPE_DATA, 64), frame(64, HDR_TYPE_PAD, 128)].concat();
+        assert_eq!(Ok(()), target.validate(&block));
+        assert_eq!(Err(BlockError::Empty), target.validate(&[]));
+        assert_eq!(
+            Err(BlockError::InvalidFrameType {
+                offset: 0,
+                frame_type: HDR_TYPE_PAD
+            }),
+            target.validate(&frame(32, HDR_TYPE_PAD, 64))
+        );
+        assert_eq!(
+            Err(BlockError::Mismatch {
+                offset: 64,
+                field: "term offset",
+                expected: 128,
+                actual: 96
+            }),
+            target.validate(&[frame(40, HDR_TYPE_DATA, 64), frame(32, HDR_TYPE_DATA, 96)].concat())
+        );
+        let mut truncated = frame(64, HDR_TYPE_DATA, 64);
+        truncated[0..4].copy_from_slice(&96i32.to_le_bytes());
+        assert_eq!(
+            Err(BlockError::InvalidFrameLength {
+                offset: 0,
+                frame_length: 96
+            }),
+            target.validate(&truncated)
+        );
+        assert_eq!(
+            Err(BlockError::InvalidFrameLength {
+                offset: 64,
+                frame_length: 16
+            }),
+            target.validate(&[&block[..64], &[0u8; 16]].concat())
+        );
+        assert!(matches!(
+            BlockTarget {
+                term_offset: 64 * 1024 - 32,
+                ..target
+            }
+            .validate(&block),
+            Err(BlockError::ExceedsTerm { .. })
+        ));
+    }
+
+    #[test]
+    fn channel_uri_set_initial_position() {
+        use crate::{ChannelUri, ChannelUriError, TermLength};
+
+        let term_length = TermLength::new(64 * 1024).unwrap();
+        let mut uri = ChannelUri::parse("aeron:udp?endpoint=localhost:20121|term-id=1").unwrap();
+        uri.set_initial_position(2 * 64 * 1024 + 256, 5, term_length)
+            .unwrap();
+        assert_eq!(Some(5), uri.initial_term_id());
+        assert_eq!(Some(7), uri.term_id());
+        assert_eq!(Some(256), uri.term_offset());
+        assert_eq!(Some(64 * 1024), uri.term_length());
+        assert!(matches!(
+            uri.set_initial_position(100, 5, term_length),
+            Err(ChannelUriError::InvalidCombination(_))
+        ));
+    }
 }
@@ -2,6 +2,7 @@ use log::info;
 use rusteron_media_driver::*;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Flag to indicate when the application should stop (set on Ctrl+C)
@@ -17,6 +18,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let aeron_context = AeronDriverContext::new()?;
     info!("aeron dir: {:?}", aeron_context.get_dir());
     aeron_context.print_configuration();
+    // Track duty cycles only when asked to, e.g. RUSTERON_DUTY_CYCLE_REPORT_SECS=10 logs them every 10s
+    let duty_cycles = match std::env::var("RUSTERON_DUTY_CYCLE_REPORT_SECS") {
+        Ok(secs) => Some((
+            aeron_context.track_duty_cycles()?,
+            Duration::from_secs(secs.parse()?),
+        )),
+        Err(_) => None,
+    };
 
     // Create Aeron driver
     let aeron_driver = AeronDriver::new(&aeron_context)?;
@@ -25,10 +34,20 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     info!("Aeron media driver started successfully. Press Ctrl+C to stop.");
 
     // Poll for work until Ctrl+C is pressed
+    let mut last_report = Instant::now();
     while running.load(Ordering::Acquire) {
         aeron_driver.main_idle_strategy(aeron_driver.main_do_work()?);
+        if let Some((duty_cycles, interval)) = &duty_cycles {
+            if last_report.elapsed() >= *interval {
+                info!("duty cycles:\n{duty_cycles}");
+                last_report = Instant::now();
+            }
+        }
     }
     info!("Received signal to stop the media driver.");
+    if let Some((duty_cycles, _)) = &duty_cycles {
+        info!("duty cycles:\n{duty_cycles}");
+    }
     info!("Aeron media driver stopped successfully.");
     Ok(())
 }
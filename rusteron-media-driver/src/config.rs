@@ -0,0 +1,324 @@
+//! Snapshot of a driver context's effective configuration.
+//!
+//! Unlike `print_configuration`, which writes to stdout, [`DriverConfigSnapshot`] captures every
+//! setting as a value so it can be logged, serialized (with the `serde` feature) or compared with
+//! the declared configuration.
+//!
+//! ```no_run
+//! # use rusteron_media_driver::*;
+//! # fn run() -> Result<(), AeronCError> {
+//! let expected = AeronDriverContext::new()?;
+//! expected.set_term_buffer_length(64 * 1024)?;
+//!
+//! let running = AeronDriverContext::new()?;
+//! for difference in expected.effective_config().diff(&running.effective_config()) {
+//!     println!("{difference}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use crate::bindings::*;
+use crate::port_manager::PortRange;
+use crate::AeronDriverContext;
+use std::ffi::CStr;
+use std::fmt;
+
+/// Formats a setting for [`DriverConfigSnapshot`]'s `name=value` output and diffs.
+trait ConfigValue {
+    fn to_config_string(&self) -> String;
+}
+
+macro_rules! impl_config_value {
+    ($($ty:ty),*) => {
+        $(impl ConfigValue for $ty {
+            fn to_config_string(&self) -> String {
+                self.to_string()
+            }
+        })*
+    };
+}
+
+impl_config_value!(bool, u8, u32, u64, usize, i32, i64, String, PortRange);
+
+impl<T: ConfigValue> ConfigValue for Option<T> {
+    fn to_config_string(&self) -> String {
+        self.as_ref()
+            .map(ConfigValue::to_config_string)
+            .unwrap_or_default()
+    }
+}
+
+impl ConfigValue for Vec<String> {
+    fn to_config_string(&self) -> String {
+        self.join(",")
+    }
+}
+
+/// Names a C supplier function after the built in it points to, or `custom` for anything else
+/// such as the strategies implemented in Rust.
+macro_rules! supplier_name {
+    ($supplier:expr, $($known:ident => $name:literal),* $(,)?) => {
+        match $supplier {
+            None => String::new(),
+            $(Some(supplier) if is_function(supplier, $known) => $name.to_string(),)*
+            Some(_) => "custom".to_string(),
+        }
+    };
+}
+
+/// Whether the function pointer `value` points to `function`, which is coerced to the same type.
+fn is_function<F: Copy + PartialEq>(value: F, function: F) -> bool {
+    value == function
+}
+
+/// Generates [`DriverConfigSnapshot`] with a field per setting, captured from the context getters.
+macro_rules! driver_config {
+    ($ctx:ident; $($field:ident: $ty:ty = $value:expr;)*) => {
+        /// Every setting of a driver context, see the [module docs](self).
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        pub struct DriverConfigSnapshot {
+            $(pub $field: $ty,)*
+        }
+
+        impl DriverConfigSnapshot {
+            fn capture($ctx: &AeronDriverContext) -> Self {
+                Self {
+                    $($field: $value,)*
+                }
+            }
+
+            /// Settings as `(name, value)` pairs, in declaration order.
+            pub fn entries(&self) -> Vec<(&'static str, String)> {
+                vec![$((stringify!($field), self.$field.to_config_string()),)*]
+            }
+        }
+    };
+}
+
+driver_config! { ctx;
+    dir: String = ctx.get_dir().to_string();
+    dir_delete_on_start: bool = ctx.get_dir_delete_on_start();
+    dir_delete_on_shutdown: bool = ctx.get_dir_delete_on_shutdown();
+    dir_warn_if_exists: bool = ctx.get_dir_warn_if_exists();
+    connect_enabled: bool = ctx.get_connect_enabled().is_ok_and(|enabled| enabled > 0);
+    enable_experimental_features: bool =
+        ctx.get_enable_experimental_features().is_ok_and(|enabled| enabled > 0);
+    threading_mode: String = threading_mode_name(ctx.get_threading_mode()).to_string();
+    conductor_idle_strategy: String = ctx.get_conductor_idle_strategy().to_string();
+    conductor_idle_strategy_init_args: String =
+        ctx.get_conductor_idle_strategy_init_args().to_string();
+    sender_idle_strategy: String = ctx.get_sender_idle_strategy().to_string();
+    sender_idle_strategy_init_args: String = ctx.get_sender_idle_strategy_init_args().to_string();
+    receiver_idle_strategy: String = ctx.get_receiver_idle_strategy().to_string();
+    receiver_idle_strategy_init_args: String =
+        ctx.get_receiver_idle_strategy_init_args().to_string();
+    sharednetwork_idle_strategy: String = ctx.get_sharednetwork_idle_strategy().to_string();
+    sharednetwork_idle_strategy_init_args: String =
+        ctx.get_sharednetwork_idle_strategy_init_args().to_string();
+    shared_idle_strategy: String = ctx.get_shared_idle_strategy().to_string();
+    shared_idle_strategy_init_args: String = ctx.get_shared_idle_strategy_init_args().to_string();
+    conductor_cpu_affinity: i32 = ctx.get_conductor_cpu_affinity();
+    sender_cpu_affinity: i32 = ctx.get_sender_cpu_affinity();
+    receiver_cpu_affinity: i32 = ctx.get_receiver_cpu_affinity();
+    conductor_cycle_threshold_ns: i64 = ctx.get_conductor_cycle_threshold_ns();
+    sender_cycle_threshold_ns: i64 = ctx.get_sender_cycle_threshold_ns();
+    receiver_cycle_threshold_ns: i64 = ctx.get_receiver_cycle_threshold_ns();
+    name_resolver_threshold_ns: i64 = ctx.get_name_resolver_threshold_ns();
+    term_buffer_length: usize = ctx.get_term_buffer_length();
+    ipc_term_buffer_length: usize = ctx.get_ipc_term_buffer_length();
+    term_buffer_sparse_file: bool = ctx.get_term_buffer_sparse_file();
+    perform_storage_checks: bool = ctx.get_perform_storage_checks();
+    low_file_store_warning_threshold: u64 = ctx.get_low_file_store_warning_threshold();
+    mtu_length: usize = ctx.get_mtu_length();
+    ipc_mtu_length: usize = ctx.get_ipc_mtu_length();
+    publication_term_window_length: usize = ctx.get_publication_term_window_length();
+    ipc_publication_term_window_length: usize = ctx.get_ipc_publication_term_window_length();
+    rcv_initial_window_length: usize = ctx.get_rcv_initial_window_length();
+    socket_so_rcvbuf: usize = ctx.get_socket_so_rcvbuf();
+    socket_so_sndbuf: usize = ctx.get_socket_so_sndbuf();
+    socket_multicast_ttl: u8 = ctx.get_socket_multicast_ttl();
+    sender_io_vector_capacity: u32 = ctx.get_sender_io_vector_capacity();
+    receiver_io_vector_capacity: u32 = ctx.get_receiver_io_vector_capacity();
+    network_publication_max_messages_per_send: u32 =
+        ctx.get_network_publication_max_messages_per_send();
+    send_to_status_poll_ratio: usize = ctx.get_send_to_status_poll_ratio();
+    to_conductor_buffer_length: usize = ctx.get_to_conductor_buffer_length();
+    to_clients_buffer_length: usize = ctx.get_to_clients_buffer_length();
+    counters_buffer_length: usize = ctx.get_counters_buffer_length();
+    error_buffer_length: usize = ctx.get_error_buffer_length();
+    loss_report_buffer_length: usize = ctx.get_loss_report_buffer_length();
+    file_page_size: usize = ctx.get_file_page_size();
+    driver_timeout_ms: u64 = ctx.get_driver_timeout_ms();
+    client_liveness_timeout_ns: u64 = ctx.get_client_liveness_timeout_ns();
+    image_liveness_timeout_ns: u64 = ctx.get_image_liveness_timeout_ns();
+    publication_linger_timeout_ns: u64 = ctx.get_publication_linger_timeout_ns();
+    publication_connection_timeout_ns: u64 = ctx.get_publication_connection_timeout_ns();
+    publication_unblock_timeout_ns: u64 = ctx.get_publication_unblock_timeout_ns();
+    timer_interval_ns: u64 = ctx.get_timer_interval_ns();
+    rcv_status_message_timeout_ns: u64 = ctx.get_rcv_status_message_timeout_ns();
+    counters_free_to_reuse_timeout_ns: u64 = ctx.get_counters_free_to_reuse_timeout_ns();
+    untethered_window_limit_timeout_ns: u64 = ctx.get_untethered_window_limit_timeout_ns();
+    untethered_linger_timeout_ns: i64 = ctx.get_untethered_linger_timeout_ns();
+    untethered_resting_timeout_ns: u64 = ctx.get_untethered_resting_timeout_ns();
+    retransmit_unicast_delay_ns: u64 = ctx.get_retransmit_unicast_delay_ns();
+    retransmit_unicast_linger_ns: u64 = ctx.get_retransmit_unicast_linger_ns();
+    nak_unicast_delay_ns: u64 = ctx.get_nak_unicast_delay_ns();
+    nak_unicast_retry_delay_ratio: u64 = ctx.get_nak_unicast_retry_delay_ratio();
+    nak_multicast_max_backoff_ns: u64 = ctx.get_nak_multicast_max_backoff_ns();
+    nak_multicast_group_size: usize = ctx.get_nak_multicast_group_size();
+    max_resend: u32 = ctx.get_max_resend();
+    re_resolution_check_interval_ns: u64 = ctx.get_re_resolution_check_interval_ns();
+    unicast_flowcontrol_supplier: String = supplier_name!(
+        ctx.get_inner_ref().unicast_flow_control_supplier_func,
+        aeron_unicast_flow_control_strategy_supplier => "unicast",
+        aeron_max_multicast_flow_control_strategy_supplier => "max",
+        aeron_min_flow_control_strategy_supplier => "min",
+        aeron_tagged_flow_control_strategy_supplier => "tagged",
+        aeron_default_multicast_flow_control_strategy_supplier => "default",
+    );
+    multicast_flowcontrol_supplier: String = supplier_name!(
+        ctx.get_inner_ref().multicast_flow_control_supplier_func,
+        aeron_unicast_flow_control_strategy_supplier => "unicast",
+        aeron_max_multicast_flow_control_strategy_supplier => "max",
+        aeron_min_flow_control_strategy_supplier => "min",
+        aeron_tagged_flow_control_strategy_supplier => "tagged",
+        aeron_default_multicast_flow_control_strategy_supplier => "default",
+    );
+    congestioncontrol_supplier: String = supplier_name!(
+        ctx.get_inner_ref().congestion_control_supplier_func,
+        aeron_congestion_control_default_strategy_supplier => "default",
+        aeron_static_window_congestion_control_strategy_supplier => "static",
+        aeron_cubic_congestion_control_strategy_supplier => "cubic",
+    );
+    flow_control_receiver_timeout_ns: u64 = ctx.get_flow_control_receiver_timeout_ns();
+    flow_control_group_tag: i64 = ctx.get_flow_control_group_tag();
+    flow_control_group_min_size: i32 = ctx.get_flow_control_group_min_size();
+    receiver_group_consideration: String =
+        inferable_boolean_name(ctx.get_receiver_group_consideration()).to_string();
+    receiver_group_tag: Option<i64> = ctx
+        .get_receiver_group_tag_is_present()
+        .then(|| ctx.get_receiver_group_tag_value());
+    spies_simulate_connection: bool = ctx.get_spies_simulate_connection();
+    reliable_stream: bool = ctx.get_reliable_stream();
+    tether_subscriptions: bool = ctx.get_tether_subscriptions();
+    rejoin_stream: bool = ctx.get_rejoin_stream();
+    stream_session_limit: i32 = ctx.get_stream_session_limit();
+    publication_reserved_session_id_low: i32 = ctx.get_publication_reserved_session_id_low();
+    publication_reserved_session_id_high: i32 = ctx.get_publication_reserved_session_id_high();
+    resource_free_limit: u32 = ctx.get_resource_free_limit();
+    async_executor_threads: u32 = ctx.get_async_executor_threads();
+    sender_wildcard_port_range: Option<PortRange> = ctx.sender_port_range();
+    receiver_wildcard_port_range: Option<PortRange> = ctx.receiver_port_range();
+    resolver_name: String = ctx.get_resolver_name().to_string();
+    resolver_interface: String = ctx.get_resolver_interface().to_string();
+    resolver_bootstrap_neighbor: String = ctx.get_resolver_bootstrap_neighbor().to_string();
+    name_resolver_supplier: String = supplier_name!(
+        ctx.get_name_resolver_supplier(),
+        aeron_default_name_resolver_supplier => "default",
+    );
+    name_resolver_init_args: String = ctx.get_name_resolver_init_args().to_string();
+    udp_channel_transport_bindings: String = transport_bindings_name(ctx);
+    udp_channel_outgoing_interceptors: Vec<String> =
+        interceptor_names(ctx.get_inner_ref().udp_channel_outgoing_interceptor_bindings);
+    udp_channel_incoming_interceptors: Vec<String> =
+        interceptor_names(ctx.get_inner_ref().udp_channel_incoming_interceptor_bindings);
+    print_configuration: bool = ctx.get_print_configuration();
+}
+
+fn threading_mode_name(mode: aeron_threading_mode_t) -> &'static str {
+    match mode {
+        aeron_threading_mode_t::AERON_THREADING_MODE_DEDICATED => "DEDICATED",
+        aeron_threading_mode_t::AERON_THREADING_MODE_SHARED_NETWORK => "SHARED_NETWORK",
+        aeron_threading_mode_t::AERON_THREADING_MODE_SHARED => "SHARED",
+        aeron_threading_mode_t::AERON_THREADING_MODE_INVOKER => "INVOKER",
+    }
+}
+
+fn inferable_boolean_name(value: aeron_inferable_boolean_t) -> &'static str {
+    match value {
+        aeron_inferable_boolean_t::AERON_FORCE_FALSE => "FORCE_FALSE",
+        aeron_inferable_boolean_t::AERON_FORCE_TRUE => "FORCE_TRUE",
+        aeron_inferable_boolean_t::AERON_INFER => "INFER",
+    }
+}
+
+unsafe fn c_string(ptr: *const std::os::raw::c_char) -> String {
+    if ptr.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(ptr).to_string_lossy().into_owned()
+    }
+}
+
+fn transport_bindings_name(ctx: &AeronDriverContext) -> String {
+    unsafe {
+        ctx.get_inner_ref()
+            .udp_channel_transport_bindings
+            .as_ref()
+            .map(|bindings| c_string(bindings.meta_info.name))
+            .unwrap_or_default()
+    }
+}
+
+/// Names of the interceptors in a chain, in the order they are applied.
+fn interceptor_names(mut bindings: *const aeron_udp_channel_interceptor_bindings_t) -> Vec<String> {
+    let mut names = vec![];
+    while let Some(interceptor) = unsafe { bindings.as_ref() } {
+        names.push(unsafe { c_string(interceptor.meta_info.name) });
+        bindings = interceptor.meta_info.next_interceptor_bindings;
+    }
+    names
+}
+
+/// A setting which differs between two [`DriverConfigSnapshot`]s.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigDifference {
+    pub name: &'static str,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl fmt::Display for ConfigDifference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: expected {:?} but was {:?}",
+            self.name, self.expected, self.actual
+        )
+    }
+}
+
+impl DriverConfigSnapshot {
+    /// Settings which differ in `actual`, treating `self` as the expected configuration.
+    pub fn diff(&self, actual: &DriverConfigSnapshot) -> Vec<ConfigDifference> {
+        self.entries()
+            .into_iter()
+            .zip(actual.entries())
+            .filter(|((_, expected), (_, actual))| expected != actual)
+            .map(|((name, expected), (_, actual))| ConfigDifference {
+                name,
+                expected,
+                actual,
+            })
+            .collect()
+    }
+}
+
+/// One `name=value` line per setting.
+impl fmt::Display for DriverConfigSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, value) in self.entries() {
+            writeln!(f, "{name}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+impl AeronDriverContext {
+    /// Captures the context's current settings, including defaults and values applied from
+    /// `AERON_*` environment variables.
+    pub fn effective_config(&self) -> DriverConfigSnapshot {
+        DriverConfigSnapshot::capture(self)
+    }
+}
@@ -0,0 +1,275 @@
+//! Receiver congestion control selection and custom strategies implemented in Rust.
+//!
+//! The driver creates a congestion control strategy for every image it receives, which decides the
+//! receiver window advertised in status messages. By default a channel picks a built in strategy
+//! with the `cc` URI param (see `AeronUriStringBuilder::congestion_control`).
+//! [`AeronDriverContext::set_congestion_control`] instead forces one built in strategy for every
+//! image, and [`CongestionControlStrategy`] is implemented for anything else. Both ignore the `cc`
+//! param until [`AeronDriverContext::reset_congestion_control`] restores the default.
+//!
+//! ```no_run
+//! # use rusteron_media_driver::*;
+//! # use rusteron_media_driver::congestion_control::*;
+//! # fn run() -> Result<(), AeronCError> {
+//! let ctx = AeronDriverContext::new()?;
+//! ctx.set_congestion_control(CongestionControl::Cubic)?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::bindings::*;
+use crate::ffi::{socket_addr, udp_channel_uri, ContextRegistry};
+use crate::{AeronCError, AeronDriverContext, CongestionControl};
+use std::net::SocketAddr;
+use std::os::raw::{c_int, c_void};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+impl CongestionControl {
+    pub fn supplier(&self) -> aeron_congestion_control_strategy_supplier_func_t {
+        match self {
+            CongestionControl::StaticWindow => {
+                Some(aeron_static_window_congestion_control_strategy_supplier)
+            }
+            CongestionControl::Cubic => Some(aeron_cubic_congestion_control_strategy_supplier),
+        }
+    }
+}
+
+/// The image a [`CongestionControlStrategy`] is created for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CongestionControlSetup {
+    pub channel: String,
+    pub stream_id: i32,
+    pub session_id: i32,
+    pub registration_id: i64,
+    pub term_length: i32,
+    pub mtu_length: i32,
+    /// window the static window strategy would use, from the channel's `rcv-wnd` or the driver
+    /// default, capped at half a term
+    pub receiver_window_length: i32,
+    pub control_address: Option<SocketAddr>,
+    pub source_address: Option<SocketAddr>,
+}
+
+/// Progress of an image's rebuild, reported on every receiver duty cycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrackRebuild {
+    pub now_ns: i64,
+    pub new_consumption_position: i64,
+    pub last_sm_position: i64,
+    pub hwm_position: i64,
+    pub starting_rebuild_position: i64,
+    pub ending_rebuild_position: i64,
+    pub loss_occurred: bool,
+}
+
+/// Result of [`CongestionControlStrategy::on_track_rebuild`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowUpdate {
+    pub window_length: i32,
+    /// send a status message now rather than waiting for the window to be consumed
+    pub force_status_message: bool,
+}
+
+impl WindowUpdate {
+    pub fn new(window_length: i32) -> Self {
+        Self {
+            window_length,
+            force_status_message: false,
+        }
+    }
+}
+
+/// Congestion control for a single image, called on the driver's receiver thread.
+pub trait CongestionControlStrategy: Send + 'static {
+    fn initial_window_length(&self) -> i32;
+
+    fn max_window_length(&self) -> i32 {
+        self.initial_window_length()
+    }
+
+    /// whether to send an RTT measurement request to the sender now
+    fn should_measure_rtt(&mut self, _now_ns: i64) -> bool {
+        false
+    }
+
+    fn on_rttm_sent(&mut self, _now_ns: i64) {}
+
+    /// RTT measurement reply from the sender at `source`
+    fn on_rttm(&mut self, _now_ns: i64, _rtt: Duration, _source: Option<SocketAddr>) {}
+
+    fn on_track_rebuild(&mut self, rebuild: &TrackRebuild) -> WindowUpdate;
+}
+
+type StrategyFactory =
+    Box<dyn FnMut(&CongestionControlSetup) -> Box<dyn CongestionControlStrategy> + Send>;
+
+/// Factories of each driver context, which the C supplier looks up.
+static FACTORIES: ContextRegistry<usize, Arc<Mutex<StrategyFactory>>> = ContextRegistry::new();
+
+unsafe fn strategy<'a>(state: *mut c_void) -> &'a mut Box<dyn CongestionControlStrategy> {
+    &mut *(state as *mut Box<dyn CongestionControlStrategy>)
+}
+
+unsafe extern "C" fn strategy_should_measure_rtt(state: *mut c_void, now_ns: i64) -> bool {
+    strategy(state).should_measure_rtt(now_ns)
+}
+
+unsafe extern "C" fn strategy_on_rttm_sent(state: *mut c_void, now_ns: i64) {
+    strategy(state).on_rttm_sent(now_ns)
+}
+
+unsafe extern "C" fn strategy_on_rttm(
+    state: *mut c_void,
+    now_ns: i64,
+    rtt_ns: i64,
+    source_address: *mut sockaddr_storage,
+) {
+    strategy(state).on_rttm(
+        now_ns,
+        Duration::from_nanos(rtt_ns.max(0) as u64),
+        socket_addr(source_address),
+    )
+}
+
+unsafe extern "C" fn strategy_on_track_rebuild(
+    state: *mut c_void,
+    should_force_sm: *mut bool,
+    now_ns: i64,
+    new_consumption_position: i64,
+    last_sm_position: i64,
+    hwm_position: i64,
+    starting_rebuild_position: i64,
+    ending_rebuild_position: i64,
+    loss_occurred: bool,
+) -> i32 {
+    let update = strategy(state).on_track_rebuild(&TrackRebuild {
+        now_ns,
+        new_consumption_position,
+        last_sm_position,
+        hwm_position,
+        starting_rebuild_position,
+        ending_rebuild_position,
+        loss_occurred,
+    });
+    if let Some(should_force_sm) = should_force_sm.as_mut() {
+        *should_force_sm = update.force_status_message;
+    }
+    update.window_length
+}
+
+unsafe extern "C" fn strategy_initial_window_length(state: *mut c_void) -> i32 {
+    strategy(state).initial_window_length()
+}
+
+unsafe extern "C" fn strategy_max_window_length(state: *mut c_void) -> i32 {
+    strategy(state).max_window_length()
+}
+
+unsafe extern "C" fn strategy_fini(strategy: *mut aeron_congestion_control_strategy_t) -> c_int {
+    if let Some(s) = strategy.as_mut() {
+        if !s.state.is_null() {
+            drop(Box::from_raw(
+                s.state as *mut Box<dyn CongestionControlStrategy>,
+            ));
+        }
+        aeron_free(strategy as *mut c_void);
+    }
+    0
+}
+
+unsafe extern "C" fn strategy_supplier(
+    strategy: *mut *mut aeron_congestion_control_strategy_t,
+    channel: *mut aeron_udp_channel_t,
+    stream_id: i32,
+    session_id: i32,
+    registration_id: i64,
+    term_length: i32,
+    sender_mtu_length: i32,
+    control_address: *mut sockaddr_storage,
+    src_address: *mut sockaddr_storage,
+    context: *mut aeron_driver_context_t,
+    _counters_manager: *mut aeron_counters_manager_t,
+) -> c_int {
+    let factory = FACTORIES.get(&(context as usize));
+    let Some(factory) = factory else {
+        log::error!("no congestion control strategy registered for driver context");
+        return -1;
+    };
+
+    let channel_window = channel.as_ref().map_or(0, |c| c.receiver_window_length);
+    let window = if channel_window > 0 {
+        channel_window
+    } else {
+        aeron_driver_context_get_rcv_initial_window_length(context)
+    };
+    let setup = CongestionControlSetup {
+        channel: udp_channel_uri(channel),
+        stream_id,
+        session_id,
+        registration_id,
+        term_length,
+        mtu_length: sender_mtu_length,
+        receiver_window_length: window.min((term_length / 2).max(0) as usize) as i32,
+        control_address: socket_addr(control_address),
+        source_address: socket_addr(src_address),
+    };
+    let Ok(mut factory) = factory.lock() else {
+        return -1;
+    };
+    let state: Box<Box<dyn CongestionControlStrategy>> = Box::new(factory(&setup));
+
+    let mut ptr: *mut c_void = std::ptr::null_mut();
+    let result = aeron_alloc(
+        &mut ptr,
+        std::mem::size_of::<aeron_congestion_control_strategy_t>(),
+    );
+    if result < 0 || ptr.is_null() {
+        return result.min(-1);
+    }
+    let ptr = ptr as *mut aeron_congestion_control_strategy_t;
+    ptr.write(aeron_congestion_control_strategy_t {
+        should_measure_rtt: Some(strategy_should_measure_rtt),
+        on_rttm_sent: Some(strategy_on_rttm_sent),
+        on_rttm: Some(strategy_on_rttm),
+        on_track_rebuild: Some(strategy_on_track_rebuild),
+        initial_window_length: Some(strategy_initial_window_length),
+        max_window_length: Some(strategy_max_window_length),
+        fini: Some(strategy_fini),
+        state: Box::into_raw(state) as *mut c_void,
+    });
+    *strategy = ptr;
+    0
+}
+
+impl AeronDriverContext {
+    /// Uses a built in congestion control strategy for every image, regardless of the channel's
+    /// `cc` param.
+    pub fn set_congestion_control(&self, value: CongestionControl) -> Result<(), AeronCError> {
+        self.set_congestioncontrol_supplier(value.supplier())?;
+        Ok(())
+    }
+
+    /// Restores the driver's default selection, which honours each channel's `cc` param and falls
+    /// back to the static window strategy.
+    pub fn reset_congestion_control(&self) -> Result<(), AeronCError> {
+        self.set_congestioncontrol_supplier(Some(
+            aeron_congestion_control_default_strategy_supplier,
+        ))?;
+        Ok(())
+    }
+
+    /// Uses strategies created by `factory`, one per image, regardless of the channel's `cc`
+    /// param. Must be called before the driver is started.
+    pub fn set_congestion_control_strategy<S, F>(&self, mut factory: F) -> Result<(), AeronCError>
+    where
+        S: CongestionControlStrategy,
+        F: FnMut(&CongestionControlSetup) -> S + Send + 'static,
+    {
+        let key = self.get_inner() as usize;
+        let factory: StrategyFactory = Box::new(move |setup| Box::new(factory(setup)));
+        FACTORIES.insert(self, key, Arc::new(Mutex::new(factory)))?;
+        self.set_congestioncontrol_supplier(Some(strategy_supplier))?;
+        Ok(())
+    }
+}
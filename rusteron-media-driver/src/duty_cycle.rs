@@ -0,0 +1,364 @@
+//! Duty cycle tracking for the driver agents.
+//!
+//! Each driver agent (conductor, sender, receiver and the name resolver) reports the time of every
+//! duty cycle to a tracker, which the driver uses to detect stalls. A [`DutyCycleTracker`] is called
+//! in addition to the driver's own tracker, so the max cycle time and threshold exceeded system
+//! counters keep working.
+//!
+//! ```no_run
+//! # use rusteron_media_driver::*;
+//! # use rusteron_media_driver::duty_cycle::*;
+//! # fn run() -> Result<(), AeronCError> {
+//! let ctx = AeronDriverContext::new()?;
+//! let stats = ctx.track_duty_cycles()?;
+//! let (stop, driver) = AeronDriver::launch_embedded(ctx, false);
+//! // ...
+//! println!("{stats}");
+//! # Ok(())
+//! # }
+//! ```
+use crate::bindings::*;
+use crate::{AeronCError, AeronDriverContext};
+use hdrhistogram::Histogram;
+use std::fmt;
+use std::os::raw::c_void;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Driver agents with a duty cycle tracker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DriverAgent {
+    Conductor,
+    Sender,
+    Receiver,
+    NameResolver,
+}
+
+impl DriverAgent {
+    pub const ALL: [DriverAgent; 4] = [
+        DriverAgent::Conductor,
+        DriverAgent::Sender,
+        DriverAgent::Receiver,
+        DriverAgent::NameResolver,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DriverAgent::Conductor => "conductor",
+            DriverAgent::Sender => "sender",
+            DriverAgent::Receiver => "receiver",
+            DriverAgent::NameResolver => "name-resolver",
+        }
+    }
+}
+
+/// Tracks the duty cycle of a driver agent, called on the agent's thread.
+pub trait DutyCycleTracker: Send + 'static {
+    /// Starts measuring from `now_ns` without recording a cycle.
+    fn update(&mut self, now_ns: i64);
+
+    /// Records the cycle since the previous call and starts measuring from `now_ns`.
+    fn measure_and_update(&mut self, now_ns: i64);
+}
+
+/// Cycle times recorded by a [`HdrDutyCycleTracker`], readable from any thread.
+#[derive(Debug)]
+pub struct DutyCycleRecorder {
+    histogram: Mutex<Histogram<u64>>,
+    threshold_ns: u64,
+    threshold_exceeded: AtomicU64,
+}
+
+impl DutyCycleRecorder {
+    /// cycles above an hour are recorded as an hour
+    const MAX_CYCLE_NS: u64 = 3_600_000_000_000;
+
+    pub fn new(threshold: Duration) -> Self {
+        Self {
+            histogram: Mutex::new(Self::new_histogram()),
+            threshold_ns: threshold.as_nanos() as u64,
+            threshold_exceeded: AtomicU64::new(0),
+        }
+    }
+
+    pub fn record(&self, cycle_ns: u64) {
+        if cycle_ns > self.threshold_ns {
+            self.threshold_exceeded.fetch_add(1, Ordering::Relaxed);
+        }
+        if let Ok(mut histogram) = self.histogram.lock() {
+            histogram.saturating_record(cycle_ns.max(1));
+        }
+    }
+
+    pub fn threshold(&self) -> Duration {
+        Duration::from_nanos(self.threshold_ns)
+    }
+
+    pub fn threshold_exceeded(&self) -> u64 {
+        self.threshold_exceeded.load(Ordering::Relaxed)
+    }
+
+    pub fn percentile(&self, percentile: f64) -> Duration {
+        self.histogram
+            .lock()
+            .map(|h| Duration::from_nanos(h.value_at_percentile(percentile)))
+            .unwrap_or_default()
+    }
+
+    fn new_histogram() -> Histogram<u64> {
+        Histogram::new_with_bounds(1, Self::MAX_CYCLE_NS, 3).expect("valid histogram bounds")
+    }
+
+    /// Summary of the cycles recorded since the previous snapshot, which starts a new interval.
+    pub fn snapshot(&self) -> DutyCycleSnapshot {
+        // swap in an empty histogram so the agent thread is only blocked for the swap
+        let mut histogram = Self::new_histogram();
+        if let Ok(mut recorded) = self.histogram.lock() {
+            std::mem::swap(&mut *recorded, &mut histogram);
+        }
+        let at = |p: f64| Duration::from_nanos(histogram.value_at_percentile(p));
+        DutyCycleSnapshot {
+            count: histogram.len(),
+            min: Duration::from_nanos(histogram.min()),
+            mean: Duration::from_nanos(histogram.mean() as u64),
+            p50: at(50.0),
+            p90: at(90.0),
+            p99: at(99.0),
+            p99_9: at(99.9),
+            max: Duration::from_nanos(histogram.max()),
+            threshold: self.threshold(),
+            threshold_exceeded: self.threshold_exceeded.swap(0, Ordering::Relaxed),
+        }
+    }
+
+    pub fn reset(&self) {
+        if let Ok(mut histogram) = self.histogram.lock() {
+            histogram.reset();
+        }
+        self.threshold_exceeded.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Point in time summary of a [`DutyCycleRecorder`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DutyCycleSnapshot {
+    pub count: u64,
+    pub min: Duration,
+    pub mean: Duration,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub p99_9: Duration,
+    pub max: Duration,
+    pub threshold: Duration,
+    pub threshold_exceeded: u64,
+}
+
+impl fmt::Display for DutyCycleSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cycles={} min={:?} mean={:?} p50={:?} p90={:?} p99={:?} p99.9={:?} max={:?} threshold={:?} exceeded={}",
+            self.count,
+            self.min,
+            self.mean,
+            self.p50,
+            self.p90,
+            self.p99,
+            self.p99_9,
+            self.max,
+            self.threshold,
+            self.threshold_exceeded
+        )
+    }
+}
+
+/// Records cycle times into an HDR histogram and counts cycles above a threshold.
+#[derive(Debug)]
+pub struct HdrDutyCycleTracker {
+    last_ns: Option<i64>,
+    recorder: Arc<DutyCycleRecorder>,
+}
+
+impl HdrDutyCycleTracker {
+    pub fn new(threshold: Duration) -> Self {
+        Self {
+            last_ns: None,
+            recorder: Arc::new(DutyCycleRecorder::new(threshold)),
+        }
+    }
+
+    pub fn recorder(&self) -> Arc<DutyCycleRecorder> {
+        self.recorder.clone()
+    }
+}
+
+impl DutyCycleTracker for HdrDutyCycleTracker {
+    fn update(&mut self, now_ns: i64) {
+        self.last_ns = Some(now_ns);
+    }
+
+    fn measure_and_update(&mut self, now_ns: i64) {
+        if let Some(last_ns) = self.last_ns {
+            self.recorder.record((now_ns - last_ns).max(0) as u64);
+        }
+        self.last_ns = Some(now_ns);
+    }
+}
+
+/// Recorders of every driver agent, see [`AeronDriverContext::track_duty_cycles`]. Displaying
+/// it takes a snapshot of each recorder.
+#[derive(Debug, Clone)]
+pub struct DriverDutyCycles {
+    pub conductor: Arc<DutyCycleRecorder>,
+    pub sender: Arc<DutyCycleRecorder>,
+    pub receiver: Arc<DutyCycleRecorder>,
+    pub name_resolver: Arc<DutyCycleRecorder>,
+}
+
+impl DriverDutyCycles {
+    pub fn get(&self, agent: DriverAgent) -> &Arc<DutyCycleRecorder> {
+        match agent {
+            DriverAgent::Conductor => &self.conductor,
+            DriverAgent::Sender => &self.sender,
+            DriverAgent::Receiver => &self.receiver,
+            DriverAgent::NameResolver => &self.name_resolver,
+        }
+    }
+}
+
+impl fmt::Display for DriverDutyCycles {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for agent in DriverAgent::ALL {
+            writeln!(f, "{}: {}", agent.as_str(), self.get(agent).snapshot())?;
+        }
+        Ok(())
+    }
+}
+
+struct TrackerState {
+    tracker: Box<dyn DutyCycleTracker>,
+    /// the tracker which was replaced, normally the driver's stall tracker
+    previous: *mut aeron_duty_cycle_tracker_t,
+}
+
+/// Owns the tracker handed to the driver context, freed when the context is dropped.
+struct OwnedTracker(*mut aeron_duty_cycle_tracker_t);
+
+impl Drop for OwnedTracker {
+    fn drop(&mut self) {
+        unsafe {
+            let tracker = Box::from_raw(self.0);
+            drop(Box::from_raw(tracker.state as *mut TrackerState));
+        }
+    }
+}
+
+unsafe extern "C" fn tracker_update(state: *mut c_void, now_ns: i64) {
+    let state = &mut *(state as *mut TrackerState);
+    if let Some(previous) = state.previous.as_ref() {
+        if let Some(update) = previous.update {
+            update(previous.state, now_ns);
+        }
+    }
+    state.tracker.update(now_ns);
+}
+
+unsafe extern "C" fn tracker_measure_and_update(state: *mut c_void, now_ns: i64) {
+    let state = &mut *(state as *mut TrackerState);
+    if let Some(previous) = state.previous.as_ref() {
+        if let Some(measure_and_update) = previous.measure_and_update {
+            measure_and_update(previous.state, now_ns);
+        }
+    }
+    state.tracker.measure_and_update(now_ns);
+}
+
+impl AeronDriverContext {
+    /// Reports the duty cycles of `agent` to `tracker`. Must be called before the driver is
+    /// started.
+    pub fn set_duty_cycle_tracker<T: DutyCycleTracker>(
+        &self,
+        agent: DriverAgent,
+        tracker: T,
+    ) -> Result<(), AeronCError> {
+        let ctx = self.get_inner();
+        let previous = unsafe {
+            match agent {
+                DriverAgent::Conductor => {
+                    aeron_driver_context_get_conductor_duty_cycle_tracker(ctx)
+                }
+                DriverAgent::Sender => aeron_driver_context_get_sender_duty_cycle_tracker(ctx),
+                DriverAgent::Receiver => aeron_driver_context_get_receiver_duty_cycle_tracker(ctx),
+                DriverAgent::NameResolver => {
+                    aeron_driver_context_get_name_resolver_time_tracker(ctx)
+                }
+            }
+        };
+        let state = Box::new(TrackerState {
+            tracker: Box::new(tracker),
+            previous,
+        });
+        let owned = OwnedTracker(Box::into_raw(Box::new(aeron_duty_cycle_tracker_t {
+            update: Some(tracker_update),
+            measure_and_update: Some(tracker_measure_and_update),
+            state: Box::into_raw(state) as *mut c_void,
+        })));
+
+        let result = unsafe {
+            match agent {
+                DriverAgent::Conductor => {
+                    aeron_driver_context_set_conductor_duty_cycle_tracker(ctx, owned.0)
+                }
+                DriverAgent::Sender => {
+                    aeron_driver_context_set_sender_duty_cycle_tracker(ctx, owned.0)
+                }
+                DriverAgent::Receiver => {
+                    aeron_driver_context_set_receiver_duty_cycle_tracker(ctx, owned.0)
+                }
+                DriverAgent::NameResolver => {
+                    aeron_driver_context_set_name_resolver_time_tracker(ctx, owned.0)
+                }
+            }
+        };
+        if result < 0 {
+            return Err(AeronCError::from_code(result));
+        }
+        self.inner.add_dependency(owned);
+        Ok(())
+    }
+
+    /// The driver's stall threshold for `agent`.
+    pub fn cycle_threshold(&self, agent: DriverAgent) -> Duration {
+        let threshold_ns = match agent {
+            DriverAgent::Conductor => self.get_conductor_cycle_threshold_ns(),
+            DriverAgent::Sender => self.get_sender_cycle_threshold_ns(),
+            DriverAgent::Receiver => self.get_receiver_cycle_threshold_ns(),
+            DriverAgent::NameResolver => self.get_name_resolver_threshold_ns(),
+        };
+        Duration::from_nanos(threshold_ns.max(0) as u64)
+    }
+
+    /// Records the duty cycles of `agent` in an HDR histogram, counting cycles above the
+    /// driver's stall threshold for the agent.
+    pub fn track_duty_cycle(
+        &self,
+        agent: DriverAgent,
+    ) -> Result<Arc<DutyCycleRecorder>, AeronCError> {
+        let tracker = HdrDutyCycleTracker::new(self.cycle_threshold(agent));
+        let recorder = tracker.recorder();
+        self.set_duty_cycle_tracker(agent, tracker)?;
+        Ok(recorder)
+    }
+
+    /// Records the duty cycles of every driver agent, see [`Self::track_duty_cycle`].
+    pub fn track_duty_cycles(&self) -> Result<DriverDutyCycles, AeronCError> {
+        Ok(DriverDutyCycles {
+            conductor: self.track_duty_cycle(DriverAgent::Conductor)?,
+            sender: self.track_duty_cycle(DriverAgent::Sender)?,
+            receiver: self.track_duty_cycle(DriverAgent::Receiver)?,
+            name_resolver: self.track_duty_cycle(DriverAgent::NameResolver)?,
+        })
+    }
+}
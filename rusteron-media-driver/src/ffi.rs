@@ -0,0 +1,206 @@
+//! Helpers shared by the modules which plug Rust implementations into the driver's C extension
+//! points: socket address conversions, channel URIs, driver counters and a registry of Rust state
+//! per driver context.
+use crate::bindings::*;
+use crate::{AeronCError, AeronCountersManager, AeronDriverContext};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::os::raw::c_int;
+use std::sync::{Arc, Mutex, OnceLock};
+
+const AF_INET: c_int = 2;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const AF_INET6: c_int = 10;
+#[cfg(target_os = "windows")]
+const AF_INET6: c_int = 23;
+#[cfg(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "tvos",
+    target_os = "watchos",
+    target_os = "visionos"
+))]
+const AF_INET6: c_int = 30;
+#[cfg(any(target_os = "freebsd", target_os = "dragonfly"))]
+const AF_INET6: c_int = 28;
+#[cfg(any(target_os = "netbsd", target_os = "openbsd"))]
+const AF_INET6: c_int = 24;
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "windows",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "tvos",
+    target_os = "watchos",
+    target_os = "visionos",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "netbsd",
+    target_os = "openbsd"
+)))]
+compile_error!("AF_INET6 is not known for this target");
+
+/// Address family of a `sockaddr`. BSD derived platforms store a length byte before a one byte
+/// family, everywhere else the family is a native `u16`.
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "windows"))]
+fn address_family(bytes: &[u8]) -> c_int {
+    u16::from_ne_bytes([bytes[0], bytes[1]]) as c_int
+}
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "windows")))]
+fn address_family(bytes: &[u8]) -> c_int {
+    bytes[1] as c_int
+}
+
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "windows"))]
+fn set_address_family(bytes: &mut [u8], family: c_int, _len: usize) {
+    bytes[0..2].copy_from_slice(&(family as u16).to_ne_bytes());
+}
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "windows")))]
+fn set_address_family(bytes: &mut [u8], family: c_int, len: usize) {
+    bytes[0] = len as u8;
+    bytes[1] = family as u8;
+}
+
+/// Converts a `sockaddr_storage` into a [`SocketAddr`]. The port and address offsets are the same
+/// for the BSD and Linux layouts of `sockaddr_in`/`sockaddr_in6`.
+pub(crate) unsafe fn socket_addr(addr: *const sockaddr_storage) -> Option<SocketAddr> {
+    if addr.is_null() {
+        return None;
+    }
+    let bytes =
+        std::slice::from_raw_parts(addr as *const u8, std::mem::size_of::<sockaddr_storage>());
+    let port = u16::from_be_bytes([bytes[2], bytes[3]]);
+    match address_family(bytes) {
+        AF_INET => {
+            let ip = Ipv4Addr::new(bytes[4], bytes[5], bytes[6], bytes[7]);
+            Some(SocketAddr::V4(SocketAddrV4::new(ip, port)))
+        }
+        AF_INET6 => {
+            let octets: [u8; 16] = bytes[8..24].try_into().ok()?;
+            let scope_id = u32::from_ne_bytes(bytes[24..28].try_into().ok()?);
+            Some(SocketAddr::V6(SocketAddrV6::new(
+                Ipv6Addr::from(octets),
+                port,
+                0,
+                scope_id,
+            )))
+        }
+        _ => None,
+    }
+}
+
+/// Writes `value` into a `sockaddr_storage`, the inverse of [`socket_addr`].
+pub(crate) unsafe fn write_socket_addr(addr: *mut sockaddr_storage, value: SocketAddr) {
+    if addr.is_null() {
+        return;
+    }
+    let bytes =
+        std::slice::from_raw_parts_mut(addr as *mut u8, std::mem::size_of::<sockaddr_storage>());
+    bytes.fill(0);
+    bytes[2..4].copy_from_slice(&value.port().to_be_bytes());
+    match value {
+        SocketAddr::V4(v4) => {
+            set_address_family(bytes, AF_INET, 16);
+            bytes[4..8].copy_from_slice(&v4.ip().octets());
+        }
+        SocketAddr::V6(v6) => {
+            set_address_family(bytes, AF_INET6, 28);
+            bytes[4..8].copy_from_slice(&v6.flowinfo().to_be_bytes());
+            bytes[8..24].copy_from_slice(&v6.ip().octets());
+            bytes[24..28].copy_from_slice(&v6.scope_id().to_ne_bytes());
+        }
+    }
+}
+
+/// The URI a `aeron_udp_channel_t` was created from.
+pub(crate) unsafe fn udp_channel_uri(channel: *const aeron_udp_channel_t) -> String {
+    channel
+        .as_ref()
+        .map(|c| {
+            let uri = std::slice::from_raw_parts(
+                c.original_uri.as_ptr() as *const u8,
+                c.uri_length.min(c.original_uri.len()),
+            );
+            String::from_utf8_lossy(uri).to_string()
+        })
+        .unwrap_or_default()
+}
+
+/// Length of a counter's slot in the values buffer.
+const COUNTER_VALUE_LENGTH: usize = 128;
+
+/// Allocates a driver counter and returns the address of its value, or `None` when the counters
+/// are exhausted. Must be called on the conductor thread, which owns the counters manager.
+pub(crate) unsafe fn allocate_counter(
+    manager: &AeronCountersManager,
+    type_id: i32,
+    key: &[u8],
+    label: &str,
+) -> Option<*mut i64> {
+    let counter_id = manager.allocate(type_id, key, label);
+    if counter_id < 0 {
+        log::error!("failed to allocate counter: {label}");
+        return None;
+    }
+    let values = manager.get_inner_ref().values;
+    Some(values.add(counter_id as usize * COUNTER_VALUE_LENGTH) as *mut i64)
+}
+
+/// Rust state keyed by driver context address, as the C suppliers and init callbacks only
+/// receive the driver context.
+pub(crate) struct ContextRegistry<K, V> {
+    entries: OnceLock<Mutex<HashMap<K, V>>>,
+}
+
+impl<K: Eq + Hash + Copy + Send + 'static, V: Clone + Send + 'static> ContextRegistry<K, V> {
+    pub(crate) const fn new() -> Self {
+        Self {
+            entries: OnceLock::new(),
+        }
+    }
+
+    fn entries(&self) -> &Mutex<HashMap<K, V>> {
+        self.entries.get_or_init(|| Mutex::new(HashMap::new()))
+    }
+
+    pub(crate) fn get(&self, key: &K) -> Option<V> {
+        self.entries().lock().ok()?.get(key).cloned()
+    }
+
+    /// Sets the value for `key`, which is removed once `context` is dropped.
+    pub(crate) fn insert(
+        &'static self,
+        context: &AeronDriverContext,
+        key: K,
+        value: V,
+    ) -> Result<(), AeronCError> {
+        let previous = self
+            .entries()
+            .lock()
+            .map_err(|_| AeronCError::from_code(-1))?
+            .insert(key, value);
+        if previous.is_none() {
+            context.inner.add_dependency(Arc::new(RegistryGuard {
+                registry: self,
+                key,
+            }));
+        }
+        Ok(())
+    }
+}
+
+/// Removes a context's entry from a [`ContextRegistry`] once the context is dropped.
+struct RegistryGuard<K: Eq + Hash + Copy + Send + 'static, V: Clone + Send + 'static> {
+    registry: &'static ContextRegistry<K, V>,
+    key: K,
+}
+
+impl<K: Eq + Hash + Copy + Send + 'static, V: Clone + Send + 'static> Drop for RegistryGuard<K, V> {
+    fn drop(&mut self) {
+        if let Ok(mut entries) = self.registry.entries().lock() {
+            entries.remove(&self.key);
+        }
+    }
+}
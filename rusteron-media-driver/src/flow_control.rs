@@ -0,0 +1,565 @@
+//! Flow control strategy selection and custom strategies implemented in Rust.
+//!
+//! [`FlowControl`] selects one of the strategies built into the driver, and parses/prints the
+//! `fc` URI param value, e.g. `tagged,g:1001/3,t:5s`. [`FlowControlStrategy`] is implemented
+//! for strategies the driver does not provide.
+//!
+//! ```no_run
+//! # use rusteron_media_driver::*;
+//! # use rusteron_media_driver::flow_control::*;
+//! # use std::time::Duration;
+//! # fn run() -> Result<(), AeronCError> {
+//! let ctx = AeronDriverContext::new()?;
+//! ctx.set_multicast_flow_control(&"min,t:2s".parse()?)?;
+//! // slowest of 3 receivers tagged 1001, forgetting receivers silent for 5s
+//! ctx.set_unicast_flow_control_strategy(|_setup: &FlowControlSetup| {
+//!     MinReceiverFlowControl::new(Duration::from_secs(5))
+//!         .group_tag(1001)
+//!         .group_min_size(3)
+//! })?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::bindings::*;
+use crate::ffi::{socket_addr, udp_channel_uri, ContextRegistry};
+use crate::{AeronCError, AeronDriverContext};
+pub use crate::{FlowControl, FlowControlOptions};
+use std::net::SocketAddr;
+use std::os::raw::{c_int, c_void};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+impl FlowControl {
+    pub fn supplier(&self) -> aeron_flow_control_strategy_supplier_func_t {
+        match self {
+            FlowControl::Max => Some(aeron_max_multicast_flow_control_strategy_supplier),
+            FlowControl::Min(_) => Some(aeron_min_flow_control_strategy_supplier),
+            FlowControl::Tagged(_) => Some(aeron_tagged_flow_control_strategy_supplier),
+            FlowControl::Unicast => Some(aeron_unicast_flow_control_strategy_supplier),
+        }
+    }
+}
+
+/// Status message sent by a receiver, see `aeron_status_message_header_t`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatusMessage {
+    pub flags: u8,
+    pub session_id: i32,
+    pub stream_id: i32,
+    pub consumption_term_id: i32,
+    pub consumption_term_offset: i32,
+    pub receiver_window: i32,
+    pub receiver_id: i64,
+    pub group_tag: Option<i64>,
+    /// stream position the receiver has consumed up to
+    pub position: i64,
+}
+
+impl StatusMessage {
+    const HEADER_LENGTH: usize = 36;
+
+    pub fn parse(
+        buffer: &[u8],
+        initial_term_id: i32,
+        position_bits_to_shift: usize,
+    ) -> Option<Self> {
+        if buffer.len() < Self::HEADER_LENGTH {
+            return None;
+        }
+        let i32_at =
+            |offset: usize| i32::from_le_bytes(buffer[offset..offset + 4].try_into().unwrap());
+        let i64_at =
+            |offset: usize| i64::from_le_bytes(buffer[offset..offset + 8].try_into().unwrap());
+        let consumption_term_id = i32_at(16);
+        let consumption_term_offset = i32_at(20);
+        Some(Self {
+            flags: buffer[5],
+            session_id: i32_at(8),
+            stream_id: i32_at(12),
+            consumption_term_id,
+            consumption_term_offset,
+            receiver_window: i32_at(24),
+            receiver_id: i64_at(28),
+            group_tag: (buffer.len() >= Self::HEADER_LENGTH + 8)
+                .then(|| i64_at(Self::HEADER_LENGTH)),
+            position: ((consumption_term_id.wrapping_sub(initial_term_id) as i64)
+                << position_bits_to_shift)
+                + consumption_term_offset as i64,
+        })
+    }
+
+    /// position the receiver can accept data up to
+    pub fn window_limit(&self) -> i64 {
+        self.position + self.receiver_window as i64
+    }
+}
+
+/// The publication a [`FlowControlStrategy`] is created for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlowControlSetup {
+    pub channel: String,
+    pub is_multicast: bool,
+    pub stream_id: i32,
+    pub session_id: i32,
+    pub registration_id: i64,
+    pub initial_term_id: i32,
+    pub term_length: usize,
+}
+
+/// Flow control for a single network publication, called on the driver's sender thread.
+/// Methods returning `i64` return the new sender limit position.
+pub trait FlowControlStrategy: Send + 'static {
+    fn on_status_message(
+        &mut self,
+        sm: &StatusMessage,
+        receiver: Option<SocketAddr>,
+        snd_lmt: i64,
+        now_ns: i64,
+    ) -> i64;
+
+    fn on_idle(
+        &mut self,
+        _now_ns: i64,
+        snd_lmt: i64,
+        _snd_pos: i64,
+        _is_end_of_stream: bool,
+    ) -> i64 {
+        snd_lmt
+    }
+
+    fn on_setup(&mut self, _now_ns: i64, snd_lmt: i64, _snd_pos: i64) -> i64 {
+        snd_lmt
+    }
+
+    fn on_error(&mut self, _error: &[u8], _receiver: Option<SocketAddr>, _now_ns: i64) {}
+
+    /// status message asking for a setup frame to be sent
+    fn on_trigger_send_setup(
+        &mut self,
+        _sm: &StatusMessage,
+        _receiver: Option<SocketAddr>,
+        _now_ns: i64,
+    ) {
+    }
+
+    fn has_required_receivers(&self) -> bool {
+        true
+    }
+
+    fn max_retransmission_length(
+        &self,
+        term_offset: usize,
+        resend_length: usize,
+        term_buffer_length: usize,
+        _mtu_length: usize,
+    ) -> usize {
+        unsafe {
+            aeron_flow_control_calculate_retransmission_length(
+                resend_length,
+                term_buffer_length,
+                term_offset,
+                DEFAULT_RETRANSMIT_RECEIVER_WINDOW_MULTIPLE,
+            )
+        }
+    }
+}
+
+/// multiple of the receiver window which may be retransmitted, same as the unicast strategy
+pub const DEFAULT_RETRANSMIT_RECEIVER_WINDOW_MULTIPLE: usize = 16;
+
+#[derive(Debug, Clone, Copy)]
+struct TrackedReceiver {
+    receiver_id: i64,
+    limit: i64,
+    last_seen_ns: i64,
+}
+
+/// Limits the sender to the slowest of the receivers seen within `timeout`, optionally only
+/// counting receivers with a group tag and requiring a minimum number of them.
+#[derive(Debug, Clone)]
+pub struct MinReceiverFlowControl {
+    timeout_ns: i64,
+    group_tag: Option<i64>,
+    group_min_size: usize,
+    receivers: Vec<TrackedReceiver>,
+}
+
+impl MinReceiverFlowControl {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout_ns: timeout.as_nanos() as i64,
+            group_tag: None,
+            group_min_size: 0,
+            receivers: vec![],
+        }
+    }
+
+    pub fn group_tag(mut self, group_tag: i64) -> Self {
+        self.group_tag = Some(group_tag);
+        self
+    }
+
+    pub fn group_min_size(mut self, group_min_size: usize) -> Self {
+        self.group_min_size = group_min_size;
+        self
+    }
+
+    pub fn receiver_count(&self) -> usize {
+        self.receivers.len()
+    }
+
+    fn limit(&self, snd_lmt: i64) -> i64 {
+        if self.receivers.is_empty() || !self.has_required_receivers() {
+            return snd_lmt;
+        }
+        self.receivers
+            .iter()
+            .map(|r| r.limit)
+            .min()
+            .unwrap_or(snd_lmt)
+    }
+}
+
+impl FlowControlStrategy for MinReceiverFlowControl {
+    fn on_status_message(
+        &mut self,
+        sm: &StatusMessage,
+        _receiver: Option<SocketAddr>,
+        snd_lmt: i64,
+        now_ns: i64,
+    ) -> i64 {
+        if self.group_tag.is_some() && self.group_tag != sm.group_tag {
+            return snd_lmt;
+        }
+        let limit = sm.window_limit();
+        match self
+            .receivers
+            .iter_mut()
+            .find(|r| r.receiver_id == sm.receiver_id)
+        {
+            Some(receiver) => {
+                receiver.limit = receiver.limit.max(limit);
+                receiver.last_seen_ns = now_ns;
+            }
+            None => self.receivers.push(TrackedReceiver {
+                receiver_id: sm.receiver_id,
+                limit,
+                last_seen_ns: now_ns,
+            }),
+        }
+        self.limit(snd_lmt)
+    }
+
+    fn on_idle(
+        &mut self,
+        now_ns: i64,
+        snd_lmt: i64,
+        _snd_pos: i64,
+        _is_end_of_stream: bool,
+    ) -> i64 {
+        let timeout_ns = self.timeout_ns;
+        self.receivers
+            .retain(|r| now_ns - r.last_seen_ns <= timeout_ns);
+        self.limit(snd_lmt)
+    }
+
+    fn has_required_receivers(&self) -> bool {
+        self.receivers.len() >= self.group_min_size
+    }
+}
+
+type StrategyFactory = Box<dyn FnMut(&FlowControlSetup) -> Box<dyn FlowControlStrategy> + Send>;
+
+struct StrategyState {
+    strategy: Box<dyn FlowControlStrategy>,
+    initial_term_id: i32,
+}
+
+/// Factories of each driver context and whether they are for multicast, which the C suppliers
+/// look up.
+static FACTORIES: ContextRegistry<(usize, bool), Arc<Mutex<StrategyFactory>>> =
+    ContextRegistry::new();
+
+unsafe fn strategy_state<'a>(state: *mut c_void) -> &'a mut StrategyState {
+    &mut *(state as *mut StrategyState)
+}
+
+unsafe fn bytes<'a>(buffer: *const u8, length: usize) -> &'a [u8] {
+    if buffer.is_null() {
+        &[]
+    } else {
+        std::slice::from_raw_parts(buffer, length)
+    }
+}
+
+unsafe extern "C" fn strategy_on_status_message(
+    state: *mut c_void,
+    sm: *const u8,
+    length: usize,
+    recv_addr: *mut sockaddr_storage,
+    snd_lmt: i64,
+    initial_term_id: i32,
+    position_bits_to_shift: usize,
+    now_ns: i64,
+) -> i64 {
+    let state = strategy_state(state);
+    match StatusMessage::parse(bytes(sm, length), initial_term_id, position_bits_to_shift) {
+        Some(sm) => state
+            .strategy
+            .on_status_message(&sm, socket_addr(recv_addr), snd_lmt, now_ns),
+        None => snd_lmt,
+    }
+}
+
+unsafe extern "C" fn strategy_on_idle(
+    state: *mut c_void,
+    now_ns: i64,
+    snd_lmt: i64,
+    snd_pos: i64,
+    is_end_of_stream: bool,
+) -> i64 {
+    strategy_state(state)
+        .strategy
+        .on_idle(now_ns, snd_lmt, snd_pos, is_end_of_stream)
+}
+
+unsafe extern "C" fn strategy_on_setup(
+    state: *mut c_void,
+    _setup: *const u8,
+    _length: usize,
+    now_ns: i64,
+    snd_lmt: i64,
+    _position_bits_to_shift: usize,
+    snd_pos: i64,
+) -> i64 {
+    strategy_state(state)
+        .strategy
+        .on_setup(now_ns, snd_lmt, snd_pos)
+}
+
+unsafe extern "C" fn strategy_on_error(
+    state: *mut c_void,
+    error: *const u8,
+    length: usize,
+    recv_addr: *mut sockaddr_storage,
+    now_ns: i64,
+) {
+    strategy_state(state)
+        .strategy
+        .on_error(bytes(error, length), socket_addr(recv_addr), now_ns)
+}
+
+unsafe extern "C" fn strategy_on_trigger_send_setup(
+    state: *mut c_void,
+    sm: *const u8,
+    length: usize,
+    recv_addr: *mut sockaddr_storage,
+    now_ns: i64,
+) {
+    let state = strategy_state(state);
+    // the position is not used when triggering a setup, so the term id does not matter
+    if let Some(sm) = StatusMessage::parse(bytes(sm, length), state.initial_term_id, 0) {
+        state
+            .strategy
+            .on_trigger_send_setup(&sm, socket_addr(recv_addr), now_ns)
+    }
+}
+
+unsafe extern "C" fn strategy_max_retransmission_length(
+    state: *mut c_void,
+    term_offset: usize,
+    resend_length: usize,
+    term_buffer_length: usize,
+    mtu_length: usize,
+) -> usize {
+    strategy_state(state).strategy.max_retransmission_length(
+        term_offset,
+        resend_length,
+        term_buffer_length,
+        mtu_length,
+    )
+}
+
+unsafe extern "C" fn strategy_has_required_receivers(
+    strategy: *mut aeron_flow_control_strategy_t,
+) -> bool {
+    strategy.as_ref().map_or(true, |s| {
+        strategy_state(s.state).strategy.has_required_receivers()
+    })
+}
+
+unsafe extern "C" fn strategy_fini(strategy: *mut aeron_flow_control_strategy_t) -> c_int {
+    if let Some(s) = strategy.as_mut() {
+        if !s.state.is_null() {
+            drop(Box::from_raw(s.state as *mut StrategyState));
+        }
+        aeron_free(strategy as *mut c_void);
+    }
+    0
+}
+
+unsafe fn create_strategy(
+    strategy: *mut *mut aeron_flow_control_strategy_t,
+    context: *mut aeron_driver_context_t,
+    channel: *const aeron_udp_channel_t,
+    is_multicast: bool,
+    stream_id: i32,
+    session_id: i32,
+    registration_id: i64,
+    initial_term_id: i32,
+    term_length: usize,
+) -> c_int {
+    let factory = FACTORIES.get(&(context as usize, is_multicast));
+    let Some(factory) = factory else {
+        log::error!("no flow control strategy registered for driver context");
+        return -1;
+    };
+    let setup = FlowControlSetup {
+        channel: udp_channel_uri(channel),
+        is_multicast,
+        stream_id,
+        session_id,
+        registration_id,
+        initial_term_id,
+        term_length,
+    };
+    let Ok(mut factory) = factory.lock() else {
+        return -1;
+    };
+    let state = Box::new(StrategyState {
+        strategy: factory(&setup),
+        initial_term_id,
+    });
+
+    let mut ptr: *mut c_void = std::ptr::null_mut();
+    let result = aeron_alloc(
+        &mut ptr,
+        std::mem::size_of::<aeron_flow_control_strategy_t>(),
+    );
+    if result < 0 || ptr.is_null() {
+        return result.min(-1);
+    }
+    let ptr = ptr as *mut aeron_flow_control_strategy_t;
+    ptr.write(aeron_flow_control_strategy_t {
+        on_status_message: Some(strategy_on_status_message),
+        on_idle: Some(strategy_on_idle),
+        on_setup: Some(strategy_on_setup),
+        on_error: Some(strategy_on_error),
+        fini: Some(strategy_fini),
+        has_required_receivers: Some(strategy_has_required_receivers),
+        on_trigger_send_setup: Some(strategy_on_trigger_send_setup),
+        max_retransmission_length: Some(strategy_max_retransmission_length),
+        state: Box::into_raw(state) as *mut c_void,
+    });
+    *strategy = ptr;
+    0
+}
+
+macro_rules! strategy_supplier {
+    ($name:ident, $is_multicast:expr) => {
+        unsafe extern "C" fn $name(
+            strategy: *mut *mut aeron_flow_control_strategy_t,
+            context: *mut aeron_driver_context_t,
+            _counters_manager: *mut aeron_counters_manager_t,
+            channel: *const aeron_udp_channel_t,
+            stream_id: i32,
+            session_id: i32,
+            registration_id: i64,
+            initial_term_id: i32,
+            term_length: usize,
+        ) -> c_int {
+            create_strategy(
+                strategy,
+                context,
+                channel,
+                $is_multicast,
+                stream_id,
+                session_id,
+                registration_id,
+                initial_term_id,
+                term_length,
+            )
+        }
+    };
+}
+
+strategy_supplier!(multicast_strategy_supplier, true);
+strategy_supplier!(unicast_strategy_supplier, false);
+
+impl AeronDriverContext {
+    /// Uses a built in strategy for multicast publications, its options become the driver
+    /// defaults for channels which do not set `fc`.
+    pub fn set_multicast_flow_control(
+        &self,
+        flow_control: &FlowControl,
+    ) -> Result<(), AeronCError> {
+        if *flow_control == FlowControl::Unicast {
+            log::error!("unicast flow control can not be used for multicast");
+            return Err(AeronCError::from_code(-1));
+        }
+        self.set_flow_control_options(flow_control)?;
+        self.set_multicast_flowcontrol_supplier(flow_control.supplier())?;
+        Ok(())
+    }
+
+    /// Uses a built in strategy for unicast publications, its options become the driver
+    /// defaults for channels which do not set `fc`.
+    pub fn set_unicast_flow_control(&self, flow_control: &FlowControl) -> Result<(), AeronCError> {
+        self.set_flow_control_options(flow_control)?;
+        self.set_unicast_flowcontrol_supplier(flow_control.supplier())?;
+        Ok(())
+    }
+
+    fn set_flow_control_options(&self, flow_control: &FlowControl) -> Result<(), AeronCError> {
+        if let Some(options) = flow_control.options() {
+            if let Some(tag) = options.group_tag {
+                self.set_flow_control_group_tag(tag)?;
+            }
+            if let Some(size) = options.group_min_size {
+                self.set_flow_control_group_min_size(size)?;
+            }
+            if let Some(timeout) = options.timeout {
+                self.set_flow_control_receiver_timeout_ns(timeout.as_nanos() as u64)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Uses strategies created by `factory`, one per multicast publication. Must be called before
+    /// the driver is started.
+    pub fn set_multicast_flow_control_strategy<S, F>(&self, factory: F) -> Result<(), AeronCError>
+    where
+        S: FlowControlStrategy,
+        F: FnMut(&FlowControlSetup) -> S + Send + 'static,
+    {
+        self.register_flow_control_strategy(true, factory)?;
+        self.set_multicast_flowcontrol_supplier(Some(multicast_strategy_supplier))?;
+        Ok(())
+    }
+
+    /// Uses strategies created by `factory`, one per unicast publication. Must be called before
+    /// the driver is started.
+    pub fn set_unicast_flow_control_strategy<S, F>(&self, factory: F) -> Result<(), AeronCError>
+    where
+        S: FlowControlStrategy,
+        F: FnMut(&FlowControlSetup) -> S + Send + 'static,
+    {
+        self.register_flow_control_strategy(false, factory)?;
+        self.set_unicast_flowcontrol_supplier(Some(unicast_strategy_supplier))?;
+        Ok(())
+    }
+
+    fn register_flow_control_strategy<S, F>(
+        &self,
+        is_multicast: bool,
+        mut factory: F,
+    ) -> Result<(), AeronCError>
+    where
+        S: FlowControlStrategy,
+        F: FnMut(&FlowControlSetup) -> S + Send + 'static,
+    {
+        let key = (self.get_inner() as usize, is_multicast);
+        let factory: StrategyFactory = Box::new(move |setup| Box::new(factory(setup)));
+        FACTORIES.insert(self, key, Arc::new(Mutex::new(factory)))
+    }
+}
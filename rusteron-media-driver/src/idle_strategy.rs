@@ -0,0 +1,466 @@
+//! Typed idle strategy selection for the driver agents.
+//!
+//! The driver's `set_*_idle_strategy` setters take a strategy name and `set_*_idle_strategy_init_args`
+//! a string of arguments. [`DriverIdleStrategy`] covers the built in strategies with typed
+//! arguments, and [`register_idle_strategy`] makes a strategy implemented in Rust available by name.
+//!
+//! ```no_run
+//! # use rusteron_media_driver::*;
+//! # use rusteron_media_driver::idle_strategy::*;
+//! # use std::time::Duration;
+//! # fn run() -> Result<(), AeronCError> {
+//! register_idle_strategy("park", || |work_count: i32| {
+//!     if work_count == 0 {
+//!         std::thread::park_timeout(Duration::from_micros(50));
+//!     }
+//! })?;
+//!
+//! let ctx = AeronDriverContext::new()?;
+//! let sleeping = DriverIdleStrategy::sleeping(Duration::from_millis(1));
+//! ctx.set_idle_strategy(IdleAgent::Conductor, &sleeping)?;
+//! ctx.set_idle_strategy(IdleAgent::Sender, &DriverIdleStrategy::backoff())?;
+//! ctx.set_idle_strategy(IdleAgent::Receiver, &"park".parse().expect("registered"))?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::bindings::*;
+use crate::{AeronCError, AeronDriverContext, IntoCString};
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::fmt;
+use std::os::raw::{c_char, c_int, c_void};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+/// Driver agents with an idle strategy. Which are used depends on the threading mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IdleAgent {
+    Conductor,
+    Sender,
+    Receiver,
+    /// sender and receiver in `SHARED_NETWORK` threading mode
+    SharedNetwork,
+    /// all agents in `SHARED` threading mode
+    Shared,
+}
+
+impl IdleAgent {
+    pub const ALL: [IdleAgent; 5] = [
+        IdleAgent::Conductor,
+        IdleAgent::Sender,
+        IdleAgent::Receiver,
+        IdleAgent::SharedNetwork,
+        IdleAgent::Shared,
+    ];
+}
+
+/// Idles a driver agent between duty cycles, called on the agent's thread.
+pub trait IdleStrategy: Send + 'static {
+    /// `work_count` is the work done by the last duty cycle, `0` if there was none.
+    fn idle(&mut self, work_count: i32);
+}
+
+impl<F: FnMut(i32) + Send + 'static> IdleStrategy for F {
+    fn idle(&mut self, work_count: i32) {
+        self(work_count)
+    }
+}
+
+/// An idle strategy for a driver agent.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DriverIdleStrategy {
+    /// never idles
+    Noop,
+    /// spins with a cpu pause hint
+    BusySpin,
+    /// yields the thread
+    Yielding,
+    /// sleeps for `ns` when there was no work
+    Sleeping { ns: u64 },
+    /// spins, then yields, then parks with an exponential backoff from `min_park` to `max_park`
+    Backoff {
+        max_spins: u64,
+        max_yields: u64,
+        min_park: Duration,
+        max_park: Duration,
+    },
+    /// a strategy added with [`register_idle_strategy`]
+    Custom(String),
+}
+
+impl DriverIdleStrategy {
+    pub fn sleeping(period: Duration) -> Self {
+        DriverIdleStrategy::Sleeping {
+            ns: period.as_nanos() as u64,
+        }
+    }
+
+    /// Backoff with the driver's default spins, yields and park periods.
+    pub fn backoff() -> Self {
+        DriverIdleStrategy::Backoff {
+            max_spins: AERON_IDLE_STRATEGY_BACKOFF_MAX_SPINS as u64,
+            max_yields: AERON_IDLE_STRATEGY_BACKOFF_MAX_YIELDS as u64,
+            min_park: Duration::from_nanos(AERON_IDLE_STRATEGY_BACKOFF_MIN_PARK_PERIOD_NS as u64),
+            max_park: Duration::from_nanos(AERON_IDLE_STRATEGY_BACKOFF_MAX_PARK_PERIOD_NS as u64),
+        }
+    }
+
+    /// Name the driver reports for the strategy, see `get_*_idle_strategy`.
+    pub fn name(&self) -> &str {
+        match self {
+            DriverIdleStrategy::Noop => "noop",
+            DriverIdleStrategy::BusySpin => "spin",
+            DriverIdleStrategy::Yielding => "yield",
+            DriverIdleStrategy::Sleeping { .. } => "sleep-ns",
+            DriverIdleStrategy::Backoff { .. } => "backoff",
+            DriverIdleStrategy::Custom(name) => name,
+        }
+    }
+
+    /// Arguments in the `*_IDLE_STRATEGY_INIT_ARGS` format, `None` if the strategy has none.
+    pub fn init_args(&self) -> Option<String> {
+        match self {
+            DriverIdleStrategy::Sleeping { ns } => Some(ns.to_string()),
+            DriverIdleStrategy::Backoff {
+                max_spins,
+                max_yields,
+                min_park,
+                max_park,
+            } => Some(format!(
+                "{max_spins},{max_yields},{},{}",
+                min_park.as_nanos(),
+                max_park.as_nanos()
+            )),
+            _ => None,
+        }
+    }
+
+    /// Parses a strategy name and its init args, the inverse of [`name`](Self::name) and
+    /// [`init_args`](Self::init_args).
+    pub fn parse(name: &str, init_args: Option<&str>) -> Result<Self, String> {
+        let init_args = init_args.map(str::trim).filter(|args| !args.is_empty());
+        let strategy = match name.trim() {
+            "noop" => DriverIdleStrategy::Noop,
+            "spin" => DriverIdleStrategy::BusySpin,
+            "yield" => DriverIdleStrategy::Yielding,
+            "sleep-ns" | "sleeping" => DriverIdleStrategy::Sleeping {
+                ns: init_args
+                    .ok_or("sleep-ns idle strategy requires a sleep period in ns")?
+                    .parse()
+                    .map_err(|e| format!("invalid sleep period {init_args:?}: {e}"))?,
+            },
+            "backoff" => match init_args {
+                None => DriverIdleStrategy::backoff(),
+                Some(args) => {
+                    let values = args
+                        .split(',')
+                        .map(|v| v.trim().parse::<u64>())
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|e| format!("invalid backoff args {args:?}: {e}"))?;
+                    let [max_spins, max_yields, min_park, max_park] = values[..] else {
+                        return Err(format!(
+                            "expected backoff args max_spins,max_yields,min_park_ns,max_park_ns, got {args:?}"
+                        ));
+                    };
+                    DriverIdleStrategy::Backoff {
+                        max_spins,
+                        max_yields,
+                        min_park: Duration::from_nanos(min_park),
+                        max_park: Duration::from_nanos(max_park),
+                    }
+                }
+            },
+            name if is_registered(name) => DriverIdleStrategy::Custom(name.to_string()),
+            name => return Err(format!("unknown idle strategy {name:?}")),
+        };
+        strategy.validate()?;
+        Ok(strategy)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            DriverIdleStrategy::Sleeping { ns: 0 } => {
+                Err("sleeping idle strategy period must be greater than 0".to_string())
+            }
+            DriverIdleStrategy::Backoff {
+                min_park, max_park, ..
+            } if min_park.is_zero() || min_park > max_park => Err(format!(
+                "backoff park periods must satisfy 0 < min_park ({min_park:?}) <= max_park ({max_park:?})"
+            )),
+            DriverIdleStrategy::Custom(name) if !is_registered(name) => {
+                Err(format!("idle strategy {name:?} is not registered"))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl FromStr for DriverIdleStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DriverIdleStrategy::parse(s, None)
+    }
+}
+
+impl fmt::Display for DriverIdleStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())?;
+        if let Some(args) = self.init_args() {
+            write!(f, "({args})")?;
+        }
+        Ok(())
+    }
+}
+
+const BUILT_IN_NAMES: [&str; 6] = ["noop", "spin", "yield", "sleep-ns", "sleeping", "backoff"];
+
+type StrategyFactory = Arc<dyn Fn() -> Box<dyn IdleStrategy> + Send + Sync>;
+
+fn registry() -> &'static Mutex<HashMap<String, StrategyFactory>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<String, StrategyFactory>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn is_registered(name: &str) -> bool {
+    registry().lock().is_ok_and(|r| r.contains_key(name))
+}
+
+/// Makes an idle strategy available as `DriverIdleStrategy::Custom(name)`, `factory` creates a
+/// strategy for each agent it is used by. Registering a name again replaces the factory for
+/// contexts configured afterwards.
+pub fn register_idle_strategy<S, F>(name: &str, factory: F) -> Result<(), AeronCError>
+where
+    S: IdleStrategy,
+    F: Fn() -> S + Send + Sync + 'static,
+{
+    if BUILT_IN_NAMES.contains(&name) {
+        log::error!("idle strategy name {name:?} is reserved for a built in strategy");
+        return Err(AeronCError::from_code(-1));
+    }
+    registry()
+        .lock()
+        .map_err(|_| AeronCError::from_code(-1))?
+        .insert(name.to_string(), Arc::new(move || Box::new(factory())));
+    Ok(())
+}
+
+/// Strings and Rust state handed to the driver context, freed when the context is dropped.
+struct OwnedIdleStrategy {
+    _name: CString,
+    _init_args: Option<CString>,
+    strategy: Option<*mut Box<dyn IdleStrategy>>,
+}
+
+impl Drop for OwnedIdleStrategy {
+    fn drop(&mut self) {
+        if let Some(strategy) = self.strategy {
+            unsafe { drop(Box::from_raw(strategy)) };
+        }
+    }
+}
+
+/// The driver owns the state pointer it is given, so custom strategies are referenced through a
+/// cell allocated by the driver.
+unsafe extern "C" fn custom_idle(state: *mut c_void, work_count: c_int) {
+    let strategy = *(state as *mut *mut Box<dyn IdleStrategy>);
+    (*strategy).idle(work_count);
+}
+
+impl AeronDriverContext {
+    /// Uses `strategy` for `agent`, must be called before the driver is started.
+    pub fn set_idle_strategy(
+        &self,
+        agent: IdleAgent,
+        strategy: &DriverIdleStrategy,
+    ) -> Result<(), AeronCError> {
+        if let Err(e) = strategy.validate() {
+            log::error!("invalid {agent:?} idle strategy: {e}");
+            return Err(AeronCError::from_code(-1));
+        }
+        let name = strategy.name().into_c_string();
+        let init_args = strategy.init_args().map(|args| args.into_c_string());
+
+        let owned = match strategy {
+            DriverIdleStrategy::Noop
+            | DriverIdleStrategy::BusySpin
+            | DriverIdleStrategy::Yielding
+            | DriverIdleStrategy::Sleeping { .. } => {
+                // init args are read when the strategy is set, so must be set first
+                self.set_idle_strategy_init_args(agent, init_args.as_ref())?;
+                match agent {
+                    IdleAgent::Conductor => self.set_conductor_idle_strategy(&name),
+                    IdleAgent::Sender => self.set_sender_idle_strategy(&name),
+                    IdleAgent::Receiver => self.set_receiver_idle_strategy(&name),
+                    IdleAgent::SharedNetwork => self.set_sharednetwork_idle_strategy(&name),
+                    IdleAgent::Shared => self.set_shared_idle_strategy(&name),
+                }?;
+                OwnedIdleStrategy {
+                    _name: name,
+                    _init_args: init_args,
+                    strategy: None,
+                }
+            }
+            DriverIdleStrategy::Backoff {
+                max_spins,
+                max_yields,
+                min_park,
+                max_park,
+            } => {
+                let mut state: *mut c_void = std::ptr::null_mut();
+                let result = unsafe {
+                    aeron_idle_strategy_backoff_state_init(
+                        &mut state,
+                        *max_spins,
+                        *max_yields,
+                        min_park.as_nanos() as u64,
+                        max_park.as_nanos() as u64,
+                    )
+                };
+                if result < 0 {
+                    return Err(AeronCError::from_code(result));
+                }
+                // only recorded so the context reports the strategy's settings
+                if let Err(e) = self.set_idle_strategy_init_args(agent, init_args.as_ref()) {
+                    unsafe { aeron_free(state) };
+                    return Err(e);
+                }
+                self.set_idle_strategy_func(
+                    agent,
+                    Some(aeron_idle_strategy_backoff_idle),
+                    state,
+                    &name,
+                );
+                OwnedIdleStrategy {
+                    _name: name,
+                    _init_args: init_args,
+                    strategy: None,
+                }
+            }
+            DriverIdleStrategy::Custom(custom) => {
+                let factory = registry()
+                    .lock()
+                    .map_err(|_| AeronCError::from_code(-1))?
+                    .get(custom)
+                    .cloned()
+                    .ok_or_else(|| AeronCError::from_code(-1))?;
+                let strategy = Box::into_raw(Box::new(factory()));
+                let owned = OwnedIdleStrategy {
+                    _name: name,
+                    _init_args: None,
+                    strategy: Some(strategy),
+                };
+
+                let mut cell: *mut c_void = std::ptr::null_mut();
+                let result = unsafe { aeron_alloc(&mut cell, std::mem::size_of::<*mut c_void>()) };
+                if result < 0 || cell.is_null() {
+                    return Err(AeronCError::from_code(result.min(-1)));
+                }
+                unsafe { (cell as *mut *mut Box<dyn IdleStrategy>).write(strategy) };
+                self.set_idle_strategy_func(agent, Some(custom_idle), cell, &owned._name);
+                owned
+            }
+        };
+        self.inner.add_dependency(owned);
+        Ok(())
+    }
+
+    /// Uses `strategy` for every agent.
+    pub fn set_idle_strategies(&self, strategy: &DriverIdleStrategy) -> Result<(), AeronCError> {
+        for agent in IdleAgent::ALL {
+            self.set_idle_strategy(agent, strategy)?;
+        }
+        Ok(())
+    }
+
+    /// The strategy used by `agent`, `None` if it isn't one of [`DriverIdleStrategy`]'s.
+    pub fn idle_strategy(&self, agent: IdleAgent) -> Option<DriverIdleStrategy> {
+        let (name, init_args) = match agent {
+            IdleAgent::Conductor => (
+                self.get_conductor_idle_strategy(),
+                self.get_conductor_idle_strategy_init_args(),
+            ),
+            IdleAgent::Sender => (
+                self.get_sender_idle_strategy(),
+                self.get_sender_idle_strategy_init_args(),
+            ),
+            IdleAgent::Receiver => (
+                self.get_receiver_idle_strategy(),
+                self.get_receiver_idle_strategy_init_args(),
+            ),
+            IdleAgent::SharedNetwork => (
+                self.get_sharednetwork_idle_strategy(),
+                self.get_sharednetwork_idle_strategy_init_args(),
+            ),
+            IdleAgent::Shared => (
+                self.get_shared_idle_strategy(),
+                self.get_shared_idle_strategy_init_args(),
+            ),
+        };
+        DriverIdleStrategy::parse(name, Some(init_args)).ok()
+    }
+
+    /// Sets the init args of `agent`'s idle strategy, leaving them unchanged when `None`.
+    fn set_idle_strategy_init_args(
+        &self,
+        agent: IdleAgent,
+        init_args: Option<&CString>,
+    ) -> Result<(), AeronCError> {
+        if let Some(args) = init_args {
+            match agent {
+                IdleAgent::Conductor => self.set_conductor_idle_strategy_init_args(args),
+                IdleAgent::Sender => self.set_sender_idle_strategy_init_args(args),
+                IdleAgent::Receiver => self.set_receiver_idle_strategy_init_args(args),
+                IdleAgent::SharedNetwork => self.set_sharednetwork_idle_strategy_init_args(args),
+                IdleAgent::Shared => self.set_shared_idle_strategy_init_args(args),
+            }?;
+        }
+        Ok(())
+    }
+
+    /// Replaces the idle function and state of `agent`, which the driver frees when the context is
+    /// closed.
+    fn set_idle_strategy_func(
+        &self,
+        agent: IdleAgent,
+        func: aeron_idle_strategy_func_t,
+        state: *mut c_void,
+        name: &CString,
+    ) {
+        let ctx = self.get_inner_mut();
+        let (current_func, current_state, current_name): (_, _, &mut *const c_char) = match agent {
+            IdleAgent::Conductor => (
+                &mut ctx.conductor_idle_strategy_func,
+                &mut ctx.conductor_idle_strategy_state,
+                &mut ctx.conductor_idle_strategy_name,
+            ),
+            IdleAgent::Sender => (
+                &mut ctx.sender_idle_strategy_func,
+                &mut ctx.sender_idle_strategy_state,
+                &mut ctx.sender_idle_strategy_name,
+            ),
+            IdleAgent::Receiver => (
+                &mut ctx.receiver_idle_strategy_func,
+                &mut ctx.receiver_idle_strategy_state,
+                &mut ctx.receiver_idle_strategy_name,
+            ),
+            IdleAgent::SharedNetwork => (
+                &mut ctx.shared_network_idle_strategy_func,
+                &mut ctx.shared_network_idle_strategy_state,
+                &mut ctx.shared_network_idle_strategy_name,
+            ),
+            IdleAgent::Shared => (
+                &mut ctx.shared_idle_strategy_func,
+                &mut ctx.shared_idle_strategy_state,
+                &mut ctx.shared_idle_strategy_name,
+            ),
+        };
+        if !current_state.is_null() {
+            unsafe { aeron_free(*current_state) };
+        }
+        *current_func = func;
+        *current_state = state;
+        *current_name = name.as_ptr();
+    }
+}
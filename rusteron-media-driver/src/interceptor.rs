@@ -0,0 +1,609 @@
+//! UDP channel interceptors implemented in Rust.
+//!
+//! The media driver can pass every UDP packet it sends or receives through a chain of interceptors.
+//! Implement [`UdpChannelInterceptor`] and install it with
+//! [`AeronDriverContext::add_udp_channel_interceptor`] before the driver is started, e.g. for
+//! packet capture, checksums or chaos testing. [`LossGenerator`] is a ready made interceptor which
+//! randomly drops frames.
+//!
+//! ```no_run
+//! # use rusteron_media_driver::*;
+//! # use rusteron_media_driver::interceptor::*;
+//! # fn run() -> Result<(), AeronCError> {
+//! let ctx = AeronDriverContext::new()?;
+//! let loss = LossGenerator::new(0.1).stream_id(1001);
+//! let stats = loss.stats();
+//! ctx.add_udp_channel_interceptor(loss)?;
+//! let (stop, driver) = AeronDriver::launch_embedded(ctx, false);
+//! // ...
+//! println!("dropped {} frames", stats.dropped());
+//! # Ok(())
+//! # }
+//! ```
+use crate::bindings::*;
+use crate::ffi::{socket_addr, ContextRegistry};
+use crate::{
+    AeronCError, AeronCountersManager, AeronDriverContext, AeronUdpChannelInterceptorBindings,
+};
+use std::net::SocketAddr;
+use std::os::raw::{c_int, c_void};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Aeron frame types, see `aeron_protocol.h`.
+pub const HDR_TYPE_PAD: u16 = 0x00;
+pub const HDR_TYPE_DATA: u16 = 0x01;
+pub const HDR_TYPE_NAK: u16 = 0x02;
+pub const HDR_TYPE_SM: u16 = 0x03;
+pub const HDR_TYPE_ERR: u16 = 0x04;
+pub const HDR_TYPE_SETUP: u16 = 0x05;
+pub const HDR_TYPE_RTTM: u16 = 0x06;
+
+/// What to do with a packet passed to a [`UdpChannelInterceptor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterceptAction {
+    /// pass the packet on to the next interceptor, and eventually the socket or the driver
+    Forward,
+    /// silently discard the packet
+    Drop,
+    /// send the packet once at least the given time has passed, after any packets sent in the
+    /// meantime. Only supported for outgoing packets, incoming packets are forwarded.
+    Delay(Duration),
+    /// send the packet now and a copy of it once the given time has passed. Only supported for
+    /// outgoing packets, incoming packets are forwarded.
+    Duplicate(Duration),
+    /// send the packet once a later packet has been sent on the same transport and at least the
+    /// given time has passed. Only supported for outgoing packets, incoming packets are forwarded.
+    Reorder(Duration),
+}
+
+/// Hooks invoked by the media driver for every UDP packet sent or received.
+///
+/// Hooks run on the driver's sender and receiver threads so must be cheap and must not block.
+pub trait UdpChannelInterceptor: Send + 'static {
+    /// Called once on the driver's conductor thread when the first UDP transport is created,
+    /// e.g. to allocate driver counters from `counters`.
+    fn on_start(&mut self, counters: &AeronCountersManager) {
+        let _ = counters;
+    }
+
+    /// Called before a packet is sent to `addr`. The packet may be modified in place.
+    fn on_outgoing(&mut self, buffer: &mut [u8], addr: Option<SocketAddr>) -> InterceptAction {
+        let _ = (buffer, addr);
+        InterceptAction::Forward
+    }
+
+    /// Called when a packet is received from `addr`, before it is processed by the driver.
+    fn on_incoming(&mut self, buffer: &[u8], addr: Option<SocketAddr>) -> InterceptAction {
+        let _ = (buffer, addr);
+        InterceptAction::Forward
+    }
+}
+
+/// The common fields of an Aeron frame header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameHeader {
+    pub frame_length: i32,
+    pub frame_type: u16,
+    pub session_id: i32,
+    pub stream_id: i32,
+}
+
+impl FrameHeader {
+    /// Parses the header at the start of `buffer`, returns `None` if it is too short.
+    pub fn parse(buffer: &[u8]) -> Option<Self> {
+        let read_i32 = |offset: usize| -> Option<i32> {
+            Some(i32::from_le_bytes(
+                buffer.get(offset..offset + 4)?.try_into().ok()?,
+            ))
+        };
+        let frame_type = u16::from_le_bytes(buffer.get(6..8)?.try_into().ok()?);
+        // data and setup frames carry a term offset before the session id
+        let session_offset = match frame_type {
+            HDR_TYPE_DATA | HDR_TYPE_PAD | HDR_TYPE_SETUP => 12,
+            _ => 8,
+        };
+        Some(Self {
+            frame_length: read_i32(0)?,
+            frame_type,
+            session_id: read_i32(session_offset)?,
+            stream_id: read_i32(session_offset + 4)?,
+        })
+    }
+}
+
+/// Small, seedable xorshift generator so interceptors don't need an external rng.
+#[derive(Debug, Clone)]
+pub struct XorShiftRng(u64);
+
+impl XorShiftRng {
+    pub fn new(seed: u64) -> Self {
+        // zero is a fixed point of xorshift
+        Self(if seed == 0 {
+            0x9E37_79B9_7F4A_7C15
+        } else {
+            seed
+        })
+    }
+
+    pub fn from_time() -> Self {
+        Self::new(
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or_default(),
+        )
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    /// uniform value in `[0, 1)`
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Counts of frames seen by a [`LossGenerator`].
+#[derive(Debug, Default)]
+pub struct LossStats {
+    dropped: AtomicU64,
+    forwarded: AtomicU64,
+}
+
+impl LossStats {
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    pub fn forwarded(&self) -> u64 {
+        self.forwarded.load(Ordering::Relaxed)
+    }
+}
+
+/// Randomly drops frames matching the configured frame type, stream and session filters.
+///
+/// By default only incoming data frames are dropped (i.e. loss as seen by the receiver), use
+/// [`LossGenerator::outgoing`] to drop frames before they are sent instead.
+#[derive(Debug)]
+pub struct LossGenerator {
+    drop_rate: f64,
+    frame_type: Option<u16>,
+    stream_id: Option<i32>,
+    session_id: Option<i32>,
+    outgoing: bool,
+    rng: XorShiftRng,
+    stats: Arc<LossStats>,
+}
+
+impl LossGenerator {
+    /// `drop_rate` is the probability, between 0 and 1, that a matching frame is dropped.
+    pub fn new(drop_rate: f64) -> Self {
+        Self {
+            drop_rate: drop_rate.clamp(0.0, 1.0),
+            frame_type: Some(HDR_TYPE_DATA),
+            stream_id: None,
+            session_id: None,
+            outgoing: false,
+            rng: XorShiftRng::from_time(),
+            stats: Arc::new(LossStats::default()),
+        }
+    }
+
+    /// seed the random generator so the same frames are dropped on every run
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = XorShiftRng::new(seed);
+        self
+    }
+
+    pub fn stream_id(mut self, stream_id: i32) -> Self {
+        self.stream_id = Some(stream_id);
+        self
+    }
+
+    pub fn session_id(mut self, session_id: i32) -> Self {
+        self.session_id = Some(session_id);
+        self
+    }
+
+    /// only drop frames of the given type (defaults to [`HDR_TYPE_DATA`])
+    pub fn frame_type(mut self, frame_type: u16) -> Self {
+        self.frame_type = Some(frame_type);
+        self
+    }
+
+    /// drop frames of every type
+    pub fn any_frame_type(mut self) -> Self {
+        self.frame_type = None;
+        self
+    }
+
+    /// drop frames as they are sent rather than when they are received
+    pub fn outgoing(mut self) -> Self {
+        self.outgoing = true;
+        self
+    }
+
+    /// stats which remain readable after the generator is installed on a driver
+    pub fn stats(&self) -> Arc<LossStats> {
+        self.stats.clone()
+    }
+
+    fn matches(&self, header: &FrameHeader) -> bool {
+        self.frame_type.map_or(true, |t| t == header.frame_type)
+            && self.stream_id.map_or(true, |s| s == header.stream_id)
+            && self.session_id.map_or(true, |s| s == header.session_id)
+    }
+
+    /// Decides whether the frame in `buffer` should be dropped.
+    pub fn should_drop(&mut self, buffer: &[u8]) -> bool {
+        let drop = match FrameHeader::parse(buffer) {
+            Some(header) if self.matches(&header) => self.rng.next_f64() < self.drop_rate,
+            _ => false,
+        };
+        if drop {
+            self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.stats.forwarded.fetch_add(1, Ordering::Relaxed);
+        }
+        drop
+    }
+}
+
+impl UdpChannelInterceptor for LossGenerator {
+    fn on_outgoing(&mut self, buffer: &mut [u8], _addr: Option<SocketAddr>) -> InterceptAction {
+        if self.outgoing && self.should_drop(buffer) {
+            InterceptAction::Drop
+        } else {
+            InterceptAction::Forward
+        }
+    }
+
+    fn on_incoming(&mut self, buffer: &[u8], _addr: Option<SocketAddr>) -> InterceptAction {
+        if !self.outgoing && self.should_drop(buffer) {
+            InterceptAction::Drop
+        } else {
+            InterceptAction::Forward
+        }
+    }
+}
+
+/// Copy of an outgoing packet held back by [`InterceptAction::Delay`],
+/// [`InterceptAction::Duplicate`] or [`InterceptAction::Reorder`].
+struct PendingPacket {
+    transport: usize,
+    /// next outgoing interceptor of the transport, which lives as long as the transport
+    delegate: usize,
+    address: sockaddr_storage,
+    data: Vec<u8>,
+    due: Instant,
+    /// reordered packets wait for a later packet to be sent on the transport
+    overtaken: bool,
+}
+
+#[derive(Default)]
+struct ChainState {
+    interceptors: Vec<Box<dyn UdpChannelInterceptor>>,
+    pending: Vec<PendingPacket>,
+    started: bool,
+}
+
+impl ChainState {
+    fn on_start(&mut self, counters: &AeronCountersManager) {
+        if !self.started {
+            self.started = true;
+            self.interceptors
+                .iter_mut()
+                .for_each(|i| i.on_start(counters));
+        }
+    }
+
+    fn on_outgoing(&mut self, buffer: &mut [u8], addr: Option<SocketAddr>) -> InterceptAction {
+        self.interceptors
+            .iter_mut()
+            .map(|i| i.on_outgoing(buffer, addr))
+            .find(|a| *a != InterceptAction::Forward)
+            .unwrap_or(InterceptAction::Forward)
+    }
+
+    fn on_incoming(&mut self, buffer: &[u8], addr: Option<SocketAddr>) -> InterceptAction {
+        self.interceptors
+            .iter_mut()
+            .map(|i| i.on_incoming(buffer, addr))
+            .find(|a| *a == InterceptAction::Drop)
+            .unwrap_or(InterceptAction::Forward)
+    }
+
+    /// Sends the held packets of `transport` which are due, reordered packets only once a later
+    /// packet has overtaken them.
+    unsafe fn release_due(&mut self, transport: *mut aeron_udp_channel_transport_t) {
+        let now = Instant::now();
+        let mut i = 0;
+        while i < self.pending.len() {
+            let due = &self.pending[i];
+            if due.transport == transport as usize && due.overtaken && due.due <= now {
+                let mut due = self.pending.remove(i);
+                let delegate = &*(due.delegate as *const aeron_udp_channel_outgoing_interceptor_t);
+                if let Some(send) = delegate.outgoing_send_func {
+                    let mut packet = iovec {
+                        iov_base: due.data.as_mut_ptr() as *mut c_void,
+                        iov_len: due.data.len(),
+                    };
+                    let mut bytes = 0;
+                    send(
+                        delegate.interceptor_state,
+                        delegate.next_interceptor,
+                        transport,
+                        &mut due.address,
+                        &mut packet,
+                        1,
+                        &mut bytes,
+                    );
+                }
+            } else {
+                i += 1;
+            }
+        }
+    }
+}
+
+type InterceptorChain = Arc<Mutex<ChainState>>;
+
+/// Interceptor chains of each driver context, which the C init callbacks look up.
+static CHAINS: ContextRegistry<usize, InterceptorChain> = ContextRegistry::new();
+
+unsafe extern "C" fn interceptor_init(
+    interceptor_state: *mut *mut c_void,
+    context: *mut aeron_driver_context_t,
+    _affinity: aeron_udp_channel_transport_affinity_t,
+) -> c_int {
+    match CHAINS.get(&(context as usize)) {
+        Some(chain) => {
+            if let Ok(mut state) = chain.lock() {
+                state.on_start(&AeronDriverContext::from(context).counters_manager());
+            }
+            *interceptor_state = Box::into_raw(Box::new(chain)) as *mut c_void;
+            0
+        }
+        None => {
+            log::error!("no udp channel interceptors registered for driver context");
+            -1
+        }
+    }
+}
+
+unsafe extern "C" fn interceptor_close(interceptor_state: *mut c_void) -> c_int {
+    if !interceptor_state.is_null() {
+        drop(Box::from_raw(interceptor_state as *mut InterceptorChain));
+    }
+    0
+}
+
+unsafe extern "C" fn interceptor_outgoing_send(
+    interceptor_state: *mut c_void,
+    delegate: *mut aeron_udp_channel_outgoing_interceptor_t,
+    transport: *mut aeron_udp_channel_transport_t,
+    address: *mut sockaddr_storage,
+    iov: *mut iovec,
+    iov_length: usize,
+    bytes_sent: *mut i64,
+) -> c_int {
+    let chain = &*(interceptor_state as *const InterceptorChain);
+    let Some(delegate) = delegate.as_ref() else {
+        return -1;
+    };
+    let Some(send) = delegate.outgoing_send_func else {
+        return -1;
+    };
+    let addr = socket_addr(address);
+    let Ok(mut chain) = chain.lock() else {
+        return send(
+            delegate.interceptor_state,
+            delegate.next_interceptor,
+            transport,
+            address,
+            iov,
+            iov_length,
+            bytes_sent,
+        );
+    };
+
+    let mut sent = 0;
+    let mut total_bytes = 0;
+    for i in 0..iov_length {
+        let packet = &mut *iov.add(i);
+        let buffer = std::slice::from_raw_parts_mut(packet.iov_base as *mut u8, packet.iov_len);
+        let action = chain.on_outgoing(buffer, addr);
+
+        if let InterceptAction::Delay(delay)
+        | InterceptAction::Duplicate(delay)
+        | InterceptAction::Reorder(delay) = action
+        {
+            chain.pending.push(PendingPacket {
+                transport: transport as usize,
+                delegate: delegate as *const _ as usize,
+                address: *address,
+                data: buffer.to_vec(),
+                due: Instant::now() + delay,
+                overtaken: !matches!(action, InterceptAction::Reorder(_)),
+            });
+        }
+
+        if matches!(
+            action,
+            InterceptAction::Drop | InterceptAction::Delay(_) | InterceptAction::Reorder(_)
+        ) {
+            // report the packet as sent so the sender does not retry it
+            total_bytes += packet.iov_len as i64;
+        } else {
+            let mut bytes = 0;
+            let result = send(
+                delegate.interceptor_state,
+                delegate.next_interceptor,
+                transport,
+                address,
+                packet,
+                1,
+                &mut bytes,
+            );
+            if result < 0 {
+                return result;
+            }
+            total_bytes += bytes;
+            if result == 0 {
+                break;
+            }
+            chain
+                .pending
+                .iter_mut()
+                .filter(|p| p.transport == transport as usize)
+                .for_each(|p| p.overtaken = true);
+        }
+        sent += 1;
+    }
+
+    // after the packets above, so packets they overtook can go
+    chain.release_due(transport);
+
+    if !bytes_sent.is_null() {
+        *bytes_sent = total_bytes;
+    }
+    sent
+}
+
+unsafe extern "C" fn interceptor_incoming(
+    interceptor_state: *mut c_void,
+    delegate: *mut aeron_udp_channel_incoming_interceptor_t,
+    transport: *mut aeron_udp_channel_transport_t,
+    receiver_clientd: *mut c_void,
+    endpoint_clientd: *mut c_void,
+    destination_clientd: *mut c_void,
+    buffer: *mut u8,
+    length: usize,
+    addr: *mut sockaddr_storage,
+    media_timestamp: *mut timespec,
+) {
+    let chain = &*(interceptor_state as *const InterceptorChain);
+    let packet = std::slice::from_raw_parts(buffer as *const u8, length);
+    let source = socket_addr(addr);
+    let action = match chain.lock() {
+        Ok(mut chain) => {
+            // transports are polled even when nothing is being sent on them
+            chain.release_due(transport);
+            chain.on_incoming(packet, source)
+        }
+        Err(_) => InterceptAction::Forward,
+    };
+
+    if action != InterceptAction::Drop {
+        if let Some(delegate) = delegate.as_ref() {
+            if let Some(incoming) = delegate.incoming_func {
+                incoming(
+                    delegate.interceptor_state,
+                    delegate.next_interceptor,
+                    transport,
+                    receiver_clientd,
+                    endpoint_clientd,
+                    destination_clientd,
+                    buffer,
+                    length,
+                    addr,
+                    media_timestamp,
+                );
+            }
+        }
+    }
+}
+
+unsafe extern "C" fn interceptor_transport_notification(
+    interceptor_state: *mut c_void,
+    transport: *mut aeron_udp_channel_transport_t,
+    _udp_channel: *const aeron_udp_channel_t,
+    _data_packet_dispatcher: *mut aeron_data_packet_dispatcher_t,
+    notification_type: aeron_udp_channel_interceptor_notification_type_t,
+) -> c_int {
+    // held packets must not outlive their transport, its address may be reused by a new one
+    if notification_type
+        == aeron_udp_channel_interceptor_notification_type_t::AERON_UDP_CHANNEL_INTERCEPTOR_REMOVE_NOTIFICATION
+    {
+        let chain = &*(interceptor_state as *const InterceptorChain);
+        if let Ok(mut chain) = chain.lock() {
+            chain.pending.retain(|p| p.transport != transport as usize);
+        }
+    }
+    0
+}
+
+/// Allocates bindings with the C allocator as the driver context owns them once set.
+unsafe fn new_bindings(
+    next: *const aeron_udp_channel_interceptor_bindings_t,
+) -> Result<*mut aeron_udp_channel_interceptor_bindings_t, AeronCError> {
+    let mut ptr: *mut c_void = std::ptr::null_mut();
+    let result = aeron_alloc(
+        &mut ptr,
+        std::mem::size_of::<aeron_udp_channel_interceptor_bindings_t>(),
+    );
+    if result < 0 || ptr.is_null() {
+        return Err(AeronCError::from_code(result.min(-1)));
+    }
+    let bindings = ptr as *mut aeron_udp_channel_interceptor_bindings_t;
+    bindings.write(aeron_udp_channel_interceptor_bindings_t {
+        outgoing_init_func: Some(interceptor_init),
+        incoming_init_func: Some(interceptor_init),
+        outgoing_send_func: Some(interceptor_outgoing_send),
+        incoming_func: Some(interceptor_incoming),
+        outgoing_close_func: Some(interceptor_close),
+        incoming_close_func: Some(interceptor_close),
+        outgoing_transport_notification_func: Some(interceptor_transport_notification),
+        meta_info: aeron_udp_channel_interceptor_bindings_stct_interceptor_meta_info_fields {
+            name: c"rusteron".as_ptr(),
+            type_: c"interceptor".as_ptr(),
+            next_interceptor_bindings: next,
+            source_symbol: None,
+        },
+        ..Default::default()
+    });
+    Ok(bindings)
+}
+
+impl AeronDriverContext {
+    /// Adds `interceptor` to the chain of Rust interceptors applied to every UDP packet sent and
+    /// received by a driver using this context. Must be called before the driver is started.
+    pub fn add_udp_channel_interceptor<I: UdpChannelInterceptor>(
+        &self,
+        interceptor: I,
+    ) -> Result<(), AeronCError> {
+        let key = self.get_inner() as usize;
+        if let Some(chain) = CHAINS.get(&key) {
+            if let Ok(mut chain) = chain.lock() {
+                chain.interceptors.push(Box::new(interceptor));
+            }
+            return Ok(());
+        }
+
+        unsafe {
+            let outgoing = new_bindings(self.get_udp_channel_outgoing_interceptors().get_inner())?;
+            self.set_udp_channel_outgoing_interceptors(&AeronUdpChannelInterceptorBindings::from(
+                outgoing,
+            ))?;
+            let incoming = new_bindings(self.get_udp_channel_incoming_interceptors().get_inner())?;
+            self.set_udp_channel_incoming_interceptors(&AeronUdpChannelInterceptorBindings::from(
+                incoming,
+            ))?;
+        }
+
+        let chain = ChainState {
+            interceptors: vec![Box::new(interceptor)],
+            pending: vec![],
+            started: false,
+        };
+        CHAINS.insert(self, key, Arc::new(Mutex::new(chain)))
+    }
+}
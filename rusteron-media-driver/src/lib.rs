@@ -12,6 +12,7 @@
 //! - **`backtrace`** - When enabled will log a backtrace for each AeronCError
 //! - **`extra-logging`** - When enabled will log when resource is created and destroyed. useful if your seeing a segfault due to a resource being closed
 //! - **`precompile`** - When enabled will use precompiled c code instead of requiring cmake and java to me installed
+//! - **`serde`** - When enabled derives `Serialize`/`Deserialize` for [`config::DriverConfigSnapshot`]
 
 pub mod bindings {
     include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
@@ -25,6 +26,18 @@ use std::sync::Arc;
 use std::thread::{sleep, JoinHandle};
 use std::time::Duration;
 
+pub mod config;
+pub mod congestion_control;
+pub mod duty_cycle;
+mod ffi;
+pub mod flow_control;
+pub mod idle_strategy;
+pub mod interceptor;
+pub mod name_resolver;
+pub mod network_conditions;
+pub mod port_manager;
+pub mod termination;
+
 include!(concat!(env!("OUT_DIR"), "/aeron.rs"));
 include!(concat!(env!("OUT_DIR"), "/aeron_custom.rs"));
 
@@ -42,6 +55,10 @@ impl AeronDriver {
 
         let stop = Arc::new(AtomicBool::new(false));
         let stop_copy = stop.clone();
+        // an authorised termination request stops the duty cycle loop below
+        if let Err(e) = aeron_context.add_termination_stop_flag(stop.clone()) {
+            log::error!("failed to register driver termination hook: {e:?}");
+        }
         // Register signal handler for SIGINT (Ctrl+C)
         if register_sigint {
             let stop_copy2 = stop.clone();
@@ -128,6 +145,7 @@ impl AeronDriver {
 mod tests {
     use super::*;
     use log::error;
+    use serial_test::serial;
     use std::os::raw::c_int;
     use std::sync::atomic::Ordering;
     use std::time::Duration;
@@ -244,6 +262,905 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn loss_generator_filters() {
+        use crate::interceptor::*;
+
+        // data frame header for session 7, stream 1001
+        let mut frame = [0u8; 32];
+        frame[0..4].copy_from_slice(&32i32.to_le_bytes());
+        frame[6..8].copy_from_slice(&HDR_TYPE_DATA.to_le_bytes());
+        frame[12..16].copy_from_slice(&7i32.to_le_bytes());
+        frame[16..20].copy_from_slice(&1001i32.to_le_bytes());
+        let header = FrameHeader::parse(&frame).unwrap();
+        assert_eq!(HDR_TYPE_DATA, header.frame_type);
+        assert_eq!(7, header.session_id);
+        assert_eq!(1001, header.stream_id);
+        assert_eq!(None, FrameHeader::parse(&frame[..10]));
+
+        let mut all = LossGenerator::new(1.0).stream_id(1001).session_id(7);
+        let mut other_stream = LossGenerator::new(1.0).stream_id(1002);
+        let mut status_only = LossGenerator::new(1.0).frame_type(HDR_TYPE_SM);
+        let mut none = LossGenerator::new(0.0);
+        for _ in 0..10 {
+            assert_eq!(InterceptAction::Drop, all.on_incoming(&frame, None));
+            assert_eq!(
+                InterceptAction::Forward,
+                all.on_outgoing(&mut frame.clone(), None)
+            );
+            assert!(!other_stream.should_drop(&frame));
+            assert!(!status_only.should_drop(&frame));
+            assert!(!none.should_drop(&frame));
+        }
+        assert_eq!(10, all.stats().dropped());
+        assert_eq!(10, none.stats().forwarded());
+
+        // seeded generators drop the same frames
+        let mut a = LossGenerator::new(0.5).seed(42);
+        let mut b = LossGenerator::new(0.5).seed(42);
+        let drops: Vec<bool> = (0..100).map(|_| a.should_drop(&frame)).collect();
+        assert_eq!(
+            drops,
+            (0..100).map(|_| b.should_drop(&frame)).collect::<Vec<_>>()
+        );
+        assert!(drops.iter().any(|d| *d) && drops.iter().any(|d| !*d));
+    }
+
+    #[test]
+    #[serial]
+    fn udp_channel_interceptor() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::interceptor::*;
+        use std::sync::atomic::AtomicUsize;
+
+        struct PacketCount(Arc<AtomicUsize>, Arc<AtomicUsize>);
+        impl UdpChannelInterceptor for PacketCount {
+            fn on_outgoing(
+                &mut self,
+                _: &mut [u8],
+                _: Option<std::net::SocketAddr>,
+            ) -> InterceptAction {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                InterceptAction::Forward
+            }
+            fn on_incoming(
+                &mut self,
+                _: &[u8],
+                _: Option<std::net::SocketAddr>,
+            ) -> InterceptAction {
+                self.1.fetch_add(1, Ordering::SeqCst);
+                InterceptAction::Forward
+            }
+        }
+
+        let aeron_context = driver_context(0)?;
+        let sent = Arc::new(AtomicUsize::new(0));
+        let received = Arc::new(AtomicUsize::new(0));
+        aeron_context.add_udp_channel_interceptor(PacketCount(sent.clone(), received.clone()))?;
+        let loss = LossGenerator::new(0.0);
+        let stats = loss.stats();
+        aeron_context.add_udp_channel_interceptor(loss)?;
+
+        let (client, stop, driver_handle) = start_media_driver(&aeron_context)?;
+
+        let (publication, subscription) = udp_pub_sub(&client, 21000, 1001)?;
+
+        let start = std::time::Instant::now();
+        let mut delivered = 0;
+        while delivered == 0 && start.elapsed() < Duration::from_secs(10) {
+            publication.offer(b"hello", Handlers::no_reserved_value_supplier_handler());
+            delivered += subscription.poll_once(|_msg, _header| {}, 10)?;
+        }
+        assert!(delivered > 0);
+        assert!(sent.load(Ordering::SeqCst) > 0);
+        assert!(received.load(Ordering::SeqCst) > 0);
+        assert!(stats.forwarded() > 0);
+        assert_eq!(0, stats.dropped());
+
+        stop.store(true, Ordering::SeqCst);
+        driver_handle.join().unwrap()?;
+        Ok(())
+    }
+
+    #[test]
+    fn network_conditions_are_deterministic() {
+        use crate::interceptor::InterceptAction;
+        use crate::network_conditions::*;
+
+        let conditions = |seed| {
+            NetworkConditions::new(seed)
+                .loss(0.2)
+                .duplicate(0.1)
+                .reorder(0.1)
+                .delay(Duration::from_micros(100), Duration::from_micros(50))
+        };
+        let packet = [0u8; 64];
+        let mut a = conditions(7);
+        let mut b = conditions(7);
+        let actions: Vec<InterceptAction> = (0..1000).map(|_| a.decide(&packet, None)).collect();
+        assert_eq!(
+            actions,
+            (0..1000)
+                .map(|_| b.decide(&packet, None))
+                .collect::<Vec<_>>()
+        );
+
+        let counts = a.counters().snapshot();
+        assert_eq!(
+            1000,
+            counts.forwarded
+                + counts.dropped
+                + counts.delayed
+                + counts.duplicated
+                + counts.reordered
+        );
+        assert!(counts.dropped > 100 && counts.dropped < 300, "{counts:?}");
+        assert!(counts.duplicated > 0 && counts.reordered > 0 && counts.delayed > 0);
+        for action in actions {
+            if let InterceptAction::Delay(d)
+            | InterceptAction::Duplicate(d)
+            | InterceptAction::Reorder(d) = action
+            {
+                assert!(d >= Duration::from_micros(100) && d < Duration::from_micros(150));
+            }
+        }
+
+        // filtered out packets are untouched
+        let endpoint = "127.0.0.1:40123".parse().unwrap();
+        let mut filtered = NetworkConditions::new(1).loss(1.0).endpoint(endpoint);
+        assert_eq!(InterceptAction::Forward, filtered.decide(&packet, None));
+        assert_eq!(
+            InterceptAction::Drop,
+            filtered.decide(&packet, Some(endpoint))
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn network_conditions_retransmit() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::network_conditions::*;
+
+        let aeron_context = driver_context(1)?;
+        let counters = aeron_context.add_network_conditions(
+            NetworkConditions::new(1234)
+                .loss(0.1)
+                .reorder(0.05)
+                .duplicate(0.05)
+                .stream_id(1002),
+        )?;
+
+        let (client, stop, driver_handle) = start_media_driver(&aeron_context)?;
+
+        let (publication, subscription) = udp_pub_sub(&client, 21100, 1002)?;
+
+        // every message must arrive, in order, despite the loss and reordering
+        let mut next_expected = 0u64;
+        let mut next_to_send = 0u64;
+        let start = std::time::Instant::now();
+        while next_expected < 1000 && start.elapsed() < Duration::from_secs(30) {
+            if next_to_send < 1000
+                && publication.offer(
+                    &next_to_send.to_le_bytes(),
+                    Handlers::no_reserved_value_supplier_handler(),
+                ) > 0
+            {
+                next_to_send += 1;
+            }
+            subscription.poll_once(
+                |msg, _header| {
+                    assert_eq!(next_expected, u64::from_le_bytes(msg.try_into().unwrap()));
+                    next_expected += 1;
+                },
+                10,
+            )?;
+        }
+        assert_eq!(1000, next_expected);
+
+        let mut dropped_counter = None;
+        client.counters_reader().foreach_counter_once(
+            |value: i64, _id: i32, type_id: i32, key: &[u8], label: &str| {
+                if type_id == NETWORK_CONDITIONS_COUNTER_TYPE_ID
+                    && key[..8] == 1234u64.to_le_bytes()
+                    && label.starts_with("network conditions dropped")
+                {
+                    dropped_counter = Some(value);
+                }
+            },
+        );
+
+        let counts = counters.snapshot();
+        info!("network conditions {:?}", counts);
+        assert!(counts.dropped > 0);
+        let dropped_counter = dropped_counter.expect("no dropped counter");
+        assert!(dropped_counter > 0 && dropped_counter as u64 <= counts.dropped);
+
+        stop.store(true, Ordering::SeqCst);
+        driver_handle.join().unwrap()?;
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn network_conditions_reorder() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::interceptor::*;
+        use crate::network_conditions::*;
+        use std::sync::Mutex;
+
+        /// records the (term id, term offset) of each data frame of a stream as it arrives
+        struct ArrivalOrder(i32, Arc<Mutex<Vec<(i32, i32)>>>);
+        impl UdpChannelInterceptor for ArrivalOrder {
+            fn on_incoming(
+                &mut self,
+                buffer: &[u8],
+                _: Option<std::net::SocketAddr>,
+            ) -> InterceptAction {
+                let is_data = FrameHeader::parse(buffer).is_some_and(|h| {
+                    h.frame_type == HDR_TYPE_DATA && h.stream_id == self.0 && h.frame_length > 32
+                });
+                if is_data {
+                    let read_i32 = |offset: usize| {
+                        i32::from_le_bytes(buffer[offset..offset + 4].try_into().unwrap())
+                    };
+                    if let Ok(mut arrivals) = self.1.lock() {
+                        arrivals.push((read_i32(20), read_i32(8)));
+                    }
+                }
+                InterceptAction::Forward
+            }
+        }
+
+        let aeron_context = driver_context(9)?;
+        let counters = aeron_context
+            .add_network_conditions(NetworkConditions::new(99).reorder(0.2).stream_id(1007))?;
+        let arrivals = Arc::new(Mutex::new(vec![]));
+        aeron_context.add_udp_channel_interceptor(ArrivalOrder(1007, arrivals.clone()))?;
+
+        let (client, stop, driver_handle) = start_media_driver(&aeron_context)?;
+        let (publication, subscription) = udp_pub_sub(&client, 21600, 1007)?;
+
+        let mut received = 0;
+        let mut sent = 0u64;
+        let start = std::time::Instant::now();
+        while received < 200 && start.elapsed() < Duration::from_secs(10) {
+            if sent < 200
+                && publication.offer(
+                    &sent.to_le_bytes(),
+                    Handlers::no_reserved_value_supplier_handler(),
+                ) > 0
+            {
+                sent += 1;
+            }
+            received += subscription.poll_once(|_msg, _header| {}, 10)?;
+        }
+        assert_eq!(200, received);
+        stop.store(true, Ordering::SeqCst);
+        driver_handle.join().unwrap()?;
+
+        let reordered = counters.snapshot().reordered;
+        assert!(reordered > 0);
+        // a frame arriving behind one sent after it was overtaken on the wire
+        let arrivals = arrivals.lock().unwrap().clone();
+        let overtaken = arrivals
+            .iter()
+            .enumerate()
+            .filter(|(i, frame)| arrivals[..*i].iter().any(|earlier| earlier > frame))
+            .count();
+        info!(
+            "{reordered} reordered, {overtaken} of {} frames overtaken",
+            arrivals.len()
+        );
+        assert!(overtaken > 0);
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn static_name_resolver() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::name_resolver::*;
+
+        let mut resolver = StaticNameResolver::parse(
+            "# service registry\n\
+             pricing-svc = 127.0.0.1\n\
+             quotes-svc:40123=127.0.0.1:40124 # moved port\n\
+             \n",
+        )?
+        .without_fallback();
+        assert_eq!(
+            "127.0.0.1:0".parse::<std::net::SocketAddr>()?,
+            resolver.resolve("pricing-svc", "endpoint", false)?
+        );
+        assert_eq!(
+            Some("127.0.0.1:40124".to_string()),
+            resolver.lookup("quotes-svc:40123", "endpoint", false)
+        );
+        assert_eq!(
+            None,
+            resolver.lookup("pricing-svc:40123", "endpoint", false)
+        );
+        assert!(resolver.resolve("::1", "endpoint", false)?.is_ipv6());
+        assert!(resolver.resolve("unknown-svc", "endpoint", false).is_err());
+        assert!(StaticNameResolver::parse("no equals sign").is_err());
+
+        let aeron_context = driver_context(2)?;
+        aeron_context.set_name_resolver(resolver)?;
+
+        let (client, stop, driver_handle) = start_media_driver(&aeron_context)?;
+
+        let port = find_unused_udp_port(21200).expect("no free port");
+        let subscription = client.add_subscription(
+            &format!("aeron:udp?endpoint=127.0.0.1:{port}").into_c_string(),
+            1003,
+            Handlers::no_available_image_handler(),
+            Handlers::no_unavailable_image_handler(),
+            Duration::from_secs(5),
+        )?;
+        let publication = client.add_publication(
+            &format!("aeron:udp?endpoint=pricing-svc:{port}").into_c_string(),
+            1003,
+            Duration::from_secs(5),
+        )?;
+
+        let start = std::time::Instant::now();
+        let mut delivered = 0;
+        while delivered == 0 && start.elapsed() < Duration::from_secs(10) {
+            publication.offer(b"hello", Handlers::no_reserved_value_supplier_handler());
+            delivered += subscription.poll_once(|_msg, _header| {}, 10)?;
+        }
+        assert!(delivered > 0);
+
+        stop.store(true, Ordering::SeqCst);
+        driver_handle.join().unwrap()?;
+        Ok(())
+    }
+
+    #[test]
+    fn flow_control_parse() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::flow_control::*;
+
+        assert_eq!(FlowControl::Max, "max".parse()?);
+        assert_eq!(FlowControl::min(), "min".parse()?);
+        let tagged: FlowControl = "tagged,g:1001/3,t:5s".parse()?;
+        assert_eq!(
+            FlowControl::Tagged(FlowControlOptions {
+                group_tag: Some(1001),
+                group_min_size: Some(3),
+                timeout: Some(Duration::from_secs(5)),
+            }),
+            tagged
+        );
+        assert_eq!("tagged,g:1001/3,t:5s", tagged.to_string());
+        let min: FlowControl = "min,g:/2,t:250ms".parse()?;
+        assert_eq!(min, min.to_string().parse()?);
+        assert!("max,t:5s".parse::<FlowControl>().is_err());
+        assert!("min,t:soon".parse::<FlowControl>().is_err());
+        assert!("bogus".parse::<FlowControl>().is_err());
+
+        let ctx = AeronDriverContext::new()?;
+        ctx.set_multicast_flow_control(&tagged)?;
+        assert_eq!(1001, ctx.get_flow_control_group_tag());
+        assert_eq!(3, ctx.get_flow_control_group_min_size());
+        assert!(ctx
+            .set_multicast_flow_control(&FlowControl::Unicast)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn flow_control_parse_matches_driver() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::flow_control::*;
+
+        for value in [
+            "min",
+            "min,t:250ms",
+            "min,g:/2",
+            "tagged,g:1001",
+            "tagged,g:1001/3,t:5s",
+            "tagged,t:100us,g:7/0",
+        ] {
+            let c_value = std::ffi::CString::new(value)?;
+            let mut parsed: aeron_flow_control_tagged_options_t = unsafe { std::mem::zeroed() };
+            let result = unsafe {
+                aeron_flow_control_parse_tagged_options(value.len(), c_value.as_ptr(), &mut parsed)
+            };
+            assert_eq!(0, result, "driver rejected {value}");
+
+            let options = match value.parse::<FlowControl>()? {
+                FlowControl::Min(options) | FlowControl::Tagged(options) => options,
+                other => panic!("{value} parsed as {other:?}"),
+            };
+            let name = value.split(',').next().unwrap_or_default();
+            assert_eq!(name.len(), parsed.strategy_name_length, "{value}");
+            assert_eq!(
+                parsed
+                    .group_tag
+                    .is_present
+                    .then_some(parsed.group_tag.value),
+                options.group_tag,
+                "{value}"
+            );
+            assert_eq!(
+                parsed
+                    .group_min_size
+                    .is_present
+                    .then_some(parsed.group_min_size.value),
+                options.group_min_size,
+                "{value}"
+            );
+            assert_eq!(
+                parsed
+                    .timeout_ns
+                    .is_present
+                    .then(|| Duration::from_nanos(parsed.timeout_ns.value)),
+                options.timeout,
+                "{value}"
+            );
+        }
+
+        for value in ["min,t:soon", "tagged,g:tag"] {
+            let c_value = std::ffi::CString::new(value)?;
+            let mut parsed: aeron_flow_control_tagged_options_t = unsafe { std::mem::zeroed() };
+            let result = unsafe {
+                aeron_flow_control_parse_tagged_options(value.len(), c_value.as_ptr(), &mut parsed)
+            };
+            assert!(result < 0, "driver accepted {value}");
+            assert!(value.parse::<FlowControl>().is_err(), "accepted {value}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn custom_flow_control_strategy() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::flow_control::*;
+        use std::sync::Mutex;
+
+        let status_message = |receiver_id: i64, term_offset: i32, group_tag: i64| {
+            let mut sm = vec![0u8; 44];
+            sm[0..4].copy_from_slice(&44i32.to_le_bytes());
+            sm[16..20].copy_from_slice(&7i32.to_le_bytes());
+            sm[20..24].copy_from_slice(&term_offset.to_le_bytes());
+            sm[24..28].copy_from_slice(&1024i32.to_le_bytes());
+            sm[28..36].copy_from_slice(&receiver_id.to_le_bytes());
+            sm[36..44].copy_from_slice(&group_tag.to_le_bytes());
+            StatusMessage::parse(&sm, 5, 16).unwrap()
+        };
+        let sm = status_message(1, 64, 9);
+        assert_eq!((2 << 16) + 64, sm.position);
+        assert_eq!(Some(9), sm.group_tag);
+
+        let mut strategy = MinReceiverFlowControl::new(Duration::from_nanos(100))
+            .group_tag(9)
+            .group_min_size(2);
+        assert_eq!(0, strategy.on_status_message(&sm, None, 0, 0));
+        assert!(!strategy.has_required_receivers());
+        let slowest = status_message(2, 32, 9);
+        assert_eq!(
+            slowest.window_limit(),
+            strategy.on_status_message(&slowest, None, 0, 10)
+        );
+        // untagged receivers are ignored
+        strategy.on_status_message(&status_message(3, 0, 1), None, 0, 10);
+        assert_eq!(2, strategy.receiver_count());
+        // receiver 1 times out
+        assert_eq!(0, strategy.on_idle(105, 0, 0, false));
+        assert_eq!(1, strategy.receiver_count());
+
+        let aeron_context = driver_context(3)?;
+        // the factory runs on the driver's conductor thread, so only record what it saw there
+        let created = Arc::new(Mutex::new(vec![]));
+        let created_copy = created.clone();
+        aeron_context.set_unicast_flow_control_strategy(move |setup: &FlowControlSetup| {
+            if let Ok(mut created) = created_copy.lock() {
+                created.push((setup.stream_id, setup.channel.to_string()));
+            }
+            MinReceiverFlowControl::new(Duration::from_secs(5))
+        })?;
+
+        let (client, stop, driver_handle) = start_media_driver(&aeron_context)?;
+
+        let (publication, subscription) = udp_pub_sub(&client, 21300, 1004)?;
+
+        let start = std::time::Instant::now();
+        let mut delivered = 0;
+        while delivered < 100 && start.elapsed() < Duration::from_secs(10) {
+            publication.offer(b"hello", Handlers::no_reserved_value_supplier_handler());
+            delivered += subscription.poll_once(|_msg, _header| {}, 10)?;
+        }
+        assert!(delivered >= 100);
+        let created = created.lock().unwrap().clone();
+        assert_eq!(1, created.len());
+        assert_eq!(1004, created[0].0);
+        assert!(created[0].1.starts_with("aeron:udp"));
+
+        stop.store(true, Ordering::SeqCst);
+        driver_handle.join().unwrap()?;
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn custom_congestion_control_strategy() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::congestion_control::*;
+        use std::sync::atomic::AtomicUsize;
+        use std::sync::Mutex;
+
+        let uri = AeronUriStringBuilder::default();
+        uri.media(Media::Udp)?
+            .endpoint("localhost:40123")?
+            .congestion_control(CongestionControl::Cubic)?;
+        assert!(uri.build(1024)?.contains("cc=cubic"));
+
+        /// halves the window on loss and grows it back by an mtu per rebuild
+        struct Aimd {
+            window: i32,
+            max: i32,
+            mtu: i32,
+            rebuilds: Arc<AtomicUsize>,
+        }
+
+        impl CongestionControlStrategy for Aimd {
+            fn initial_window_length(&self) -> i32 {
+                self.window
+            }
+            fn max_window_length(&self) -> i32 {
+                self.max
+            }
+            fn on_track_rebuild(&mut self, rebuild: &TrackRebuild) -> WindowUpdate {
+                self.rebuilds.fetch_add(1, Ordering::SeqCst);
+                self.window = if rebuild.loss_occurred {
+                    (self.window / 2).max(self.mtu)
+                } else {
+                    (self.window + self.mtu).min(self.max)
+                };
+                WindowUpdate::new(self.window)
+            }
+        }
+
+        let aeron_context = driver_context(4)?;
+        let rebuilds = Arc::new(AtomicUsize::new(0));
+        let rebuilds_copy = rebuilds.clone();
+        // the factory runs on the driver's conductor thread, so only record what it saw there
+        let created = Arc::new(Mutex::new(vec![]));
+        let created_copy = created.clone();
+        aeron_context.set_congestion_control_strategy(move |setup: &CongestionControlSetup| {
+            if let Ok(mut created) = created_copy.lock() {
+                created.push((setup.stream_id, setup.receiver_window_length));
+            }
+            Aimd {
+                window: setup.receiver_window_length,
+                max: setup.receiver_window_length,
+                mtu: setup.mtu_length,
+                rebuilds: rebuilds_copy.clone(),
+            }
+        })?;
+
+        let (client, stop, driver_handle) = start_media_driver(&aeron_context)?;
+
+        let (publication, subscription) = udp_pub_sub(&client, 21400, 1005)?;
+
+        let start = std::time::Instant::now();
+        let mut delivered = 0;
+        while delivered < 100 && start.elapsed() < Duration::from_secs(10) {
+            publication.offer(b"hello", Handlers::no_reserved_value_supplier_handler());
+            delivered += subscription.poll_once(|_msg, _header| {}, 10)?;
+        }
+        assert!(delivered >= 100);
+        assert!(rebuilds.load(Ordering::SeqCst) > 0);
+        let created = created.lock().unwrap().clone();
+        assert_eq!(1, created.len());
+        assert_eq!(1005, created[0].0);
+        assert!(created[0].1 > 0);
+
+        stop.store(true, Ordering::SeqCst);
+        driver_handle.join().unwrap()?;
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn remote_termination() -> Result<(), Box<dyn std::error::Error>> {
+        use std::sync::atomic::AtomicUsize;
+
+        let aeron_context = driver_context(5)?;
+        let validations = Arc::new(AtomicUsize::new(0));
+        let validations_copy = validations.clone();
+        aeron_context.set_termination_validator(move |token| {
+            validations_copy.fetch_add(1, Ordering::SeqCst);
+            token == b"let me in"
+        })?;
+        // a hook set directly on the context still runs alongside the termination hooks
+        struct ContextHook(Arc<AtomicBool>);
+        impl AeronDriverTerminationHookFuncCallback for ContextHook {
+            fn handle_aeron_driver_termination_hook_func(&mut self) -> () {
+                self.0.store(true, Ordering::SeqCst);
+            }
+        }
+        let context_hook_ran = Arc::new(AtomicBool::new(false));
+        let mut context_hook = Handler::leak(ContextHook(context_hook_ran.clone()));
+        aeron_context.set_driver_termination_hook(Some(&context_hook))?;
+        let hook_ran = Arc::new(AtomicBool::new(false));
+        let hook_ran_copy = hook_ran.clone();
+        aeron_context.add_termination_hook(move || hook_ran_copy.store(true, Ordering::SeqCst))?;
+
+        let (stop, driver_handle) = AeronDriver::launch_embedded(aeron_context.clone(), false);
+        let dir = aeron_context.get_dir().to_string();
+
+        AeronContext::request_driver_termination_with_token(&dir, b"wrong")?;
+        let start = std::time::Instant::now();
+        while validations.load(Ordering::SeqCst) == 0 && start.elapsed() < Duration::from_secs(10) {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(1, validations.load(Ordering::SeqCst));
+        assert!(!stop.load(Ordering::SeqCst));
+        assert!(!aeron_context.is_termination_requested());
+
+        AeronContext::request_driver_termination_with_token(&dir, b"let me in")?;
+        let start = std::time::Instant::now();
+        while !driver_handle.is_finished() && start.elapsed() < Duration::from_secs(10) {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(driver_handle.is_finished());
+        assert!(hook_ran.load(Ordering::SeqCst));
+        assert!(context_hook_ran.load(Ordering::SeqCst));
+        assert!(stop.load(Ordering::SeqCst));
+        assert!(aeron_context.is_termination_requested());
+        driver_handle.join().unwrap()?;
+        context_hook.release();
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn duty_cycle_tracking() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::duty_cycle::*;
+
+        let mut tracker = HdrDutyCycleTracker::new(Duration::from_micros(100));
+        let recorder = tracker.recorder();
+        tracker.update(0);
+        for i in 1..=100 {
+            tracker.measure_and_update(i * 10_000);
+        }
+        tracker.measure_and_update(100 * 10_000 + 1_000_000);
+        let snapshot = recorder.snapshot();
+        assert_eq!(101, snapshot.count);
+        assert_eq!(1, snapshot.threshold_exceeded);
+        assert!(
+            snapshot.p50 >= Duration::from_micros(9) && snapshot.p50 <= Duration::from_micros(11)
+        );
+        assert!(snapshot.max >= Duration::from_micros(999));
+        assert_eq!(0, recorder.snapshot().count);
+        tracker.measure_and_update(100 * 10_000 + 2_000_000);
+        recorder.reset();
+        assert_eq!(0, recorder.snapshot().count);
+
+        let aeron_context = driver_context(6)?;
+        let duty_cycles = aeron_context.track_duty_cycles()?;
+        assert_eq!(
+            aeron_context.cycle_threshold(DriverAgent::Conductor),
+            duty_cycles.conductor.threshold()
+        );
+
+        let (stop, driver_handle) = AeronDriver::launch_embedded(aeron_context.clone(), false);
+        let start = std::time::Instant::now();
+        let mut conductor_cycles = 0;
+        while conductor_cycles < 100 && start.elapsed() < Duration::from_secs(10) {
+            std::thread::sleep(Duration::from_millis(10));
+            let conductor = duty_cycles.conductor.snapshot();
+            assert!(conductor.p50 <= conductor.p99 && conductor.p99 <= conductor.max);
+            conductor_cycles += conductor.count;
+        }
+        stop.store(true, Ordering::SeqCst);
+        driver_handle.join().unwrap()?;
+
+        assert!(conductor_cycles >= 100);
+        let sender = duty_cycles.sender.snapshot();
+        let receiver = duty_cycles.receiver.snapshot();
+        info!("duty cycles [sender={sender}] [receiver={receiver}]");
+        assert!(sender.count > 0);
+        assert!(receiver.count > 0);
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn receiver_port_range() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::port_manager::*;
+
+        let range: PortRange = "21500 21509".parse()?;
+        assert_eq!(PortRange::new(21500, 21509)?, range);
+        assert_eq!("21500 21509", range.to_string());
+        assert!("21509 21500".parse::<PortRange>().is_err());
+        assert!("21500".parse::<PortRange>().is_err());
+
+        let aeron_context = driver_context(7)?;
+        assert_eq!(None, aeron_context.sender_port_range());
+        aeron_context.set_sender_port_range(PortRange::new(21510, 21519)?)?;
+        assert_eq!(
+            Some(PortRange::new(21510, 21519)?),
+            aeron_context.sender_port_range()
+        );
+        aeron_context.set_custom_receiver_port_manager(RangePortManager::new(range))?;
+
+        let (client, stop, driver_handle) = start_media_driver(&aeron_context)?;
+
+        let channel = "aeron:udp?endpoint=localhost:0".into_c_string();
+        let subscription = client.add_subscription(
+            &channel,
+            1006,
+            Handlers::no_available_image_handler(),
+            Handlers::no_unavailable_image_handler(),
+            Duration::from_secs(5),
+        )?;
+
+        let start = std::time::Instant::now();
+        while subscription.resolved_port().is_none() && start.elapsed() < Duration::from_secs(10)
+        {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let port = subscription.resolved_port().expect("endpoint not resolved");
+        info!("subscription bound to port {port}");
+        assert!(range.contains(port));
+
+        stop.store(true, Ordering::SeqCst);
+        driver_handle.join().unwrap()?;
+        Ok(())
+    }
+
+    #[test]
+    fn effective_config_diff() -> Result<(), Box<dyn std::error::Error>> {
+        let expected = AeronDriverContext::new()?;
+        let actual = AeronDriverContext::new()?;
+        assert!(expected
+            .effective_config()
+            .diff(&actual.effective_config())
+            .is_empty());
+
+        expected.set_term_buffer_length(128 * 1024)?;
+        expected.set_conductor_idle_strategy(&"noop".into_c_string())?;
+        let config = expected.effective_config();
+        assert_eq!(128 * 1024, config.term_buffer_length);
+        assert_eq!("noop", config.conductor_idle_strategy);
+        assert!(config.to_string().contains("term_buffer_length=131072\n"));
+
+        let differences = config.diff(&actual.effective_config());
+        let names = differences.iter().map(|d| d.name).collect::<Vec<_>>();
+        assert_eq!(vec!["conductor_idle_strategy", "term_buffer_length"], names);
+        assert_eq!("131072", differences[1].expected);
+        assert_eq!(
+            actual.get_term_buffer_length().to_string(),
+            differences[1].actual
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn effective_config_captures_every_getter() -> Result<(), Box<dyn std::error::Error>> {
+        // getters which are not settings, or are captured under another name
+        let ignored = [
+            "inner",
+            "inner_mut",
+            "inner_ref",
+            "agent_on_start_function",
+            "agent_on_start_state",
+            "driver_termination_validator",
+            "driver_termination_validator_state",
+            "driver_termination_hook",
+            "driver_termination_hook_state",
+            "conductor_duty_cycle_tracker",
+            "sender_duty_cycle_tracker",
+            "receiver_duty_cycle_tracker",
+            "name_resolver_time_tracker",
+            "sender_port_manager",
+            "receiver_port_manager",
+            "receiver_group_tag_is_present",
+            "receiver_group_tag_value",
+        ];
+        let captured = AeronDriverContext::new()?
+            .effective_config()
+            .entries()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+
+        let mut in_context_impl = false;
+        let mut missing = vec![];
+        for line in include_str!(concat!(env!("OUT_DIR"), "/aeron.rs")).lines() {
+            if line.starts_with("impl ") {
+                in_context_impl = line == "impl AeronDriverContext {";
+            }
+            let getter = line
+                .trim_start()
+                .strip_prefix("pub fn get_")
+                .and_then(|getter| getter.split_once("(&self)"))
+                .map(|(name, _)| name);
+            if let Some(name) = getter.filter(|_| in_context_impl) {
+                if !captured.contains(&name) && !ignored.contains(&name) {
+                    missing.push(name);
+                }
+            }
+        }
+        assert!(
+            missing.is_empty(),
+            "not in DriverConfigSnapshot: {missing:?}"
+        );
+
+        let context = AeronDriverContext::new()?;
+        assert_eq!(
+            "default",
+            context.effective_config().congestioncontrol_supplier
+        );
+        context.set_congestion_control(CongestionControl::Cubic)?;
+        assert_eq!(
+            "cubic",
+            context.effective_config().congestioncontrol_supplier
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn driver_idle_strategies() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::idle_strategy::*;
+        use std::sync::atomic::AtomicUsize;
+
+        let backoff = DriverIdleStrategy::Backoff {
+            max_spins: 5,
+            max_yields: 10,
+            min_park: Duration::from_micros(1),
+            max_park: Duration::from_micros(100),
+        };
+        assert_eq!(
+            backoff,
+            DriverIdleStrategy::parse("backoff", backoff.init_args().as_deref())?
+        );
+        assert_eq!(DriverIdleStrategy::BusySpin, "spin".parse()?);
+        assert!("spinn".parse::<DriverIdleStrategy>().is_err());
+        assert!("sleep-ns".parse::<DriverIdleStrategy>().is_err());
+        assert!(DriverIdleStrategy::parse("backoff", Some("1,2,100,10")).is_err());
+
+        let idles = Arc::new(AtomicUsize::new(0));
+        let idles_copy = idles.clone();
+        register_idle_strategy("counting", move || {
+            let idles = idles_copy.clone();
+            move |work_count: i32| {
+                if work_count == 0 {
+                    idles.fetch_add(1, Ordering::SeqCst);
+                    std::thread::sleep(Duration::from_micros(100));
+                }
+            }
+        })?;
+        assert!(register_idle_strategy("noop", || |_: i32| {}).is_err());
+
+        let aeron_context = driver_context(8)?;
+        assert!(aeron_context
+            .set_idle_strategy(IdleAgent::Sender, &DriverIdleStrategy::Sleeping { ns: 0 })
+            .is_err());
+        aeron_context.set_idle_strategy(IdleAgent::Conductor, &"counting".parse()?)?;
+        aeron_context.set_idle_strategy(IdleAgent::Sender, &backoff)?;
+        aeron_context.set_idle_strategy(
+            IdleAgent::Receiver,
+            &DriverIdleStrategy::sleeping(Duration::from_micros(100)),
+        )?;
+        assert_eq!("counting", aeron_context.get_conductor_idle_strategy());
+        assert_eq!(
+            Some(backoff.clone()),
+            aeron_context.idle_strategy(IdleAgent::Sender)
+        );
+        assert_eq!(
+            Some(DriverIdleStrategy::Sleeping { ns: 100_000 }),
+            aeron_context.idle_strategy(IdleAgent::Receiver)
+        );
+
+        let (stop, driver_handle) = AeronDriver::launch_embedded(aeron_context.clone(), false);
+        let start = std::time::Instant::now();
+        while idles.load(Ordering::SeqCst) < 10 && start.elapsed() < Duration::from_secs(10) {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        stop.store(true, Ordering::SeqCst);
+        driver_handle.join().unwrap()?;
+        assert!(idles.load(Ordering::SeqCst) >= 10);
+        Ok(())
+    }
+
     #[test]
     pub fn test_debug() -> Result<(), Box<dyn std::error::Error>> {
         let ctx = AeronDriverContext::new()?;
@@ -273,4 +1190,52 @@ mod tests {
 
         Ok(())
     }
+
+    /// A driver context with its own directory, so tests running a driver don't share one.
+    fn driver_context(instance: u64) -> Result<AeronDriverContext, AeronCError> {
+        let aeron_context = AeronDriverContext::new()?;
+        aeron_context.set_dir_delete_on_shutdown(true)?;
+        aeron_context.set_dir_delete_on_start(true)?;
+        aeron_context.set_dir(
+            &format!(
+                "{}{}-{}",
+                aeron_context.get_dir(),
+                Aeron::epoch_clock(),
+                instance
+            )
+            .into_c_string(),
+        )?;
+        Ok(aeron_context)
+    }
+
+    /// Launches an embedded driver for `aeron_context` and connects a client to it.
+    fn start_media_driver(
+        aeron_context: &AeronDriverContext,
+    ) -> Result<(Aeron, Arc<AtomicBool>, JoinHandle<Result<(), AeronCError>>), AeronCError> {
+        let (stop, driver_handle) = AeronDriver::launch_embedded(aeron_context.clone(), false);
+        let ctx = AeronContext::new()?;
+        ctx.set_dir(&aeron_context.get_dir().into_c_string())?;
+        let client = Aeron::new(&ctx)?;
+        client.start()?;
+        Ok((client, stop, driver_handle))
+    }
+
+    /// A publication and subscription on a free udp port from `start_port`.
+    fn udp_pub_sub(
+        client: &Aeron,
+        start_port: u16,
+        stream_id: i32,
+    ) -> Result<(AeronPublication, AeronSubscription), AeronCError> {
+        let port = find_unused_udp_port(start_port).expect("no free port");
+        let channel = format!("aeron:udp?endpoint=localhost:{port}").into_c_string();
+        let subscription = client.add_subscription(
+            &channel,
+            stream_id,
+            Handlers::no_available_image_handler(),
+            Handlers::no_unavailable_image_handler(),
+            Duration::from_secs(5),
+        )?;
+        let publication = client.add_publication(&channel, stream_id, Duration::from_secs(5))?;
+        Ok((publication, subscription))
+    }
 }
@@ -0,0 +1,271 @@
+//! Name resolvers implemented in Rust.
+//!
+//! The driver resolves the host part of `endpoint`, `control` and similar URI params through its
+//! name resolver. Installing a [`NameResolver`] on an [`AeronDriverContext`] replaces the C default
+//! (DNS) resolver, e.g. to look names up in a service registry.
+//!
+//! ```no_run
+//! # use rusteron_media_driver::*;
+//! # use rusteron_media_driver::name_resolver::*;
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let ctx = AeronDriverContext::new()?;
+//! ctx.set_name_resolver(StaticNameResolver::from_file("hosts.conf")?)?;
+//! let (stop, driver) = AeronDriver::launch_embedded(ctx, false);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Names are handled in two steps, mirroring the C resolver interface:
+//! 1. [`NameResolver::lookup`] may rewrite the whole `name:port` string, e.g. `pricing-svc:40123`
+//!    to `10.0.0.12:40123`.
+//! 2. [`NameResolver::resolve`] turns the host part of the result into an address. The port of the
+//!    returned address is replaced by the port in the URI.
+use crate::bindings::*;
+use crate::ffi::{write_socket_addr, ContextRegistry};
+use crate::{AeronCError, AeronDriverContext};
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::os::raw::{c_char, c_int, c_void};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+pub trait NameResolver: Send + 'static {
+    /// Resolves a host name to an address.
+    fn resolve(
+        &mut self,
+        name: &str,
+        uri_param_name: &str,
+        is_re_resolution: bool,
+    ) -> Result<SocketAddr, AeronCError>;
+
+    /// Rewrites a `host:port` name before it is resolved, `None` leaves it unchanged.
+    fn lookup(
+        &mut self,
+        _name: &str,
+        _uri_param_name: &str,
+        _is_re_lookup: bool,
+    ) -> Option<String> {
+        None
+    }
+
+    /// Called on the driver conductor's duty cycle, returns the amount of work done.
+    fn do_work(&mut self, _now_ms: i64) -> i32 {
+        0
+    }
+}
+
+/// Resolves names from a fixed table and falls back to the system resolver for everything else.
+///
+/// Entries map a name to an ip (`pricing-svc=10.0.0.12`), which is used by
+/// [`NameResolver::resolve`], or a `name:port` to another `host:port`
+/// (`pricing-svc:40123=10.0.0.12:40124`), which is used by [`NameResolver::lookup`].
+#[derive(Debug, Clone, Default)]
+pub struct StaticNameResolver {
+    entries: HashMap<String, String>,
+    fallback: bool,
+}
+
+impl StaticNameResolver {
+    pub fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+            fallback: true,
+        }
+    }
+
+    pub fn entry(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.entries.insert(name.into(), value.into());
+        self
+    }
+
+    /// only resolve names in the table, fail for everything else (literal ips still resolve)
+    pub fn without_fallback(mut self) -> Self {
+        self.fallback = false;
+        self
+    }
+
+    /// Parses `name=value` lines, ignoring blank lines and `#` comments.
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let mut resolver = Self::new();
+        for (i, line) in content.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            match line.split_once('=') {
+                Some((name, value)) if !name.trim().is_empty() && !value.trim().is_empty() => {
+                    resolver = resolver.entry(name.trim(), value.trim());
+                }
+                _ => return Err(format!("line {}: expected name=value, got '{line}'", i + 1)),
+            }
+        }
+        Ok(resolver)
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Self::parse(&content).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.entries.get(name).map(|s| s.as_str())
+    }
+}
+
+impl NameResolver for StaticNameResolver {
+    fn resolve(
+        &mut self,
+        name: &str,
+        _uri_param_name: &str,
+        _is_re_resolution: bool,
+    ) -> Result<SocketAddr, AeronCError> {
+        let host = self.get(name).unwrap_or(name);
+        if let Ok(addr) = host.parse::<SocketAddr>() {
+            return Ok(addr);
+        }
+        // ipv6 hosts arrive in brackets from the uri
+        if let Ok(ip) = host.trim_matches(['[', ']']).parse::<IpAddr>() {
+            return Ok(SocketAddr::new(ip, 0));
+        }
+        if self.fallback {
+            if let Some(addr) = (host, 0).to_socket_addrs().ok().and_then(|mut a| a.next()) {
+                return Ok(addr);
+            }
+        }
+        log::warn!("unable to resolve name '{name}'");
+        Err(AeronCError::from_code(-1))
+    }
+
+    fn lookup(&mut self, name: &str, _uri_param_name: &str, _is_re_lookup: bool) -> Option<String> {
+        self.get(name).map(|s| s.to_string())
+    }
+}
+
+struct ResolverState {
+    resolver: Box<dyn NameResolver>,
+    /// the C side only borrows the looked up names, so they are kept for the resolver's lifetime
+    lookups: HashMap<String, CString>,
+}
+
+type SharedResolver = Arc<Mutex<ResolverState>>;
+
+/// Resolvers of each driver context, which the C supplier looks up.
+static RESOLVERS: ContextRegistry<usize, SharedResolver> = ContextRegistry::new();
+
+unsafe fn str_arg<'a>(ptr: *const c_char) -> &'a str {
+    if ptr.is_null() {
+        ""
+    } else {
+        CStr::from_ptr(ptr).to_str().unwrap_or_default()
+    }
+}
+
+unsafe fn resolver_state<'a>(resolver: *mut aeron_name_resolver_t) -> Option<&'a SharedResolver> {
+    resolver
+        .as_ref()
+        .and_then(|r| (r.state as *const SharedResolver).as_ref())
+}
+
+unsafe extern "C" fn name_resolver_supplier(
+    resolver: *mut aeron_name_resolver_t,
+    _args: *const c_char,
+    context: *mut aeron_driver_context_t,
+) -> c_int {
+    let shared = RESOLVERS.get(&(context as usize));
+    let (Some(shared), Some(resolver)) = (shared, resolver.as_mut()) else {
+        log::error!("no name resolver registered for driver context");
+        return -1;
+    };
+    resolver.name = c"rusteron".as_ptr();
+    resolver.lookup_func = Some(name_resolver_lookup);
+    resolver.resolve_func = Some(name_resolver_resolve);
+    resolver.do_work_func = Some(name_resolver_do_work);
+    resolver.close_func = Some(name_resolver_close);
+    resolver.state = Box::into_raw(Box::new(shared)) as *mut c_void;
+    0
+}
+
+unsafe extern "C" fn name_resolver_resolve(
+    resolver: *mut aeron_name_resolver_t,
+    name: *const c_char,
+    uri_param_name: *const c_char,
+    is_re_resolution: bool,
+    address: *mut sockaddr_storage,
+) -> c_int {
+    let Some(Ok(mut state)) = resolver_state(resolver).map(|s| s.lock()) else {
+        return -1;
+    };
+    match state
+        .resolver
+        .resolve(str_arg(name), str_arg(uri_param_name), is_re_resolution)
+    {
+        Ok(addr) => {
+            write_socket_addr(address, addr);
+            0
+        }
+        Err(e) => e.code.min(-1),
+    }
+}
+
+unsafe extern "C" fn name_resolver_lookup(
+    resolver: *mut aeron_name_resolver_t,
+    name: *const c_char,
+    uri_param_name: *const c_char,
+    is_re_lookup: bool,
+    resolved_name: *mut *const c_char,
+) -> c_int {
+    let Some(Ok(mut state)) = resolver_state(resolver).map(|s| s.lock()) else {
+        return -1;
+    };
+    let name_str = str_arg(name);
+    let looked_up = state
+        .resolver
+        .lookup(name_str, str_arg(uri_param_name), is_re_lookup)
+        .and_then(|s| CString::new(s).ok());
+    // same as the default resolver, an unknown name resolves to itself
+    *resolved_name = match looked_up {
+        Some(value) => {
+            let entry = state.lookups.entry(name_str.to_string()).or_default();
+            *entry = value;
+            entry.as_ptr()
+        }
+        None => name,
+    };
+    1
+}
+
+unsafe extern "C" fn name_resolver_do_work(
+    resolver: *mut aeron_name_resolver_t,
+    now_ms: i64,
+) -> c_int {
+    match resolver_state(resolver).map(|s| s.lock()) {
+        Some(Ok(mut state)) => state.resolver.do_work(now_ms),
+        _ => 0,
+    }
+}
+
+unsafe extern "C" fn name_resolver_close(resolver: *mut aeron_name_resolver_t) -> c_int {
+    if let Some(resolver) = resolver.as_mut() {
+        if !resolver.state.is_null() {
+            drop(Box::from_raw(resolver.state as *mut SharedResolver));
+            resolver.state = std::ptr::null_mut();
+        }
+    }
+    0
+}
+
+impl AeronDriverContext {
+    /// Replaces the driver's name resolver with `resolver`. Must be called before the driver is
+    /// started.
+    pub fn set_name_resolver<R: NameResolver>(&self, resolver: R) -> Result<(), AeronCError> {
+        let key = self.get_inner() as usize;
+        let state = ResolverState {
+            resolver: Box::new(resolver),
+            lookups: HashMap::new(),
+        };
+        RESOLVERS.insert(self, key, Arc::new(Mutex::new(state)))?;
+        self.set_name_resolver_supplier(Some(name_resolver_supplier))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,269 @@
+//! Deterministic network impairment for integration tests.
+//!
+//! [`NetworkConditions`] is a [`UdpChannelInterceptor`] which applies seeded random loss, fixed and
+//! jittered delay, duplication and reordering to the packets a driver sends. The same seed produces
+//! the same decisions, so NAK/retransmit behaviour can be reproduced on loopback in CI.
+//!
+//! ```no_run
+//! # use rusteron_media_driver::*;
+//! # use rusteron_media_driver::network_conditions::*;
+//! # use std::time::Duration;
+//! # fn run() -> Result<(), AeronCError> {
+//! let ctx = AeronDriverContext::new()?;
+//! let counters = ctx.add_network_conditions(
+//!     NetworkConditions::new(42)
+//!         .loss(0.05)
+//!         .delay(Duration::from_millis(1), Duration::from_micros(500))
+//!         .reorder(0.01),
+//! )?;
+//! let (stop, driver) = AeronDriver::launch_embedded(ctx, false);
+//! // ...
+//! println!("{:?}", counters.snapshot());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Once the driver starts, what was dropped, delayed, duplicated and reordered is also reported
+//! through driver counters of type [`NETWORK_CONDITIONS_COUNTER_TYPE_ID`], keyed by the seed, so
+//! it can be read with `AeronCountersReader` or `aeron-stat` from another process.
+//!
+//! Held back packets are released the next time the driver sends on or receives from the same
+//! transport, so delays are only as precise as the rate of traffic (including heartbeats and
+//! status messages). Reordered packets are also held until a later packet has been sent ahead of
+//! them, and packets still held when their transport is closed are discarded.
+use crate::ffi::allocate_counter;
+use crate::interceptor::{FrameHeader, InterceptAction, UdpChannelInterceptor, XorShiftRng};
+use crate::{AeronCError, AeronCountersManager, AeronDriverContext};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicI64, AtomicPtr, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Type id of the driver counters allocated by [`NetworkConditions`], keyed by the seed.
+pub const NETWORK_CONDITIONS_COUNTER_TYPE_ID: i32 = 1000;
+
+/// Count readable in process, mirrored to a driver counter once the driver has started.
+#[derive(Debug, Default)]
+struct Count {
+    value: AtomicU64,
+    driver_counter: AtomicPtr<i64>,
+}
+
+impl Count {
+    #[inline]
+    fn incr(&self) {
+        self.value.fetch_add(1, Ordering::Relaxed);
+        let driver_counter = self.driver_counter.load(Ordering::Acquire);
+        if !driver_counter.is_null() {
+            // the counter lives in the CnC file for as long as the driver runs
+            unsafe { AtomicI64::from_ptr(driver_counter) }.fetch_add(1, Ordering::Release);
+        }
+    }
+
+    fn load(&self) -> u64 {
+        self.value.load(Ordering::Relaxed)
+    }
+
+    fn allocate(&self, counters: &AeronCountersManager, seed: u64, name: &str) {
+        let label = format!("network conditions {name}: seed={seed}");
+        if let Some(driver_counter) = unsafe {
+            allocate_counter(
+                counters,
+                NETWORK_CONDITIONS_COUNTER_TYPE_ID,
+                &seed.to_le_bytes(),
+                &label,
+            )
+        } {
+            unsafe { AtomicI64::from_ptr(driver_counter) }
+                .store(self.load() as i64, Ordering::Release);
+            self.driver_counter.store(driver_counter, Ordering::Release);
+        }
+    }
+}
+
+/// Counts of what a [`NetworkConditions`] interceptor did to the packets it matched.
+///
+/// Dropped, delayed, duplicated and reordered packets are also counted by driver counters of type
+/// [`NETWORK_CONDITIONS_COUNTER_TYPE_ID`] labelled `network conditions <kind>: seed=<seed>`.
+#[derive(Debug, Default)]
+pub struct NetworkConditionsCounters {
+    forwarded: Count,
+    dropped: Count,
+    delayed: Count,
+    duplicated: Count,
+    reordered: Count,
+}
+
+/// Point in time copy of [`NetworkConditionsCounters`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct NetworkConditionsSnapshot {
+    pub forwarded: u64,
+    pub dropped: u64,
+    pub delayed: u64,
+    pub duplicated: u64,
+    pub reordered: u64,
+}
+
+impl NetworkConditionsCounters {
+    pub fn snapshot(&self) -> NetworkConditionsSnapshot {
+        NetworkConditionsSnapshot {
+            forwarded: self.forwarded.load(),
+            dropped: self.dropped.load(),
+            delayed: self.delayed.load(),
+            duplicated: self.duplicated.load(),
+            reordered: self.reordered.load(),
+        }
+    }
+
+    fn allocate(&self, counters: &AeronCountersManager, seed: u64) {
+        self.dropped.allocate(counters, seed, "dropped");
+        self.delayed.allocate(counters, seed, "delayed");
+        self.duplicated.allocate(counters, seed, "duplicated");
+        self.reordered.allocate(counters, seed, "reordered");
+    }
+}
+
+/// Seeded network impairment applied to outgoing packets, optionally limited to a destination
+/// endpoint and/or stream. Add one per channel to give channels different conditions.
+#[derive(Debug)]
+pub struct NetworkConditions {
+    seed: u64,
+    rng: XorShiftRng,
+    loss_rate: f64,
+    delay: Duration,
+    jitter: Duration,
+    duplicate_rate: f64,
+    reorder_rate: f64,
+    endpoint: Option<SocketAddr>,
+    stream_id: Option<i32>,
+    counters: Arc<NetworkConditionsCounters>,
+}
+
+impl NetworkConditions {
+    /// Perfect network, the seed drives every random decision made later.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: XorShiftRng::new(seed),
+            loss_rate: 0.0,
+            delay: Duration::ZERO,
+            jitter: Duration::ZERO,
+            duplicate_rate: 0.0,
+            reorder_rate: 0.0,
+            endpoint: None,
+            stream_id: None,
+            counters: Arc::new(NetworkConditionsCounters::default()),
+        }
+    }
+
+    /// probability, between 0 and 1, that a packet is dropped
+    pub fn loss(mut self, rate: f64) -> Self {
+        self.loss_rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// every packet is delayed by `delay` plus a uniformly random amount up to `jitter`
+    pub fn delay(mut self, delay: Duration, jitter: Duration) -> Self {
+        self.delay = delay;
+        self.jitter = jitter;
+        self
+    }
+
+    /// probability, between 0 and 1, that a packet is sent twice
+    pub fn duplicate(mut self, rate: f64) -> Self {
+        self.duplicate_rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// probability, between 0 and 1, that a packet is held back and sent after the packets
+    /// which follow it
+    pub fn reorder(mut self, rate: f64) -> Self {
+        self.reorder_rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// only impair packets sent to `endpoint`
+    pub fn endpoint(mut self, endpoint: SocketAddr) -> Self {
+        self.endpoint = Some(endpoint);
+        self
+    }
+
+    /// only impair frames of `stream_id`
+    pub fn stream_id(mut self, stream_id: i32) -> Self {
+        self.stream_id = Some(stream_id);
+        self
+    }
+
+    /// counters which remain readable after the conditions are installed on a driver
+    pub fn counters(&self) -> Arc<NetworkConditionsCounters> {
+        self.counters.clone()
+    }
+
+    fn matches(&self, buffer: &[u8], addr: Option<SocketAddr>) -> bool {
+        self.endpoint.map_or(true, |e| Some(e) == addr)
+            && self.stream_id.map_or(true, |s| {
+                FrameHeader::parse(buffer).map_or(false, |h| h.stream_id == s)
+            })
+    }
+
+    fn next_delay(&mut self) -> Duration {
+        if self.jitter.is_zero() {
+            self.delay
+        } else {
+            self.delay + self.jitter.mul_f64(self.rng.next_f64())
+        }
+    }
+
+    /// Decides what happens to a packet sent to `addr`.
+    pub fn decide(&mut self, buffer: &[u8], addr: Option<SocketAddr>) -> InterceptAction {
+        if !self.matches(buffer, addr) {
+            return InterceptAction::Forward;
+        }
+        // always draw the same number of values so one setting does not shift the others
+        let lose = self.rng.next_f64() < self.loss_rate;
+        let duplicate = self.rng.next_f64() < self.duplicate_rate;
+        let reorder = self.rng.next_f64() < self.reorder_rate;
+        let delay = self.next_delay();
+        let counters = &self.counters;
+
+        if lose {
+            counters.dropped.incr();
+            InterceptAction::Drop
+        } else if duplicate {
+            counters.duplicated.incr();
+            InterceptAction::Duplicate(delay)
+        } else if reorder {
+            counters.reordered.incr();
+            InterceptAction::Reorder(delay)
+        } else if !delay.is_zero() {
+            counters.delayed.incr();
+            InterceptAction::Delay(delay)
+        } else {
+            counters.forwarded.incr();
+            InterceptAction::Forward
+        }
+    }
+}
+
+impl UdpChannelInterceptor for NetworkConditions {
+    fn on_start(&mut self, counters: &AeronCountersManager) {
+        self.counters.allocate(counters, self.seed);
+    }
+
+    fn on_outgoing(&mut self, buffer: &mut [u8], addr: Option<SocketAddr>) -> InterceptAction {
+        self.decide(buffer, addr)
+    }
+}
+
+impl AeronDriverContext {
+    /// Applies `conditions` to the packets sent by a driver using this context and returns its
+    /// counters. Must be called before the driver is started.
+    pub fn add_network_conditions(
+        &self,
+        conditions: NetworkConditions,
+    ) -> Result<Arc<NetworkConditionsCounters>, AeronCError> {
+        let counters = conditions.counters();
+        self.add_udp_channel_interceptor(conditions)?;
+        Ok(counters)
+    }
+}
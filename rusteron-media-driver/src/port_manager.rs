@@ -0,0 +1,305 @@
+//! Port allocation for wildcard (`:0`) endpoints.
+//!
+//! When a channel binds to port `0` the driver asks a port manager which port to use. By default the
+//! OS picks an ephemeral port, [`PortRange`] confines the choice to a range of ports (e.g. the ones a
+//! firewall allows), and [`PortManager`] is implemented for any other allocation policy. The port
+//! picked for a subscription is available from `AeronSubscription::resolved_port` once the driver
+//! has bound it.
+//!
+//! ```no_run
+//! # use rusteron_media_driver::*;
+//! # use rusteron_media_driver::port_manager::*;
+//! # fn run() -> Result<(), AeronCError> {
+//! let ctx = AeronDriverContext::new()?;
+//! ctx.set_receiver_port_range("20700 20799".parse().expect("valid range"))?;
+//! ctx.set_sender_port_range(PortRange::new(20800, 20899).expect("valid range"))?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::bindings::*;
+use crate::ffi::{socket_addr, udp_channel_uri, write_socket_addr};
+use crate::{AeronCError, AeronDriverContext};
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{SocketAddr, UdpSocket};
+use std::os::raw::{c_int, c_void};
+use std::str::FromStr;
+
+/// Inclusive range of ports to allocate wildcard endpoints from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PortRange {
+    low: u16,
+    high: u16,
+}
+
+impl PortRange {
+    pub fn new(low: u16, high: u16) -> Result<Self, String> {
+        if low == 0 || low > high {
+            return Err(format!("invalid port range {low} {high}"));
+        }
+        Ok(Self { low, high })
+    }
+
+    pub fn low(&self) -> u16 {
+        self.low
+    }
+
+    pub fn high(&self) -> u16 {
+        self.high
+    }
+
+    pub fn len(&self) -> usize {
+        (self.high - self.low) as usize + 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    pub fn contains(&self, port: u16) -> bool {
+        (self.low..=self.high).contains(&port)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = u16> {
+        self.low..=self.high
+    }
+}
+
+/// Parses the `aeron.*.wildcard.port.range` format, `"low high"`.
+impl FromStr for PortRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut ports = s.split_whitespace().map(|p| {
+            p.parse::<u16>()
+                .map_err(|e| format!("invalid port {p:?} in range {s:?}: {e}"))
+        });
+        match (ports.next(), ports.next(), ports.next()) {
+            (Some(low), Some(high), None) => PortRange::new(low?, high?),
+            _ => Err(format!("expected \"low high\" port range, got {s:?}")),
+        }
+    }
+}
+
+impl fmt::Display for PortRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.low, self.high)
+    }
+}
+
+/// Allocates ports for endpoints, called on the driver's conductor thread.
+pub trait PortManager: Send + 'static {
+    /// Address to bind `channel` to. `bind_addr` is the address from the channel, with port `0` for
+    /// a wildcard.
+    fn get_managed_port(
+        &mut self,
+        channel: &str,
+        bind_addr: SocketAddr,
+    ) -> Result<SocketAddr, AeronCError>;
+
+    /// The endpoint bound to `bind_addr` was closed.
+    fn free_managed_port(&mut self, _bind_addr: SocketAddr) {}
+}
+
+/// Allocates wildcard ports round robin from a [`PortRange`], skipping ports which are in use by
+/// this driver or can't be bound.
+#[derive(Debug, Clone)]
+pub struct RangePortManager {
+    range: PortRange,
+    next_port: u16,
+    in_use: HashMap<u16, usize>,
+}
+
+impl RangePortManager {
+    pub fn new(range: PortRange) -> Self {
+        Self {
+            range,
+            next_port: range.low,
+            in_use: HashMap::new(),
+        }
+    }
+
+    pub fn range(&self) -> PortRange {
+        self.range
+    }
+
+    /// Whether `port` is currently allocated to an endpoint.
+    pub fn is_in_use(&self, port: u16) -> bool {
+        self.in_use.contains_key(&port)
+    }
+
+    fn is_bindable(addr: SocketAddr) -> bool {
+        UdpSocket::bind(addr).is_ok()
+    }
+}
+
+impl PortManager for RangePortManager {
+    fn get_managed_port(
+        &mut self,
+        channel: &str,
+        bind_addr: SocketAddr,
+    ) -> Result<SocketAddr, AeronCError> {
+        if bind_addr.port() != 0 {
+            if self.range.contains(bind_addr.port()) {
+                *self.in_use.entry(bind_addr.port()).or_default() += 1;
+            }
+            return Ok(bind_addr);
+        }
+
+        let start = self.next_port;
+        let ports = (start..=self.range.high).chain(self.range.low..start);
+        for port in ports {
+            let mut addr = bind_addr;
+            addr.set_port(port);
+            if !self.is_in_use(port) && Self::is_bindable(addr) {
+                self.in_use.insert(port, 1);
+                self.next_port = if port == self.range.high {
+                    self.range.low
+                } else {
+                    port + 1
+                };
+                return Ok(addr);
+            }
+        }
+        log::error!("no free port in range {} for {channel}", self.range);
+        Err(AeronCError::from_code(-1))
+    }
+
+    fn free_managed_port(&mut self, bind_addr: SocketAddr) {
+        let port = bind_addr.port();
+        if let Some(count) = self.in_use.get_mut(&port) {
+            *count -= 1;
+            if *count == 0 {
+                self.in_use.remove(&port);
+            }
+        }
+    }
+}
+
+/// Owns the port manager handed to the driver context, freed when the context is dropped.
+struct OwnedPortManager(*mut aeron_port_manager_t);
+
+impl Drop for OwnedPortManager {
+    fn drop(&mut self) {
+        unsafe {
+            let port_manager = Box::from_raw(self.0);
+            drop(Box::from_raw(
+                port_manager.state as *mut Box<dyn PortManager>,
+            ));
+        }
+    }
+}
+
+unsafe extern "C" fn port_manager_get_managed_port(
+    state: *mut c_void,
+    bind_addr_out: *mut sockaddr_storage,
+    udp_channel: *mut aeron_udp_channel_t,
+    bind_addr: *mut sockaddr_storage,
+) -> c_int {
+    let manager = &mut *(state as *mut Box<dyn PortManager>);
+    let Some(addr) = socket_addr(bind_addr) else {
+        log::error!("unsupported bind address family for port manager");
+        return -1;
+    };
+    match manager.get_managed_port(&udp_channel_uri(udp_channel), addr) {
+        Ok(addr) => {
+            write_socket_addr(bind_addr_out, addr);
+            0
+        }
+        Err(e) => e.code.min(-1),
+    }
+}
+
+unsafe extern "C" fn port_manager_free_managed_port(
+    state: *mut c_void,
+    bind_addr: *mut sockaddr_storage,
+) {
+    let manager = &mut *(state as *mut Box<dyn PortManager>);
+    if let Some(addr) = socket_addr(bind_addr) {
+        manager.free_managed_port(addr);
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Side {
+    Sender,
+    Receiver,
+}
+
+impl AeronDriverContext {
+    /// Binds sender wildcard endpoints (e.g. the control address of a multi destination
+    /// publication) to ports in `range` rather than an OS ephemeral port.
+    pub fn set_sender_port_range(&self, range: PortRange) -> Result<(), AeronCError> {
+        self.set_sender_wildcard_port_range(range.low, range.high)?;
+        Ok(())
+    }
+
+    /// Binds receiver wildcard endpoints (e.g. `aeron:udp?endpoint=0.0.0.0:0`) to ports in
+    /// `range` rather than an OS ephemeral port.
+    pub fn set_receiver_port_range(&self, range: PortRange) -> Result<(), AeronCError> {
+        self.set_receiver_wildcard_port_range(range.low, range.high)?;
+        Ok(())
+    }
+
+    /// Range sender wildcard ports are allocated from, `None` if the OS picks them.
+    pub fn sender_port_range(&self) -> Option<PortRange> {
+        let (mut low, mut high) = (0, 0);
+        self.get_sender_wildcard_port_range(&mut low, &mut high)
+            .ok()?;
+        PortRange::new(low, high).ok()
+    }
+
+    /// Range receiver wildcard ports are allocated from, `None` if the OS picks them.
+    pub fn receiver_port_range(&self) -> Option<PortRange> {
+        let (mut low, mut high) = (0, 0);
+        self.get_receiver_wildcard_port_range(&mut low, &mut high)
+            .ok()?;
+        PortRange::new(low, high).ok()
+    }
+
+    /// Uses `manager` to allocate sender ports, replacing the wildcard port range. Must be called
+    /// before the driver is started.
+    pub fn set_custom_sender_port_manager<M: PortManager>(
+        &self,
+        manager: M,
+    ) -> Result<(), AeronCError> {
+        self.set_custom_port_manager(Side::Sender, Box::new(manager))
+    }
+
+    /// Uses `manager` to allocate receiver ports, replacing the wildcard port range. Must be
+    /// called before the driver is started.
+    pub fn set_custom_receiver_port_manager<M: PortManager>(
+        &self,
+        manager: M,
+    ) -> Result<(), AeronCError> {
+        self.set_custom_port_manager(Side::Receiver, Box::new(manager))
+    }
+
+    fn set_custom_port_manager(
+        &self,
+        side: Side,
+        manager: Box<dyn PortManager>,
+    ) -> Result<(), AeronCError> {
+        let owned = OwnedPortManager(Box::into_raw(Box::new(aeron_port_manager_t {
+            get_managed_port: Some(port_manager_get_managed_port),
+            free_managed_port: Some(port_manager_free_managed_port),
+            state: Box::into_raw(Box::new(manager)) as *mut c_void,
+        })));
+        let result = unsafe {
+            match side {
+                Side::Sender => {
+                    aeron_driver_context_set_sender_port_manager(self.get_inner(), owned.0)
+                }
+                Side::Receiver => {
+                    aeron_driver_context_set_receiver_port_manager(self.get_inner(), owned.0)
+                }
+            }
+        };
+        if result < 0 {
+            return Err(AeronCError::from_code(result));
+        }
+        self.inner.add_dependency(owned);
+        Ok(())
+    }
+}
@@ -0,0 +1,175 @@
+//! Remote driver termination.
+//!
+//! A client (e.g. ops tooling) asks a driver to terminate with
+//! `AeronContext::request_driver_termination_with_token`. The driver's termination validator
+//! decides whether the request is authorised, by default every request is denied. Once a request
+//! is accepted the termination hooks run and drivers started with
+//! [`AeronDriver::launch_embedded`](crate::AeronDriver::launch_embedded) stop.
+//!
+//! ```no_run
+//! # use rusteron_media_driver::*;
+//! # fn run() -> Result<(), AeronCError> {
+//! let ctx = AeronDriverContext::new()?;
+//! ctx.set_termination_token(b"secret".to_vec())?;
+//! ctx.add_termination_hook(|| println!("driver terminated remotely"))?;
+//! let (stop, driver) = AeronDriver::launch_embedded(ctx.clone(), false);
+//!
+//! // from another process
+//! AeronContext::request_driver_termination_with_token(ctx.get_dir(), b"secret")?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::bindings::aeron_driver_termination_hook_func_t;
+use crate::{
+    AeronCError, AeronDriverContext, AeronDriverTerminationHookFuncCallback,
+    AeronDriverTerminationValidatorFuncCallback, Handler, OwnedHandler,
+};
+use std::os::raw::c_void;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+struct TerminationValidator<F>(F);
+
+impl<F: FnMut(&[u8]) -> bool> AeronDriverTerminationValidatorFuncCallback
+    for TerminationValidator<F>
+{
+    fn handle_aeron_driver_termination_validator_func(&mut self, buffer: &mut [u8]) -> bool {
+        let accepted = (self.0)(buffer);
+        if !accepted {
+            log::warn!("driver termination request denied");
+        }
+        accepted
+    }
+}
+
+/// Compares tokens in time independent of where they differ.
+fn tokens_match(expected: &[u8], actual: &[u8]) -> bool {
+    expected.len() == actual.len()
+        && expected
+            .iter()
+            .zip(actual)
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Hooks and embedded driver stop flags run when the driver terminates. Shared through the
+/// context's dependencies so hooks added before and after launching the driver are all run.
+#[derive(Default)]
+pub(crate) struct TerminationHooks {
+    hooks: Mutex<Vec<Box<dyn FnMut() + Send>>>,
+    stop_flags: Mutex<Vec<Arc<AtomicBool>>>,
+    terminated: AtomicBool,
+}
+
+impl TerminationHooks {
+    fn run(&self) {
+        self.terminated.store(true, Ordering::SeqCst);
+        // taken out so a hook adding another hook doesn't deadlock
+        let hooks = self
+            .hooks
+            .lock()
+            .map(|mut hooks| std::mem::take(&mut *hooks))
+            .unwrap_or_default();
+        for mut hook in hooks {
+            hook();
+        }
+        if let Ok(flags) = self.stop_flags.lock() {
+            for stop in flags.iter() {
+                stop.store(true, Ordering::SeqCst);
+            }
+        }
+    }
+}
+
+struct TerminationHookHandler {
+    hooks: Arc<TerminationHooks>,
+    /// hook set on the context before ours, which still runs first
+    previous: aeron_driver_termination_hook_func_t,
+    previous_state: *mut c_void,
+}
+
+impl AeronDriverTerminationHookFuncCallback for TerminationHookHandler {
+    fn handle_aeron_driver_termination_hook_func(&mut self) -> () {
+        log::info!("driver termination requested");
+        if let Some(previous) = self.previous {
+            unsafe { previous(self.previous_state) };
+        }
+        self.hooks.run();
+    }
+}
+
+impl AeronDriverContext {
+    /// Decides whether a termination request is authorised given the token it was sent with,
+    /// replacing any previous validator. The validator is released when the context is dropped.
+    pub fn set_termination_validator<F>(&self, validator: F) -> Result<(), AeronCError>
+    where
+        F: FnMut(&[u8]) -> bool + Send + 'static,
+    {
+        let mut handler = Handler::leak(TerminationValidator(validator));
+        if let Err(e) = self.set_driver_termination_validator(Some(&handler)) {
+            handler.release();
+            return Err(e);
+        }
+        self.inner.add_dependency(OwnedHandler(handler));
+        Ok(())
+    }
+
+    /// Only authorises termination requests sent with `token`.
+    pub fn set_termination_token(&self, token: impl Into<Vec<u8>>) -> Result<(), AeronCError> {
+        let token = token.into();
+        self.set_termination_validator(move |actual| tokens_match(&token, actual))
+    }
+
+    /// Runs `hook` on the driver conductor thread once an authorised termination request is
+    /// received, after any termination hook already set on the context.
+    pub fn add_termination_hook<F>(&self, hook: F) -> Result<(), AeronCError>
+    where
+        F: FnMut() + Send + 'static,
+    {
+        self.termination_hooks()?
+            .hooks
+            .lock()
+            .map_err(|_| AeronCError::from_code(-1))?
+            .push(Box::new(hook));
+        Ok(())
+    }
+
+    /// Whether an authorised termination request has been received.
+    pub fn is_termination_requested(&self) -> bool {
+        self.inner
+            .get_dependency::<Arc<TerminationHooks>>()
+            .is_some_and(|hooks| hooks.terminated.load(Ordering::SeqCst))
+    }
+
+    /// Sets `stop` once the driver is terminated, used by the embedded driver's duty cycle loop.
+    pub(crate) fn add_termination_stop_flag(
+        &self,
+        stop: Arc<AtomicBool>,
+    ) -> Result<(), AeronCError> {
+        self.termination_hooks()?
+            .stop_flags
+            .lock()
+            .map_err(|_| AeronCError::from_code(-1))?
+            .push(stop);
+        Ok(())
+    }
+
+    fn termination_hooks(&self) -> Result<Arc<TerminationHooks>, AeronCError> {
+        if let Some(hooks) = self.inner.get_dependency::<Arc<TerminationHooks>>() {
+            return Ok(hooks);
+        }
+        let hooks = Arc::new(TerminationHooks::default());
+        let mut handler = Handler::leak(TerminationHookHandler {
+            hooks: hooks.clone(),
+            previous: self.get_driver_termination_hook(),
+            previous_state: self.get_driver_termination_hook_state(),
+        });
+        if let Err(e) = self.set_driver_termination_hook(Some(&handler)) {
+            handler.release();
+            return Err(e);
+        }
+        self.inner.add_dependency(OwnedHandler(handler));
+        self.inner.add_dependency(hooks.clone());
+        Ok(hooks)
+    }
+}